@@ -12,6 +12,8 @@ pub trait ClientCapabilitiesExt {
     fn has_pull_configuration_support(&self) -> bool;
 
     fn has_push_configuration_support(&self) -> bool;
+
+    fn has_file_watching_support(&self) -> bool;
 }
 
 impl ClientCapabilitiesExt for ClientCapabilities {
@@ -55,14 +57,23 @@ impl ClientCapabilitiesExt for ClientCapabilities {
             .and_then(|cap| cap.dynamic_registration)
             == Some(true)
     }
+
+    fn has_file_watching_support(&self) -> bool {
+        self.workspace
+            .as_ref()
+            .and_then(|cap| cap.did_change_watched_files)
+            .and_then(|cap| cap.dynamic_registration)
+            == Some(true)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use lsp_types::{
-        DocumentSymbolClientCapabilities, GotoCapability, HoverClientCapabilities,
-        TextDocumentClientCapabilities, WindowClientCapabilities,
+        DocumentSymbolClientCapabilities, DynamicRegistrationClientCapabilities, GotoCapability,
+        HoverClientCapabilities, TextDocumentClientCapabilities, WindowClientCapabilities,
+        WorkspaceClientCapabilities,
     };
 
     #[test]
@@ -145,4 +156,24 @@ mod tests {
         let capabilities = ClientCapabilities::default();
         assert!(!capabilities.has_hover_markdown_support());
     }
+
+    #[test]
+    fn test_has_file_watching_support_true() {
+        let capabilities = ClientCapabilities {
+            workspace: Some(WorkspaceClientCapabilities {
+                did_change_watched_files: Some(DynamicRegistrationClientCapabilities {
+                    dynamic_registration: Some(true),
+                }),
+                ..WorkspaceClientCapabilities::default()
+            }),
+            ..ClientCapabilities::default()
+        };
+        assert!(capabilities.has_file_watching_support());
+    }
+
+    #[test]
+    fn test_has_file_watching_support_false() {
+        let capabilities = ClientCapabilities::default();
+        assert!(!capabilities.has_file_watching_support());
+    }
 }
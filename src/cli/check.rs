@@ -0,0 +1,352 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use anyhow::{anyhow, Result};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    create_workspace_full, diagnostics::DiagnosticsManager, DocumentLanguage, Options,
+    ServerContext, Uri, Workspace,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputFormat {
+    Text,
+    Json,
+    Sarif,
+}
+
+impl FromStr for OutputFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "sarif" => Ok(Self::Sarif),
+            _ => Err(anyhow!("unknown output format: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileDiagnostics {
+    uri: Uri,
+    diagnostics: Vec<Diagnostic>,
+}
+
+/// Analyzes all documents below `root` with the given server `options`
+/// and writes the diagnostics to `output`.
+/// Returns `true` if at least one error has been found.
+pub fn check(
+    root: &Path,
+    format: OutputFormat,
+    chktex: bool,
+    options: Options,
+    output: &mut dyn io::Write,
+) -> Result<bool> {
+    let root = fs::canonicalize(root)?;
+    let context = Arc::new(ServerContext::new(root.clone()));
+    *context.options.write().unwrap() = Arc::new(options);
+    let workspace = create_workspace_full(Arc::clone(&context))?;
+
+    let mut paths = Vec::new();
+    find_documents(&root, &mut paths)?;
+    for path in paths {
+        workspace.load(path)?;
+    }
+
+    let options = { context.options.read().unwrap().clone() };
+    let mut manager = DiagnosticsManager::default();
+    let mut documents = workspace.documents();
+    documents.sort_by(|a, b| a.uri.as_str().cmp(b.uri.as_str()));
+    for document in &documents {
+        manager.update_static(&workspace, Arc::clone(&document.uri), &options);
+        if chktex && document.language() == DocumentLanguage::Latex {
+            manager.update_chktex(&workspace, Arc::clone(&document.uri), &options);
+        }
+    }
+
+    let mut files = Vec::new();
+    for document in &documents {
+        let mut diagnostics = manager.publish(Arc::clone(&document.uri));
+        if !diagnostics.is_empty() {
+            diagnostics.sort_by_key(|diagnostic| diagnostic.range.start);
+            files.push(FileDiagnostics {
+                uri: document.uri.as_ref().clone(),
+                diagnostics,
+            });
+        }
+    }
+
+    match format {
+        OutputFormat::Text => write_text(&root, &files, output)?,
+        OutputFormat::Json => serde_json::to_writer_pretty(&mut *output, &files)?,
+        OutputFormat::Sarif => serde_json::to_writer_pretty(&mut *output, &create_sarif(&files))?,
+    };
+
+    let has_errors = files
+        .iter()
+        .flat_map(|file| &file.diagnostics)
+        .any(|diagnostic| diagnostic.severity == Some(DiagnosticSeverity::ERROR));
+
+    Ok(has_errors)
+}
+
+fn find_documents(directory: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        let is_hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .map_or(false, |name| name.starts_with('.'));
+
+        if is_hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            find_documents(&path, paths)?;
+        } else if matches!(
+            DocumentLanguage::by_path(&path),
+            Some(DocumentLanguage::Latex) | Some(DocumentLanguage::Bibtex)
+        ) {
+            paths.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+fn write_text(root: &Path, files: &[FileDiagnostics], output: &mut dyn io::Write) -> Result<()> {
+    for file in files {
+        let path = file
+            .uri
+            .to_file_path()
+            .ok()
+            .map(|path| {
+                path.strip_prefix(root)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(path)
+            })
+            .map_or_else(|| file.uri.to_string(), |path| path.display().to_string());
+
+        for diagnostic in &file.diagnostics {
+            let code = match &diagnostic.code {
+                Some(NumberOrString::Number(code)) => format!(" [{}]", code),
+                Some(NumberOrString::String(code)) => format!(" [{}]", code),
+                None => String::new(),
+            };
+
+            writeln!(
+                output,
+                "{}:{}:{}: {}: {}{}",
+                path,
+                diagnostic.range.start.line + 1,
+                diagnostic.range.start.character + 1,
+                severity_name(diagnostic.severity),
+                diagnostic.message,
+                code
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn create_sarif(files: &[FileDiagnostics]) -> serde_json::Value {
+    let results: Vec<_> = files
+        .iter()
+        .flat_map(|file| {
+            file.diagnostics.iter().map(move |diagnostic| {
+                let rule_id = match &diagnostic.code {
+                    Some(NumberOrString::Number(code)) => code.to_string(),
+                    Some(NumberOrString::String(code)) => code.clone(),
+                    None => diagnostic.source.clone().unwrap_or_default(),
+                };
+
+                let level = match diagnostic.severity {
+                    Some(DiagnosticSeverity::ERROR) => "error",
+                    Some(DiagnosticSeverity::WARNING) => "warning",
+                    _ => "note",
+                };
+
+                let range = diagnostic.range;
+                json!({
+                    "ruleId": rule_id,
+                    "level": level,
+                    "message": { "text": diagnostic.message },
+                    "locations": [{
+                        "physicalLocation": {
+                            "artifactLocation": { "uri": file.uri.as_str() },
+                            "region": {
+                                "startLine": range.start.line + 1,
+                                "startColumn": range.start.character + 1,
+                                "endLine": range.end.line + 1,
+                                "endColumn": range.end.character + 1,
+                            }
+                        }
+                    }]
+                })
+            })
+        })
+        .collect();
+
+    json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "texlab",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "informationUri": "https://github.com/latex-lsp/texlab",
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+fn severity_name(severity: Option<DiagnosticSeverity>) -> &'static str {
+    match severity {
+        Some(DiagnosticSeverity::ERROR) => "error",
+        Some(DiagnosticSeverity::WARNING) => "warning",
+        Some(DiagnosticSeverity::INFORMATION) => "info",
+        Some(DiagnosticSeverity::HINT) => "hint",
+        _ => "error",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::{tempdir, TempDir};
+
+    use crate::DiagnosticsOptions;
+
+    use super::*;
+
+    fn run_check(files: &[(&str, &str)], format: OutputFormat, options: Options) -> (bool, String) {
+        let directory = create_directory(files);
+        let mut output = Vec::new();
+        let has_errors = check(directory.path(), format, false, options, &mut output).unwrap();
+        (has_errors, String::from_utf8(output).unwrap())
+    }
+
+    fn create_directory(files: &[(&str, &str)]) -> TempDir {
+        let directory = tempdir().unwrap();
+        for (name, text) in files {
+            fs::write(directory.path().join(name), text).unwrap();
+        }
+
+        directory
+    }
+
+    #[test]
+    fn test_text_error() {
+        let (has_errors, output) =
+            run_check(&[("main.tex", "}")], OutputFormat::Text, Options::default());
+
+        assert!(has_errors);
+        assert_eq!(output, "main.tex:1:1: error: Unexpected \"}\" [1]\n");
+    }
+
+    #[test]
+    fn test_text_warning() {
+        let (has_errors, output) = run_check(
+            &[
+                (
+                    "main.tex",
+                    "\\begin{document}\\cite{bar}\\bibliography{refs}\\end{document}",
+                ),
+                (
+                    "refs.bib",
+                    "@misc{foo, author = {Foo}, title = {Foo}, year = {2020}}",
+                ),
+            ],
+            OutputFormat::Text,
+            Options::default(),
+        );
+
+        assert!(!has_errors);
+        assert_eq!(
+            output,
+            "main.tex:1:23: warning: Undefined citation key \"bar\" [12]\n"
+        );
+    }
+
+    #[test]
+    fn test_no_diagnostics() {
+        let (has_errors, output) = run_check(
+            &[("main.tex", "\\begin{document}\\end{document}")],
+            OutputFormat::Text,
+            Options::default(),
+        );
+
+        assert!(!has_errors);
+        assert_eq!(output, "");
+    }
+
+    #[test]
+    fn test_sarif() {
+        let (has_errors, output) = run_check(
+            &[("main.tex", "}")],
+            OutputFormat::Sarif,
+            Options::default(),
+        );
+
+        assert!(has_errors);
+        let sarif: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(sarif["version"], "2.1.0");
+        let results = sarif["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0]["ruleId"], "1");
+        assert_eq!(results[0]["level"], "error");
+        assert_eq!(results[0]["message"]["text"], "Unexpected \"}\"");
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert!(location["artifactLocation"]["uri"]
+            .as_str()
+            .unwrap()
+            .ends_with("/main.tex"));
+        assert_eq!(location["region"]["startLine"], 1);
+        assert_eq!(location["region"]["startColumn"], 1);
+    }
+
+    #[test]
+    fn test_options() {
+        let files = [
+            (
+                "main.tex",
+                "\\begin{document}\\cite{foo}\\bibliography{refs}\\end{document}",
+            ),
+            (
+                "refs.bib",
+                "@misc{foo, author = {Foo}, title = {Foo}, year = {2020}}\n@misc{bar, author = {Bar}, title = {Bar}, year = {2020}}",
+            ),
+        ];
+
+        let (_, output) = run_check(&files, OutputFormat::Text, Options::default());
+        assert_eq!(output, "");
+
+        let options = Options {
+            diagnostics: DiagnosticsOptions {
+                uncited_entries: true,
+            },
+            ..Options::default()
+        };
+
+        let (has_errors, output) = run_check(&files, OutputFormat::Text, options);
+        assert!(!has_errors);
+        assert_eq!(
+            output,
+            "refs.bib:2:7: hint: Entry \"bar\" is never cited [14]\n"
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use std::{fs, io, path::PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::{features::format_bibtex_text, BibtexFormattingOptions};
+
+/// Formats the given BibTeX files in place.
+/// If `check` is set, the files are left untouched and a diff is written to `output` instead.
+/// Returns `true` if at least one file is not formatted correctly.
+pub fn format(
+    files: &[PathBuf],
+    check: bool,
+    line_length: usize,
+    tab_size: u32,
+    options: &BibtexFormattingOptions,
+    output: &mut dyn io::Write,
+) -> Result<bool> {
+    let mut has_changes = false;
+    for path in files {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+
+        let new_text = format_bibtex_text(&text, true, tab_size, line_length, options);
+        if new_text == text {
+            continue;
+        }
+
+        has_changes = true;
+        if check {
+            write_diff(&path.display().to_string(), &text, &new_text, output)?;
+        } else {
+            fs::write(path, new_text)
+                .with_context(|| format!("failed to write {}", path.display()))?;
+        }
+    }
+
+    Ok(has_changes)
+}
+
+/// The number of unchanged lines that are shown around each change.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum DiffOp {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Writes a unified diff between the lines of both texts.
+fn write_diff(
+    name: &str,
+    old_text: &str,
+    new_text: &str,
+    output: &mut dyn io::Write,
+) -> io::Result<()> {
+    // Keep the line endings so that a missing newline at the end of the file shows up as a change.
+    let old_lines: Vec<_> = old_text.split_inclusive('\n').collect();
+    let new_lines: Vec<_> = new_text.split_inclusive('\n').collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    writeln!(output, "--- {}", name)?;
+    writeln!(output, "+++ {}", name)?;
+
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| **op != DiffOp::Equal)
+    {
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + 1 + CONTEXT_LINES).min(ops.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    for (start, end) in hunks {
+        let old_start = ops[..start]
+            .iter()
+            .filter(|op| **op != DiffOp::Insert)
+            .count();
+        let new_start = ops[..start]
+            .iter()
+            .filter(|op| **op != DiffOp::Delete)
+            .count();
+        let old_len = ops[start..end]
+            .iter()
+            .filter(|op| **op != DiffOp::Insert)
+            .count();
+        let new_len = ops[start..end]
+            .iter()
+            .filter(|op| **op != DiffOp::Delete)
+            .count();
+        writeln!(
+            output,
+            "@@ -{} +{} @@",
+            hunk_range(old_start, old_len),
+            hunk_range(new_start, new_len)
+        )?;
+
+        let (mut i, mut j) = (old_start, new_start);
+        for op in &ops[start..end] {
+            let (prefix, line) = match op {
+                DiffOp::Equal => {
+                    i += 1;
+                    j += 1;
+                    (' ', old_lines[i - 1])
+                }
+                DiffOp::Delete => {
+                    i += 1;
+                    ('-', old_lines[i - 1])
+                }
+                DiffOp::Insert => {
+                    j += 1;
+                    ('+', new_lines[j - 1])
+                }
+            };
+
+            write!(output, "{}{}", prefix, line)?;
+            if !line.ends_with('\n') {
+                writeln!(output)?;
+                writeln!(output, "\\ No newline at end of file")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    // Empty ranges refer to the line before the change.
+    if len == 0 {
+        format!("{},0", start)
+    } else {
+        format!("{},{}", start + 1, len)
+    }
+}
+
+/// Computes the shortest edit script between the lines using the linear space variant
+/// of the algorithm of Myers, which needs O((N + M) D) time for D differences
+/// but only O(N + M) memory.
+fn diff_lines(old_lines: &[&str], new_lines: &[&str]) -> Vec<DiffOp> {
+    let mut ops = Vec::with_capacity(old_lines.len() + new_lines.len());
+    diff_range(old_lines, new_lines, &mut ops);
+    ops
+}
+
+fn diff_range(old_lines: &[&str], new_lines: &[&str], ops: &mut Vec<DiffOp>) {
+    let prefix_len = old_lines
+        .iter()
+        .zip(new_lines)
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let suffix_len = old_lines[prefix_len..]
+        .iter()
+        .rev()
+        .zip(new_lines[prefix_len..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let old_lines = &old_lines[prefix_len..old_lines.len() - suffix_len];
+    let new_lines = &new_lines[prefix_len..new_lines.len() - suffix_len];
+    ops.resize(ops.len() + prefix_len, DiffOp::Equal);
+    if old_lines.is_empty() || new_lines.is_empty() {
+        ops.resize(ops.len() + old_lines.len(), DiffOp::Delete);
+        ops.resize(ops.len() + new_lines.len(), DiffOp::Insert);
+    } else {
+        let (x, y) = find_middle_snake(old_lines, new_lines);
+        diff_range(&old_lines[..x], &new_lines[..y], ops);
+        diff_range(&old_lines[x..], &new_lines[y..], ops);
+    }
+    ops.resize(ops.len() + suffix_len, DiffOp::Equal);
+}
+
+/// Searches the shortest edit script from both ends at the same time
+/// and returns a point where both paths overlap.
+/// The lines must neither start nor end with a common line.
+fn find_middle_snake(old_lines: &[&str], new_lines: &[&str]) -> (usize, usize) {
+    let n = old_lines.len() as isize;
+    let m = new_lines.len() as isize;
+    let delta = n - m;
+    let is_odd = delta % 2 != 0;
+    let max = (n + m + 1) / 2;
+    let index = |k: isize| (k + max + 1) as usize;
+
+    // The furthest reaching x coordinate on each diagonal k = x - y,
+    // measured from the start (forward) and from the end (backward).
+    let mut forward = vec![0; 2 * max as usize + 3];
+    let mut backward = vec![0; 2 * max as usize + 3];
+    for d in 0..=max {
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[index(k - 1)] < forward[index(k + 1)]) {
+                forward[index(k + 1)]
+            } else {
+                forward[index(k - 1)] + 1
+            };
+
+            let mut y = x - k;
+            while x < n && y < m && old_lines[x as usize] == new_lines[y as usize] {
+                x += 1;
+                y += 1;
+            }
+
+            forward[index(k)] = x;
+
+            // The backward path on the opposite diagonal has made d - 1 edits so far.
+            let backward_k = delta - k;
+            if is_odd
+                && x <= n
+                && y <= m
+                && (-(d - 1)..=d - 1).contains(&backward_k)
+                && x + backward[index(backward_k)] >= n
+            {
+                return (x as usize, y as usize);
+            }
+        }
+
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[index(k - 1)] < backward[index(k + 1)]) {
+                backward[index(k + 1)]
+            } else {
+                backward[index(k - 1)] + 1
+            };
+
+            let mut y = x - k;
+            while x < n
+                && y < m
+                && old_lines[(n - x - 1) as usize] == new_lines[(m - y - 1) as usize]
+            {
+                x += 1;
+                y += 1;
+            }
+
+            backward[index(k)] = x;
+
+            let forward_k = delta - k;
+            if !is_odd
+                && x <= n
+                && y <= m
+                && (-d..=d).contains(&forward_k)
+                && x + forward[index(forward_k)] >= n
+            {
+                return ((n - x) as usize, (m - y) as usize);
+            }
+        }
+    }
+
+    unreachable!("the paths always overlap after (N + M) / 2 edits")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let mut output = Vec::new();
+        write_diff(
+            "main.bib",
+            "% foo\n@article{foo, bar = baz}\n",
+            "% foo\n@article{foo,\n  bar = baz,\n}\n",
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "--- main.bib\n+++ main.bib\n@@ -1,2 +1,4 @@\n % foo\n-@article{foo, bar = baz}\n+@article{foo,\n+  bar = baz,\n+}\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_trailing_newline() {
+        let mut output = Vec::new();
+        write_diff(
+            "main.bib",
+            "@article{foo,\n}",
+            "@article{foo,\n}\n",
+            &mut output,
+        )
+        .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "--- main.bib\n+++ main.bib\n@@ -1,2 +1,2 @@\n @article{foo,\n-}\n\\ No newline at end of file\n+}\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_separate_hunks() {
+        let old_text: String = (1..=20).map(|i| format!("{}\n", i)).collect();
+        let new_text = old_text
+            .replacen("2\n", "two\n", 1)
+            .replace("18\n", "eighteen\n");
+
+        let mut output = Vec::new();
+        write_diff("main.bib", &old_text, &new_text, &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "--- main.bib\n+++ main.bib\n@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n@@ -15,6 +15,6 @@\n 15\n 16\n 17\n-18\n+eighteen\n 19\n 20\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_empty_file() {
+        let mut output = Vec::new();
+        write_diff("main.bib", "", "@string{foo = bar}\n", &mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "--- main.bib\n+++ main.bib\n@@ -0,0 +1,1 @@\n+@string{foo = bar}\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_lines_shortest() {
+        let old_lines = ["a", "b", "c", "a", "b", "b", "a"];
+        let new_lines = ["c", "b", "a", "b", "a", "c"];
+        let ops = diff_lines(&old_lines, &new_lines);
+
+        let edits = ops.iter().filter(|op| **op != DiffOp::Equal).count();
+        assert_eq!(edits, 5);
+    }
+
+    #[test]
+    fn test_diff_lines_all_changed() {
+        let old_lines: Vec<_> = (0..1000).map(|i| format!("{}\n", i)).collect();
+        let new_lines: Vec<_> = (0..1000).map(|i| format!("  {}\n", i)).collect();
+        let old_lines: Vec<_> = old_lines.iter().map(String::as_str).collect();
+        let new_lines: Vec<_> = new_lines.iter().map(String::as_str).collect();
+        let ops = diff_lines(&old_lines, &new_lines);
+
+        let deletions = ops.iter().filter(|op| **op == DiffOp::Delete).count();
+        let insertions = ops.iter().filter(|op| **op == DiffOp::Insert).count();
+        assert_eq!((deletions, insertions), (1000, 1000));
+        assert_eq!(ops.len(), 2000);
+    }
+}
@@ -0,0 +1,7 @@
+mod check;
+mod format;
+
+pub use self::{
+    check::{check, OutputFormat},
+    format::format,
+};
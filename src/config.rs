@@ -1,4 +1,4 @@
-use std::sync::{Mutex, RwLock};
+use std::sync::{Arc, Mutex, RwLock};
 
 use crossbeam_channel::Sender;
 use log::{error, warn};
@@ -9,7 +9,9 @@ use lsp_types::{
     ClientCapabilities, ConfigurationItem, ConfigurationParams, Registration, RegistrationParams,
 };
 
-use crate::{client::send_request, req_queue::ReqQueue, ClientCapabilitiesExt, Options};
+use crate::{
+    client::send_request, req_queue::ReqQueue, ClientCapabilitiesExt, Options, ServerContext, Uri,
+};
 
 pub fn register_config_capability(
     req_queue: &Mutex<ReqQueue>,
@@ -39,50 +41,136 @@ pub fn register_config_capability(
     }
 }
 
+/// Pulls the global configuration and the configuration of every workspace folder.
 pub fn pull_config(
     req_queue: &Mutex<ReqQueue>,
     sender: &Sender<Message>,
-    options: &RwLock<Options>,
+    context: &ServerContext,
     client_capabilities: &ClientCapabilities,
 ) {
     if !client_capabilities.has_pull_configuration_support() {
         return;
     }
 
-    let params = ConfigurationParams {
-        items: vec![ConfigurationItem {
+    let folder_uris: Vec<_> = context
+        .workspace_folders
+        .read()
+        .unwrap()
+        .iter()
+        .map(|folder| Arc::clone(&folder.uri))
+        .collect();
+
+    let items = std::iter::once(None)
+        .chain(folder_uris.iter().map(Some))
+        .map(|uri| ConfigurationItem {
             section: Some("texlab".to_string()),
-            scope_uri: None,
-        }],
-    };
+            scope_uri: uri.map(|uri| uri.as_ref().clone().into()),
+        })
+        .collect();
 
+    let params = ConfigurationParams { items };
     match send_request::<WorkspaceConfiguration>(req_queue, sender, params) {
-        Ok(mut json) => {
-            let value = json.pop().expect("invalid configuration request");
-            let new_options = match serde_json::from_value(value) {
-                Ok(new_options) => new_options,
-                Err(why) => {
-                    warn!("Invalid configuration section \"texlab\": {}", why);
-                    Options::default()
-                }
-            };
-
-            let mut options = options.write().unwrap();
-            *options = new_options;
-        }
+        Ok(json) => apply_config(context, &folder_uris, json),
         Err(why) => {
             error!("Retrieving configuration failed: {}", why);
         }
     };
 }
 
-pub fn push_config(options: &RwLock<Options>, config: serde_json::Value) {
+/// Stores the pulled configuration items, which start with the global configuration
+/// followed by the configuration of each folder in `folder_uris`.
+fn apply_config(context: &ServerContext, folder_uris: &[Arc<Uri>], json: Vec<serde_json::Value>) {
+    let mut all_options = json.into_iter().map(parse_options);
+    *context.options.write().unwrap() = Arc::new(all_options.next().unwrap_or_default());
+
+    let mut folders = context.workspace_folders.write().unwrap();
+    for (uri, options) in folder_uris.iter().zip(all_options) {
+        if let Some(folder) = folders.iter_mut().find(|folder| folder.uri == *uri) {
+            folder.options = Some(Arc::new(options));
+        }
+    }
+}
+
+fn parse_options(value: serde_json::Value) -> Options {
+    match serde_json::from_value(value) {
+        Ok(options) => options,
+        Err(why) => {
+            warn!("Invalid configuration section \"texlab\": {}", why);
+            Options::default()
+        }
+    }
+}
+
+pub fn push_config(options: &RwLock<Arc<Options>>, config: serde_json::Value) {
     match serde_json::from_value(config) {
         Ok(new_options) => {
-            *options.write().unwrap() = new_options;
+            *options.write().unwrap() = Arc::new(new_options);
         }
         Err(why) => {
             error!("Invalid configuration: {}", why);
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{env, path::PathBuf};
+
+    use serde_json::json;
+
+    use crate::WorkspaceFolderConfig;
+
+    use super::*;
+
+    #[test]
+    fn test_apply_config() {
+        let context = ServerContext::new(env::temp_dir());
+        let foo = Arc::new(Uri::from_directory_path(env::temp_dir().join("foo")).unwrap());
+        let bar = Arc::new(Uri::from_directory_path(env::temp_dir().join("bar")).unwrap());
+        *context.workspace_folders.write().unwrap() = vec![
+            WorkspaceFolderConfig::new(Arc::clone(&bar)).unwrap(),
+            WorkspaceFolderConfig::new(Arc::clone(&foo)).unwrap(),
+        ];
+
+        apply_config(
+            &context,
+            &[Arc::clone(&foo), Arc::clone(&bar)],
+            vec![
+                json!({ "rootDirectory": "global" }),
+                json!({ "rootDirectory": "foo" }),
+                json!({ "rootDirectory": "bar" }),
+            ],
+        );
+
+        let root_directory = |options: &Options| options.root_directory.clone().unwrap();
+        assert_eq!(
+            root_directory(&context.options.read().unwrap()),
+            PathBuf::from("global")
+        );
+
+        let folders = context.workspace_folders.read().unwrap();
+        assert_eq!(folders[0].uri, bar);
+        assert_eq!(
+            root_directory(folders[0].options.as_ref().unwrap()),
+            PathBuf::from("bar")
+        );
+        assert_eq!(folders[1].uri, foo);
+        assert_eq!(
+            root_directory(folders[1].options.as_ref().unwrap()),
+            PathBuf::from("foo")
+        );
+    }
+
+    #[test]
+    fn test_apply_config_missing_items() {
+        let context = ServerContext::new(env::temp_dir());
+        let foo = Arc::new(Uri::from_directory_path(env::temp_dir().join("foo")).unwrap());
+        *context.workspace_folders.write().unwrap() =
+            vec![WorkspaceFolderConfig::new(Arc::clone(&foo)).unwrap()];
+
+        apply_config(&context, &[foo], vec![json!({ "rootDirectory": "global" })]);
+
+        let folders = context.workspace_folders.read().unwrap();
+        assert_eq!(folders[0].options, None);
+    }
+}
@@ -1,13 +1,14 @@
 use std::{
     path::PathBuf,
-    sync::{Mutex, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
 use lsp_types::{ClientCapabilities, ClientInfo};
 
 use crate::{
+    diagnostics::DiagnosticPullSupport,
     distro::{DistributionKind, Resolver},
-    Options,
+    Options, Uri,
 };
 
 #[derive(Debug)]
@@ -17,7 +18,29 @@ pub struct ServerContext {
     pub resolver: Mutex<Resolver>,
     pub client_capabilities: Mutex<ClientCapabilities>,
     pub client_info: Mutex<Option<ClientInfo>>,
-    pub options: RwLock<Options>,
+    pub diagnostic_pull_support: Mutex<DiagnosticPullSupport>,
+    pub options: RwLock<Arc<Options>>,
+    pub workspace_folders: RwLock<Vec<WorkspaceFolderConfig>>,
+}
+
+/// The configuration of a single workspace folder.
+/// Folders without their own options fall back to the global options.
+#[derive(Debug, Clone)]
+pub struct WorkspaceFolderConfig {
+    pub uri: Arc<Uri>,
+    pub directory: PathBuf,
+    pub options: Option<Arc<Options>>,
+}
+
+impl WorkspaceFolderConfig {
+    pub fn new(uri: Arc<Uri>) -> Option<Self> {
+        let directory = uri.to_file_path().ok()?;
+        Some(Self {
+            uri,
+            directory,
+            options: None,
+        })
+    }
 }
 
 impl ServerContext {
@@ -28,7 +51,116 @@ impl ServerContext {
             resolver: Mutex::new(Resolver::default()),
             client_capabilities: Mutex::default(),
             client_info: Mutex::default(),
+            diagnostic_pull_support: Mutex::default(),
             options: RwLock::default(),
+            workspace_folders: RwLock::default(),
         }
     }
+
+    /// Returns the options of the innermost workspace folder containing the document
+    /// or the global options if there is no such folder.
+    pub fn options_for(&self, uri: &Uri) -> Arc<Options> {
+        self.find_folder(uri)
+            .and_then(|folder| folder.options)
+            .unwrap_or_else(|| Arc::clone(&self.options.read().unwrap()))
+    }
+
+    /// Returns the directory that relative paths in the options of the document are resolved against.
+    pub fn directory_for(&self, uri: &Uri) -> PathBuf {
+        self.find_folder(uri)
+            .map(|folder| folder.directory)
+            .unwrap_or_else(|| self.current_directory.clone())
+    }
+
+    fn find_folder(&self, uri: &Uri) -> Option<WorkspaceFolderConfig> {
+        let path = uri.to_file_path().ok()?;
+        self.workspace_folders
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|folder| path.starts_with(&folder.directory))
+            .max_by_key(|folder| folder.directory.components().count())
+            .cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    fn options(root_directory: &str) -> Arc<Options> {
+        Arc::new(Options {
+            root_directory: Some(root_directory.into()),
+            ..Options::default()
+        })
+    }
+
+    fn folder(directory: PathBuf, options: Option<Arc<Options>>) -> WorkspaceFolderConfig {
+        let uri = Arc::new(Uri::from_directory_path(&directory).unwrap());
+        WorkspaceFolderConfig {
+            uri,
+            directory,
+            options,
+        }
+    }
+
+    fn create_context() -> (ServerContext, PathBuf) {
+        let root = env::temp_dir().join("workspace");
+        let context = ServerContext::new(env::temp_dir());
+        *context.options.write().unwrap() = options("global");
+        *context.workspace_folders.write().unwrap() = vec![
+            folder(root.join("inner"), Some(options("inner"))),
+            folder(root.clone(), Some(options("outer"))),
+            folder(root.join("other"), None),
+        ];
+        (context, root)
+    }
+
+    fn uri(path: PathBuf) -> Uri {
+        Uri::from_file_path(path).unwrap()
+    }
+
+    #[test]
+    fn test_innermost_folder() {
+        let (context, root) = create_context();
+        let uri = uri(root.join("inner").join("main.tex"));
+        assert_eq!(context.options_for(&uri), options("inner"));
+        assert_eq!(context.directory_for(&uri), root.join("inner"));
+    }
+
+    #[test]
+    fn test_outer_folder() {
+        let (context, root) = create_context();
+        let uri = uri(root.join("inner2").join("main.tex"));
+        assert_eq!(context.options_for(&uri), options("outer"));
+        assert_eq!(context.directory_for(&uri), root);
+    }
+
+    #[test]
+    fn test_folder_without_options() {
+        let (context, root) = create_context();
+        let uri = uri(root.join("other").join("main.tex"));
+        assert_eq!(context.options_for(&uri), options("global"));
+        assert_eq!(context.directory_for(&uri), root.join("other"));
+    }
+
+    #[test]
+    fn test_outside_of_folders() {
+        let (context, _) = create_context();
+        let uri = uri(env::temp_dir().join("main.tex"));
+        assert_eq!(context.options_for(&uri), options("global"));
+        assert_eq!(context.directory_for(&uri), env::temp_dir());
+    }
+
+    #[test]
+    fn test_options_are_shared() {
+        let (context, root) = create_context();
+        let uri = uri(root.join("main.tex"));
+        assert!(Arc::ptr_eq(
+            &context.options_for(&uri),
+            &context.options_for(&uri)
+        ));
+    }
 }
@@ -1,17 +1,54 @@
 use std::sync::Arc;
 
 use cstree::TextRange;
-use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, TextEdit};
 use multimap::MultiMap;
+use once_cell::sync::Lazy;
+use rustc_hash::FxHashSet;
 
 use crate::{
+    diagnostics::DiagnosticFix,
     syntax::{
         bibtex::{self, HasDelimiters, HasType},
         CstNode,
     },
-    Document, LineIndexExt, Uri, Workspace,
+    Document, LineIndexExt, Uri, Workspace, LANGUAGE_DATA,
 };
 
+const FIELD_ALIASES: &[(&str, &str)] = &[
+    ("address", "location"),
+    ("annote", "annotation"),
+    ("archiveprefix", "eprinttype"),
+    ("journal", "journaltitle"),
+    ("primaryclass", "eprintclass"),
+    ("school", "institution"),
+];
+
+/// Classic BibTeX entry types that biblatex maps to one of its own types
+/// together with the fields that the mapping fills in.
+const ENTRY_TYPE_ALIASES: &[(&str, &str, &[&str])] = &[
+    ("conference", "inproceedings", &[]),
+    ("electronic", "online", &[]),
+    ("mastersthesis", "thesis", &["type"]),
+    ("phdthesis", "thesis", &["type"]),
+    ("techreport", "report", &["type"]),
+    ("www", "online", &[]),
+];
+
+static TYPE_SPECIFIC_FIELDS: Lazy<FxHashSet<&'static str>> = Lazy::new(|| {
+    LANGUAGE_DATA
+        .entry_types
+        .iter()
+        .flat_map(|ty| {
+            ty.required_fields
+                .iter()
+                .flatten()
+                .chain(&ty.optional_fields)
+        })
+        .map(String::as_str)
+        .collect()
+});
+
 pub fn analyze_bibtex_static(
     workspace: &dyn Workspace,
     diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
@@ -20,8 +57,12 @@ pub fn analyze_bibtex_static(
     let document = workspace.get(uri)?;
     let data = document.data.as_bibtex()?;
 
+    let (bibtex_count, biblatex_count) =
+        count_field_names(data.root.descendants().filter_map(bibtex::Field::cast));
+    let prefers_biblatex_names = biblatex_count > bibtex_count;
+
     for node in data.root.descendants() {
-        analyze_entry(&document, diagnostics_by_uri, node)
+        analyze_entry(&document, diagnostics_by_uri, node, prefers_biblatex_names)
             .or_else(|| analyze_field(&document, diagnostics_by_uri, node));
     }
 
@@ -32,15 +73,25 @@ fn analyze_entry(
     document: &Document,
     diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
     node: &bibtex::SyntaxNode,
+    prefers_biblatex_names: bool,
 ) -> Option<()> {
     let entry = bibtex::Entry::cast(node)?;
     if entry.left_delimiter().is_none() {
+        let ty = entry.ty()?;
+        let fix = DiagnosticFix::new(
+            "Insert \"{\"",
+            vec![TextEdit::new(
+                document
+                    .line_index
+                    .line_col_lsp_range(TextRange::empty(ty.text_range().end())),
+                "{".into(),
+            )],
+        );
+
         diagnostics_by_uri.insert(
             Arc::clone(&document.uri),
             Diagnostic {
-                range: document
-                    .line_index
-                    .line_col_lsp_range(entry.ty()?.text_range()),
+                range: document.line_index.line_col_lsp_range(ty.text_range()),
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(NumberOrString::Number(4)),
                 code_description: None,
@@ -48,19 +99,40 @@ fn analyze_entry(
                 message: "Expecting a curly bracket: \"{\"".to_string(),
                 related_information: None,
                 tags: None,
-                data: None,
+                data: fix.into_data(),
             },
         );
         return Some(());
     }
 
     if entry.key().is_none() {
+        let delimiter = entry.left_delimiter()?;
+        let end = delimiter.text_range().end();
+        let key = if document.text[usize::from(end)..]
+            .trim_start()
+            .starts_with(',')
+        {
+            "key"
+        } else {
+            "key,"
+        };
+
+        let fix = DiagnosticFix::new(
+            "Insert entry key",
+            vec![TextEdit::new(
+                document
+                    .line_index
+                    .line_col_lsp_range(TextRange::empty(end)),
+                key.into(),
+            )],
+        );
+
         diagnostics_by_uri.insert(
             Arc::clone(&document.uri),
             Diagnostic {
                 range: document
                     .line_index
-                    .line_col_lsp_range(entry.left_delimiter()?.text_range()),
+                    .line_col_lsp_range(delimiter.text_range()),
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(NumberOrString::Number(5)),
                 code_description: None,
@@ -68,19 +140,31 @@ fn analyze_entry(
                 message: "Expecting a key".to_string(),
                 related_information: None,
                 tags: None,
-                data: None,
+                data: fix.into_data(),
             },
         );
         return Some(());
     }
 
-    if entry.key().is_none() {
+    if entry.right_delimiter().is_none() {
+        let end = entry
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|element| element.into_token())
+            .filter(|token| token.kind() != bibtex::WHITESPACE)
+            .last()?
+            .text_range()
+            .end();
+
+        let range = document
+            .line_index
+            .line_col_lsp_range(TextRange::empty(end));
+        let fix = DiagnosticFix::new("Insert \"}\"", vec![TextEdit::new(range, "}".into())]);
+
         diagnostics_by_uri.insert(
             Arc::clone(&document.uri),
             Diagnostic {
-                range: document
-                    .line_index
-                    .line_col_lsp_range(entry.right_delimiter()?.text_range()),
+                range,
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(NumberOrString::Number(6)),
                 code_description: None,
@@ -88,15 +172,324 @@ fn analyze_entry(
                 message: "Expecting a curly bracket: \"}\"".to_string(),
                 related_information: None,
                 tags: None,
+                data: fix.into_data(),
+            },
+        );
+        return Some(());
+    }
+
+    analyze_entry_fields(document, diagnostics_by_uri, entry, prefers_biblatex_names)
+}
+
+fn analyze_entry_fields(
+    document: &Document,
+    diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
+    entry: bibtex::Entry,
+    prefers_biblatex_names: bool,
+) -> Option<()> {
+    let fields: Vec<_> = entry
+        .fields()
+        .filter_map(|field| Some((canonical_field_name(field.name()?.text()), field)))
+        .collect();
+
+    for (name, field) in &fields {
+        analyze_date(document, diagnostics_by_uri, name, field);
+    }
+
+    let ty = entry.ty()?;
+    let original_ty_name = ty.text()[1..].to_lowercase();
+    let type_alias = ENTRY_TYPE_ALIASES
+        .iter()
+        .find(|(alias, _, _)| *alias == original_ty_name);
+    let (ty_name, implied_fields) = type_alias.map_or(
+        (original_ty_name.as_str(), &[][..]),
+        |(_, target, implied_fields)| (*target, *implied_fields),
+    );
+
+    // Entries follow the naming of their file unless they clearly use one of both conventions.
+    let prefers_biblatex_names = if type_alias.is_some() {
+        false
+    } else {
+        match count_field_names(entry.fields()) {
+            (0, 0) => prefers_biblatex_names,
+            (bibtex_count, biblatex_count) => biblatex_count > bibtex_count,
+        }
+    };
+
+    let ty_doc = LANGUAGE_DATA
+        .find_entry_type(ty_name)
+        .filter(|ty_doc| ty_doc.has_field_data())?;
+
+    let inherits_fields = fields
+        .iter()
+        .any(|(name, _)| matches!(name.as_str(), "crossref" | "xdata" | "xref"));
+
+    let key_range = entry.key()?.small_range();
+    for group in ty_doc
+        .required_fields
+        .iter()
+        .filter(|_| !inherits_fields)
+        .filter(|group| {
+            !group.iter().any(|required| {
+                implied_fields.contains(&required.as_str())
+                    || fields.iter().any(|(name, _)| name == required)
+            })
+        })
+    {
+        let (first_name, _) =
+            preferred_field_name(&original_ty_name, &group[0], prefers_biblatex_names);
+        let fix = DiagnosticFix::new(
+            format!("Insert field \"{}\"", first_name),
+            vec![insert_field(document, &entry, first_name)?],
+        );
+
+        let names: Vec<_> = group
+            .iter()
+            .map(|name| {
+                match preferred_field_name(&original_ty_name, name, prefers_biblatex_names) {
+                    (name, Some(alias)) => format!("\"{}\" (alias \"{}\")", name, alias),
+                    (name, None) => format!("\"{}\"", name),
+                }
+            })
+            .collect();
+        diagnostics_by_uri.insert(
+            Arc::clone(&document.uri),
+            Diagnostic {
+                range: document.line_index.line_col_lsp_range(key_range),
+                severity: Some(DiagnosticSeverity::WARNING),
+                code: Some(NumberOrString::Number(15)),
+                code_description: None,
+                source: Some("texlab".to_string()),
+                message: format!("Missing required field {}", names.join(" or ")),
+                related_information: None,
+                tags: None,
+                data: fix.into_data(),
+            },
+        );
+    }
+
+    for (_, field) in fields.iter().filter(|(name, _)| {
+        TYPE_SPECIFIC_FIELDS.contains(name.as_str()) && !ty_doc.allows_field(name)
+    }) {
+        let name = field.name()?;
+        diagnostics_by_uri.insert(
+            Arc::clone(&document.uri),
+            Diagnostic {
+                range: document.line_index.line_col_lsp_range(name.text_range()),
+                severity: Some(DiagnosticSeverity::INFORMATION),
+                code: Some(NumberOrString::Number(16)),
+                code_description: None,
+                source: Some("texlab".to_string()),
+                message: format!(
+                    "Field \"{}\" does not belong to entry type \"{}\"",
+                    name.text(),
+                    ty_doc.name
+                ),
+                related_information: None,
+                tags: None,
                 data: None,
             },
         );
+    }
+
+    Some(())
+}
+
+fn analyze_date(
+    document: &Document,
+    diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
+    name: &str,
+    field: &bibtex::Field,
+) -> Option<()> {
+    let is_valid: fn(&str) -> bool = match name {
+        "year" => is_valid_year,
+        "date" | "eventdate" | "origdate" | "urldate" => is_valid_date,
+        _ => return None,
+    };
+
+    let value = field.value()?;
+    let text = literal_text(&value)?;
+    if is_valid(&text) {
         return Some(());
     }
 
+    diagnostics_by_uri.insert(
+        Arc::clone(&document.uri),
+        Diagnostic {
+            range: document
+                .line_index
+                .line_col_lsp_range(value.syntax().text_range()),
+            severity: Some(DiagnosticSeverity::WARNING),
+            code: Some(NumberOrString::Number(17)),
+            code_description: None,
+            source: Some("texlab".to_string()),
+            message: format!("Invalid {}: \"{}\"", name, text),
+            related_information: None,
+            tags: None,
+            data: None,
+        },
+    );
     Some(())
 }
 
+fn insert_field(document: &Document, entry: &bibtex::Entry, name: &str) -> Option<TextEdit> {
+    let right_delimiter = entry.right_delimiter()?.text_range().start();
+    let last_token = entry
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.text_range().end() <= right_delimiter)
+        .filter(|token| token.kind() != bibtex::WHITESPACE)
+        .last()?;
+
+    let indent = entry
+        .fields()
+        .next()
+        .and_then(|field| {
+            let start = usize::from(field.syntax().text_range().start());
+            let line_start = document.text[..start].rfind('\n')? + 1;
+            let indent = &document.text[line_start..start];
+            if indent.chars().all(char::is_whitespace) {
+                Some(indent)
+            } else {
+                None
+            }
+        })
+        .unwrap_or("  ");
+
+    let text = if last_token.kind() == bibtex::COMMA {
+        format!("\n{}{} = {{}},", indent, name)
+    } else {
+        format!(",\n{}{} = {{}}", indent, name)
+    };
+
+    let range = document
+        .line_index
+        .line_col_lsp_range(TextRange::empty(last_token.text_range().end()));
+    Some(TextEdit::new(range, text))
+}
+
+/// Returns the name that classic BibTeX uses for a biblatex field in an entry of the given type.
+fn bibtex_field_name(ty_name: &str, name: &str) -> Option<&'static str> {
+    // BibTeX only uses `school` for theses, other entry types have an `institution` as well.
+    if name == "institution" && !ty_name.ends_with("thesis") {
+        return None;
+    }
+
+    FIELD_ALIASES
+        .iter()
+        .find(|(_, target)| *target == name)
+        .map(|(alias, _)| *alias)
+}
+
+/// Returns the name of a biblatex field that matches the naming convention
+/// together with the other accepted name.
+fn preferred_field_name<'a>(
+    ty_name: &str,
+    name: &'a str,
+    prefers_biblatex_names: bool,
+) -> (&'a str, Option<&'a str>) {
+    match bibtex_field_name(ty_name, name) {
+        Some(alias) if prefers_biblatex_names => (name, Some(alias)),
+        Some(alias) => (alias, Some(name)),
+        None => (name, None),
+    }
+}
+
+/// Counts the fields that use the classic BibTeX name and the fields that use the biblatex name
+/// of a field with an alias.
+fn count_field_names<'a>(fields: impl Iterator<Item = bibtex::Field<'a>>) -> (usize, usize) {
+    let mut counts = (0, 0);
+    for name in fields.filter_map(|field| Some(field.name()?.text().to_lowercase())) {
+        if FIELD_ALIASES.iter().any(|(alias, _)| *alias == name) {
+            counts.0 += 1;
+        } else if FIELD_ALIASES.iter().any(|(_, target)| *target == name) {
+            counts.1 += 1;
+        }
+    }
+    counts
+}
+
+fn canonical_field_name(name: &str) -> String {
+    let name = name.to_lowercase();
+    FIELD_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, target)| target.to_string())
+}
+
+fn literal_text(value: &bibtex::Value) -> Option<String> {
+    let mut tokens = value.tokens();
+    let token = tokens.next()?;
+    if tokens.next().is_some() {
+        return None;
+    }
+
+    let text = token.syntax().text().to_string();
+    if let Some(text) = text
+        .strip_prefix('{')
+        .and_then(|text| text.strip_suffix('}'))
+        .or_else(|| {
+            text.strip_prefix('"')
+                .and_then(|text| text.strip_suffix('"'))
+        })
+    {
+        // Braces only protect the case of the text, so `{{2020}}` is the same as `{2020}`.
+        Some(
+            text.chars()
+                .filter(|c| !matches!(c, '{' | '}'))
+                .collect::<String>()
+                .trim()
+                .to_string(),
+        )
+    } else if text.chars().all(|c| c.is_ascii_digit()) {
+        Some(text)
+    } else {
+        None
+    }
+}
+
+fn is_valid_year(text: &str) -> bool {
+    let digits = text.strip_prefix('-').unwrap_or(text);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_valid_date(text: &str) -> bool {
+    match text.split_once('/') {
+        Some((start, end)) => {
+            (start.is_empty() || start == ".." || is_valid_date_part(start))
+                && (end.is_empty() || end == ".." || is_valid_date_part(end))
+                && !(start.is_empty() && end.is_empty())
+        }
+        None => is_valid_date_part(text),
+    }
+}
+
+fn is_valid_date_part(text: &str) -> bool {
+    let text = text.split('T').next().unwrap_or_default();
+    let text = text.trim_end_matches(|c| matches!(c, '?' | '~' | '%'));
+    let text = text.strip_prefix('-').unwrap_or(text);
+
+    let is_number = |component: &str, min: u32, max: u32| {
+        component.len() == 2
+            && (component == "XX"
+                || component
+                    .parse::<u32>()
+                    .map_or(false, |value| (min..=max).contains(&value)))
+    };
+
+    let mut components = text.split('-');
+    let year = components.next().unwrap_or_default();
+    let is_year_valid = year.len() == 4 && year.chars().all(|c| c.is_ascii_digit() || c == 'X');
+
+    let is_month_valid = components.next().map_or(true, |month| {
+        is_number(month, 1, 12) || is_number(month, 21, 24)
+    });
+
+    let is_day_valid = components.next().map_or(true, |day| is_number(day, 1, 31));
+    is_year_valid && is_month_valid && is_day_valid && components.next().is_none()
+}
+
 fn analyze_field(
     document: &Document,
     diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
@@ -104,12 +497,15 @@ fn analyze_field(
 ) -> Option<()> {
     let field = bibtex::Field::cast(node)?;
     if field.equality_sign().is_none() {
+        let range = document
+            .line_index
+            .line_col_lsp_range(TextRange::empty(field.name()?.text_range().end()));
+        let fix = DiagnosticFix::new("Insert \"=\"", vec![TextEdit::new(range, " =".into())]);
+
         diagnostics_by_uri.insert(
             Arc::clone(&document.uri),
             Diagnostic {
-                range: document
-                    .line_index
-                    .line_col_lsp_range(TextRange::empty(field.name()?.text_range().end())),
+                range,
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(NumberOrString::Number(7)),
                 code_description: None,
@@ -117,19 +513,25 @@ fn analyze_field(
                 message: "Expecting an equality sign: \"=\"".to_string(),
                 related_information: None,
                 tags: None,
-                data: None,
+                data: fix.into_data(),
             },
         );
         return Some(());
     }
 
     if field.value().is_none() {
+        let range = document
+            .line_index
+            .line_col_lsp_range(TextRange::empty(field.equality_sign()?.text_range().end()));
+        let fix = DiagnosticFix::new(
+            "Insert empty field value",
+            vec![TextEdit::new(range, " {}".into())],
+        );
+
         diagnostics_by_uri.insert(
             Arc::clone(&document.uri),
             Diagnostic {
-                range: document.line_index.line_col_lsp_range(TextRange::empty(
-                    field.equality_sign()?.text_range().end(),
-                )),
+                range,
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(NumberOrString::Number(8)),
                 code_description: None,
@@ -137,7 +539,7 @@ fn analyze_field(
                 message: "Expecting a field value".to_string(),
                 related_information: None,
                 tags: None,
-                data: None,
+                data: fix.into_data(),
             },
         );
         return Some(());
@@ -145,3 +547,188 @@ fn analyze_field(
 
     Some(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::project::testing::{create_workspace, uri};
+
+    use super::*;
+
+    fn analyze_diagnostics(text: &str) -> Vec<Diagnostic> {
+        let workspace = create_workspace(&[("main.bib", text)]);
+        let mut diagnostics_by_uri = MultiMap::new();
+        analyze_bibtex_static(&workspace, &mut diagnostics_by_uri, &uri("main.bib"));
+        diagnostics_by_uri
+            .iter_all()
+            .flat_map(|(_, diagnostics)| diagnostics)
+            .cloned()
+            .collect()
+    }
+
+    fn analyze(text: &str) -> Vec<String> {
+        analyze_diagnostics(text)
+            .into_iter()
+            .map(|diagnostic| diagnostic.message)
+            .collect()
+    }
+
+    #[test]
+    fn test_is_valid_year() {
+        assert!(is_valid_year("2020"));
+        assert!(is_valid_year("-300"));
+        assert!(!is_valid_year(""));
+        assert!(!is_valid_year("-"));
+        assert!(!is_valid_year("20x0"));
+        assert!(!is_valid_year("2020/2021"));
+    }
+
+    #[test]
+    fn test_is_valid_date() {
+        assert!(is_valid_date("2020"));
+        assert!(is_valid_date("2020-02"));
+        assert!(is_valid_date("2020-02-29"));
+        assert!(is_valid_date("2020-21"));
+        assert!(is_valid_date("-0300"));
+        assert!(is_valid_date("2020-02-29T10:00:00"));
+        assert!(!is_valid_date("2020-13"));
+        assert!(!is_valid_date("2020-02-32"));
+        assert!(!is_valid_date("20"));
+        assert!(!is_valid_date("2020-02-29-01"));
+    }
+
+    #[test]
+    fn test_is_valid_date_range() {
+        assert!(is_valid_date("2020/2021"));
+        assert!(is_valid_date("2020-01/2020-03"));
+        assert!(is_valid_date("2020/"));
+        assert!(is_valid_date("/2020"));
+        assert!(is_valid_date("../2020"));
+        assert!(is_valid_date("2020/.."));
+        assert!(!is_valid_date("/"));
+        assert!(!is_valid_date("2020/13"));
+    }
+
+    #[test]
+    fn test_is_valid_date_uncertain() {
+        assert!(is_valid_date("2020?"));
+        assert!(is_valid_date("2020-02~"));
+        assert!(is_valid_date("2020-02-29%"));
+        assert!(is_valid_date("19XX"));
+        assert!(is_valid_date("2020-XX"));
+        assert!(!is_valid_date("2020!"));
+    }
+
+    #[test]
+    fn test_valid_entry() {
+        let actual =
+            analyze("@article{foo, author = {Foo}, title = {Bar}, journal = {Baz}, year = {2020}}");
+
+        assert_eq!(actual, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_missing_field() {
+        let actual = analyze("@article{foo, author = {Foo}, title = {Bar}, year = {2020}}");
+
+        assert_eq!(
+            actual,
+            vec!["Missing required field \"journal\" (alias \"journaltitle\")"]
+        );
+    }
+
+    #[test]
+    fn test_missing_field_fix() {
+        let diagnostics = analyze_diagnostics(
+            "@article{foo,\n  author = {Foo},\n  title = {Bar},\n  year = {2020},\n}",
+        );
+
+        assert_eq!(diagnostics.len(), 1);
+        let fix = DiagnosticFix::from_data(diagnostics[0].data.as_ref().unwrap()).unwrap();
+        assert_eq!(fix.title, "Insert field \"journal\"");
+        assert_eq!(fix.edits[0].new_text, "\n  journal = {},");
+    }
+
+    #[test]
+    fn test_missing_field_biblatex_names() {
+        let actual = analyze(
+            "@article{foo, author = {Foo}, title = {Bar}, date = {2020}}\n@article{bar, author = {Foo}, title = {Bar}, journaltitle = {Baz}, date = {2020}}",
+        );
+
+        assert_eq!(
+            actual,
+            vec!["Missing required field \"journaltitle\" (alias \"journal\")"]
+        );
+    }
+
+    #[test]
+    fn test_missing_field_entry_names() {
+        let actual = analyze(
+            "@article{foo, author = {Foo}, title = {Bar}, journaltitle = {Baz}, date = {2020}}\n@article{bar, author = {Foo}, title = {Bar}, journaltitle = {Baz}, date = {2021}}\n@article{baz, author = {Foo}, title = {Bar}, year = {2020}, primaryclass = {cs.CL}}",
+        );
+
+        assert_eq!(
+            actual,
+            vec!["Missing required field \"journal\" (alias \"journaltitle\")"]
+        );
+    }
+
+    #[test]
+    fn test_unknown_field() {
+        let actual = analyze("@book{foo, author = {Foo}, title = {Bar}, year = 2020, foo = {Baz}}");
+
+        assert_eq!(actual, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_field_of_other_type() {
+        let actual =
+            analyze("@book{foo, author = {Foo}, title = {Bar}, year = 2020, journal = {Baz}}");
+
+        assert_eq!(
+            actual,
+            vec!["Field \"journal\" does not belong to entry type \"book\""]
+        );
+    }
+
+    #[test]
+    fn test_alias_type() {
+        let actual = analyze(
+            "@phdthesis{foo, author = {Foo}, title = {Bar}, school = {Baz}, year = {2020}}",
+        );
+
+        assert_eq!(actual, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_alias_type_missing_field() {
+        let actual = analyze("@techreport{foo, author = {Foo}, title = {Bar}, year = {2020}}");
+
+        assert_eq!(actual, vec!["Missing required field \"institution\""]);
+    }
+
+    #[test]
+    fn test_alias_type_missing_school() {
+        let actual = analyze("@phdthesis{foo, author = {Foo}, title = {Bar}, year = {2020}}");
+
+        assert_eq!(
+            actual,
+            vec!["Missing required field \"school\" (alias \"institution\")"]
+        );
+    }
+
+    #[test]
+    fn test_nested_braces() {
+        let actual = analyze(
+            "@misc{foo, author = {Foo}, title = {Bar}, year = {{2020}}, date = {{2020}-{01}}}",
+        );
+
+        assert_eq!(actual, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_invalid_year() {
+        let actual = analyze("@misc{foo, author = {Foo}, title = {Bar}, year = {{20x0}}}");
+
+        assert_eq!(actual, vec!["Invalid year: \"20x0\""]);
+    }
+}
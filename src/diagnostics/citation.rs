@@ -0,0 +1,328 @@
+use std::sync::Arc;
+
+use cstree::TextRange;
+use lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString};
+use multimap::MultiMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    syntax::{bibtex, latex, CstNode},
+    DiagnosticsOptions, Document, LineIndexExt, Uri, WorkspaceSubset,
+};
+
+use super::project::{find_projects, insert_unique};
+
+pub fn analyze_citation_static(
+    subset: &WorkspaceSubset,
+    diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
+    options: &DiagnosticsOptions,
+) {
+    // An entry is only uncited if none of the projects that include its document cite it.
+    let mut project_counts: FxHashMap<&Uri, usize> = FxHashMap::default();
+    let mut uncited_counts: FxHashMap<(&Uri, TextRange), usize> = FxHashMap::default();
+
+    for project in find_projects(subset) {
+        let mut citations = Vec::new();
+        let mut entries = Vec::new();
+        for document in &project.documents {
+            *project_counts.entry(document.uri.as_ref()).or_default() += 1;
+            if let Some(data) = document.data.as_latex() {
+                for key in data
+                    .root
+                    .descendants()
+                    .filter_map(latex::Citation::cast)
+                    .filter_map(|citation| citation.key_list())
+                    .flat_map(|keys| keys.keys())
+                {
+                    citations.push((*document, key.to_string(), key.small_range()));
+                }
+
+                for (key, range) in data
+                    .root
+                    .descendants()
+                    .filter_map(latex::GenericCommand::cast)
+                    .filter_map(bibitem_key)
+                {
+                    entries.push((*document, key, range));
+                }
+            } else {
+                entries.extend(
+                    bibtex_entry_keys(document).map(|(key, range)| (*document, key, range)),
+                );
+            }
+        }
+
+        // BibTeX does not distinguish between keys that only differ in case.
+        let mut entry_counts: FxHashMap<String, usize> = FxHashMap::default();
+        for (_, key, _) in &entries {
+            *entry_counts.entry(key.to_lowercase()).or_default() += 1;
+        }
+
+        let entry_keys: FxHashSet<&str> = entries.iter().map(|(_, key, _)| key.as_str()).collect();
+        let cited_keys: FxHashSet<&str> =
+            citations.iter().map(|(_, key, _)| key.as_str()).collect();
+        let cites_all = cited_keys.contains("*");
+
+        if !entries.is_empty() {
+            for (document, key, range) in &citations {
+                if key != "*" && !entry_keys.contains(key.as_str()) {
+                    report(
+                        diagnostics_by_uri,
+                        document,
+                        *range,
+                        DiagnosticSeverity::WARNING,
+                        12,
+                        format!("Undefined citation key \"{}\"", key),
+                        None,
+                    );
+                }
+            }
+        }
+
+        let has_citations = !citations.is_empty();
+        for (document, key, range) in &entries {
+            if entry_counts[&key.to_lowercase()] > 1 {
+                report(
+                    diagnostics_by_uri,
+                    document,
+                    *range,
+                    DiagnosticSeverity::WARNING,
+                    13,
+                    format!("Duplicate entry key \"{}\"", key),
+                    None,
+                );
+            } else if options.uncited_entries
+                && document.data.as_bibtex().is_some()
+                && has_citations
+                && !cites_all
+                && !cited_keys.contains(key.as_str())
+            {
+                *uncited_counts
+                    .entry((document.uri.as_ref(), *range))
+                    .or_default() += 1;
+            }
+        }
+    }
+
+    for document in &subset.documents {
+        for (key, range) in bibtex_entry_keys(document) {
+            let uncited_count = uncited_counts.get(&(document.uri.as_ref(), range));
+            if uncited_count.is_some() && uncited_count == project_counts.get(document.uri.as_ref())
+            {
+                report(
+                    diagnostics_by_uri,
+                    document,
+                    range,
+                    DiagnosticSeverity::HINT,
+                    14,
+                    format!("Entry \"{}\" is never cited", key),
+                    Some(vec![DiagnosticTag::UNNECESSARY]),
+                );
+            }
+        }
+    }
+}
+
+fn bibtex_entry_keys(document: &Document) -> impl Iterator<Item = (String, TextRange)> + '_ {
+    document
+        .data
+        .as_bibtex()
+        .into_iter()
+        .flat_map(|data| data.root.children().filter_map(bibtex::Entry::cast))
+        .filter_map(|entry| entry.key())
+        .map(|key| (key.to_string(), key.small_range()))
+}
+
+/// Returns the key of a `\bibitem` so that documents with a `thebibliography` environment
+/// can be checked without a BibTeX file.
+fn bibitem_key(command: latex::GenericCommand) -> Option<(String, TextRange)> {
+    if command.name()?.text() != "\\bibitem" {
+        return None;
+    }
+
+    let group = command
+        .syntax()
+        .children()
+        .find_map(latex::CurlyGroup::cast)?;
+
+    let key: String = group
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| !matches!(token.kind(), latex::L_CURLY | latex::R_CURLY))
+        .map(|token| token.text())
+        .collect::<String>()
+        .trim()
+        .to_string();
+
+    if key.is_empty() {
+        None
+    } else {
+        Some((key, group.small_range()))
+    }
+}
+
+fn report(
+    diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
+    document: &Document,
+    range: TextRange,
+    severity: DiagnosticSeverity,
+    code: i32,
+    message: String,
+    tags: Option<Vec<DiagnosticTag>>,
+) {
+    let diagnostic = Diagnostic {
+        range: document.line_index.line_col_lsp_range(range),
+        severity: Some(severity),
+        code: Some(NumberOrString::Number(code)),
+        code_description: None,
+        source: Some("texlab".to_string()),
+        message,
+        related_information: None,
+        tags,
+        data: None,
+    };
+
+    insert_unique(diagnostics_by_uri, &document.uri, diagnostic);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::project::testing::{create_subset, messages};
+
+    use super::*;
+
+    fn analyze(files: &[(&str, &str)]) -> Vec<(String, String)> {
+        let subset = create_subset(files);
+        let mut diagnostics_by_uri = MultiMap::new();
+        let options = DiagnosticsOptions {
+            uncited_entries: true,
+        };
+        analyze_citation_static(&subset, &mut diagnostics_by_uri, &options);
+        messages(files, &diagnostics_by_uri)
+    }
+
+    #[test]
+    fn test_undefined_citation() {
+        let actual = analyze(&[
+            (
+                "main.tex",
+                "\\begin{document}\\cite{foo}\\cite{bar}\\bibliography{refs}\\end{document}",
+            ),
+            ("refs.bib", "@article{foo, title = {Foo}}"),
+        ]);
+
+        assert_eq!(
+            actual,
+            vec![("main.tex".into(), "Undefined citation key \"bar\"".into())]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_key_ignores_case() {
+        let actual = analyze(&[
+            (
+                "main.tex",
+                "\\begin{document}\\nocite{*}\\bibliography{refs}\\end{document}",
+            ),
+            (
+                "refs.bib",
+                "@article{foo, title = {Foo}}\n@book{Foo, title = {Bar}}",
+            ),
+        ]);
+
+        assert_eq!(
+            actual,
+            vec![
+                ("refs.bib".into(), "Duplicate entry key \"foo\"".into()),
+                ("refs.bib".into(), "Duplicate entry key \"Foo\"".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_uncited_entry() {
+        let actual = analyze(&[
+            (
+                "main.tex",
+                "\\begin{document}\\cite{foo}\\bibliography{refs}\\end{document}",
+            ),
+            (
+                "refs.bib",
+                "@article{foo, title = {Foo}}\n@book{bar, title = {Bar}}",
+            ),
+        ]);
+
+        assert_eq!(
+            actual,
+            vec![("refs.bib".into(), "Entry \"bar\" is never cited".into())]
+        );
+    }
+
+    #[test]
+    fn test_bibitem() {
+        let actual = analyze(&[(
+            "main.tex",
+            "\\begin{document}\\cite{foo}\\begin{thebibliography}{9}\\bibitem{foo} Foo\\end{thebibliography}\\end{document}",
+        )]);
+
+        assert_eq!(actual, Vec::new());
+    }
+
+    #[test]
+    fn test_bibitem_undefined_citation() {
+        let actual = analyze(&[(
+            "main.tex",
+            "\\begin{document}\\cite{bar}\\begin{thebibliography}{9}\\bibitem[Foo]{foo} Foo\\end{thebibliography}\\end{document}",
+        )]);
+
+        assert_eq!(
+            actual,
+            vec![("main.tex".into(), "Undefined citation key \"bar\"".into())]
+        );
+    }
+
+    #[test]
+    fn test_sibling_roots_sharing_bibliography() {
+        let actual = analyze(&[
+            (
+                "a.tex",
+                "\\begin{document}\\cite{foo}\\bibliography{refs}\\end{document}",
+            ),
+            (
+                "b.tex",
+                "\\begin{document}\\cite{bar}\\bibliography{refs}\\end{document}",
+            ),
+            (
+                "refs.bib",
+                "@article{foo, title = {Foo}}\n@book{bar, title = {Bar}}",
+            ),
+        ]);
+
+        assert_eq!(actual, Vec::new());
+    }
+
+    #[test]
+    fn test_separate_bibliographies() {
+        let actual = analyze(&[
+            (
+                "a.tex",
+                "\\begin{document}\\cite{bar}\\bibliography{a}\\end{document}",
+            ),
+            (
+                "b.tex",
+                "\\begin{document}\\cite{bar}\\bibliography{a,b}\\end{document}",
+            ),
+            ("a.bib", "@article{foo, title = {Foo}}"),
+            ("b.bib", "@book{bar, title = {Bar}}"),
+        ]);
+
+        assert_eq!(
+            actual,
+            vec![
+                ("a.tex".into(), "Undefined citation key \"bar\"".into()),
+                ("a.bib".into(), "Entry \"foo\" is never cited".into()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,27 @@
+use lsp_types::TextEdit;
+use serde::{Deserialize, Serialize};
+
+/// A quick fix that gets attached to a diagnostic through `Diagnostic::data`
+/// so that code actions do not need to analyze the document again.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct DiagnosticFix {
+    pub title: String,
+    pub edits: Vec<TextEdit>,
+}
+
+impl DiagnosticFix {
+    pub fn new(title: impl Into<String>, edits: Vec<TextEdit>) -> Self {
+        Self {
+            title: title.into(),
+            edits,
+        }
+    }
+
+    pub fn into_data(self) -> Option<serde_json::Value> {
+        serde_json::to_value(self).ok()
+    }
+
+    pub fn from_data(data: &serde_json::Value) -> Option<Self> {
+        serde_json::from_value(data.clone()).ok()
+    }
+}
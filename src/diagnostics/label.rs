@@ -0,0 +1,228 @@
+use std::sync::Arc;
+
+use lsp_types::{Diagnostic, DiagnosticSeverity, DiagnosticTag, NumberOrString};
+use multimap::MultiMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{syntax::latex, Document, LineIndexExt, Uri, WorkspaceSubset};
+
+use super::project::{find_projects, insert_unique};
+
+pub fn analyze_label_static(
+    subset: &WorkspaceSubset,
+    diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
+) {
+    // A label is only unused if none of the projects that include its document reference it.
+    let mut project_counts: FxHashMap<&Uri, usize> = FxHashMap::default();
+    let mut unused_counts: FxHashMap<(&Uri, &latex::LabelName), usize> = FxHashMap::default();
+
+    for project in find_projects(subset) {
+        let documents: Vec<_> = project
+            .documents
+            .iter()
+            .filter(|document| !document.uri.as_str().ends_with(".aux"))
+            .filter_map(|document| Some((*document, document.data.as_latex()?)))
+            .collect();
+
+        let mut definition_counts: FxHashMap<&str, usize> = FxHashMap::default();
+        let mut references = FxHashSet::default();
+        for (_, data) in &documents {
+            for name in &data.extras.label_names {
+                if name.is_definition {
+                    *definition_counts.entry(name.text.as_str()).or_default() += 1;
+                } else {
+                    references.insert(name.text.as_str());
+                }
+            }
+        }
+
+        for (document, data) in &documents {
+            *project_counts.entry(document.uri.as_ref()).or_default() += 1;
+            for name in &data.extras.label_names {
+                if name.is_definition {
+                    if definition_counts[name.text.as_str()] > 1 {
+                        report(
+                            diagnostics_by_uri,
+                            document,
+                            name,
+                            DiagnosticSeverity::WARNING,
+                            10,
+                            format!("Duplicate label \"{}\"", name.text),
+                            None,
+                        );
+                    } else if project.has_root && !references.contains(name.text.as_str()) {
+                        *unused_counts
+                            .entry((document.uri.as_ref(), name))
+                            .or_default() += 1;
+                    }
+                } else if project.has_root && !definition_counts.contains_key(name.text.as_str()) {
+                    report(
+                        diagnostics_by_uri,
+                        document,
+                        name,
+                        DiagnosticSeverity::WARNING,
+                        9,
+                        format!("Undefined reference \"{}\"", name.text),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    for document in &subset.documents {
+        if let Some(data) = document.data.as_latex() {
+            for name in &data.extras.label_names {
+                let unused_count = unused_counts.get(&(document.uri.as_ref(), name));
+                if unused_count.is_some()
+                    && unused_count == project_counts.get(document.uri.as_ref())
+                {
+                    report(
+                        diagnostics_by_uri,
+                        document,
+                        name,
+                        DiagnosticSeverity::HINT,
+                        11,
+                        format!("Unused label \"{}\"", name.text),
+                        Some(vec![DiagnosticTag::UNNECESSARY]),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn report(
+    diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
+    document: &Document,
+    name: &latex::LabelName,
+    severity: DiagnosticSeverity,
+    code: i32,
+    message: String,
+    tags: Option<Vec<DiagnosticTag>>,
+) {
+    let diagnostic = Diagnostic {
+        range: document.line_index.line_col_lsp_range(name.range),
+        severity: Some(severity),
+        code: Some(NumberOrString::Number(code)),
+        code_description: None,
+        source: Some("texlab".to_string()),
+        message,
+        related_information: None,
+        tags,
+        data: None,
+    };
+
+    insert_unique(diagnostics_by_uri, &document.uri, diagnostic);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::diagnostics::project::testing::{create_subset, messages};
+
+    use super::*;
+
+    fn analyze(files: &[(&str, &str)]) -> Vec<(String, String)> {
+        let subset = create_subset(files);
+        let mut diagnostics_by_uri = MultiMap::new();
+        analyze_label_static(&subset, &mut diagnostics_by_uri);
+        messages(files, &diagnostics_by_uri)
+    }
+
+    #[test]
+    fn test_duplicate_in_same_file() {
+        let actual = analyze(&[(
+            "main.tex",
+            "\\begin{document}\\label{foo}\\label{foo}\\ref{foo}\\end{document}",
+        )]);
+
+        let message = "Duplicate label \"foo\"".to_string();
+        assert_eq!(
+            actual,
+            vec![
+                ("main.tex".into(), message.clone()),
+                ("main.tex".into(), message)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_in_included_file() {
+        let actual = analyze(&[
+            (
+                "main.tex",
+                "\\begin{document}\\label{foo}\\input{chapter}\\end{document}",
+            ),
+            ("chapter.tex", "\\label{foo}\\ref{foo}"),
+        ]);
+
+        let message = "Duplicate label \"foo\"".to_string();
+        assert_eq!(
+            actual,
+            vec![
+                ("main.tex".into(), message.clone()),
+                ("chapter.tex".into(), message)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_reference() {
+        let actual = analyze(&[("main.tex", "\\begin{document}\\ref{foo}\\end{document}")]);
+
+        assert_eq!(
+            actual,
+            vec![("main.tex".into(), "Undefined reference \"foo\"".into())]
+        );
+    }
+
+    #[test]
+    fn test_unused_label() {
+        let actual = analyze(&[("main.tex", "\\begin{document}\\label{foo}\\end{document}")]);
+
+        assert_eq!(
+            actual,
+            vec![("main.tex".into(), "Unused label \"foo\"".into())]
+        );
+    }
+
+    #[test]
+    fn test_sibling_roots_sharing_bibliography() {
+        let actual = analyze(&[
+            (
+                "a.tex",
+                "\\begin{document}\\label{intro}\\ref{intro}\\bibliography{refs}\\end{document}",
+            ),
+            (
+                "b.tex",
+                "\\begin{document}\\label{intro}\\ref{intro}\\bibliography{refs}\\end{document}",
+            ),
+            ("refs.bib", "@article{foo, title = {Foo}}"),
+        ]);
+
+        assert_eq!(actual, Vec::new());
+    }
+
+    #[test]
+    fn test_reference_from_sibling_root() {
+        let actual = analyze(&[
+            (
+                "a.tex",
+                "\\begin{document}\\ref{intro}\\bibliography{refs}\\end{document}",
+            ),
+            (
+                "b.tex",
+                "\\begin{document}\\label{intro}\\bibliography{refs}\\end{document}",
+            ),
+            ("refs.bib", "@article{foo, title = {Foo}}"),
+        ]);
+
+        assert_eq!(
+            actual,
+            vec![
+                ("a.tex".into(), "Undefined reference \"intro\"".into()),
+                ("b.tex".into(), "Unused label \"intro\"".into()),
+            ]
+        );
+    }
+}
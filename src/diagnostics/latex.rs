@@ -1,10 +1,11 @@
 use std::sync::Arc;
 
 use cstree::TextRange;
-use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, TextEdit};
 use multimap::MultiMap;
 
 use crate::{
+    diagnostics::DiagnosticFix,
     syntax::{latex, CstNode},
     Document, LineIndexExt, Uri, Workspace,
 };
@@ -26,10 +27,16 @@ pub fn analyze_latex_static(
             .or_else(|| analyze_curly_group(&document, diagnostics_by_uri, node))
             .or_else(|| {
                 if node.kind() == latex::ERROR && node.first_token()?.text() == "}" {
+                    let range = document.line_index.line_col_lsp_range(node.text_range());
+                    let fix = DiagnosticFix::new(
+                        "Remove \"}\"",
+                        vec![TextEdit::new(range, String::new())],
+                    );
+
                     diagnostics_by_uri.insert(
                         Arc::clone(&document.uri),
                         Diagnostic {
-                            range: document.line_index.line_col_lsp_range(node.text_range()),
+                            range,
                             severity: Some(DiagnosticSeverity::ERROR),
                             code: Some(NumberOrString::Number(1)),
                             code_description: None,
@@ -37,7 +44,7 @@ pub fn analyze_latex_static(
                             message: "Unexpected \"}\"".to_string(),
                             related_information: None,
                             tags: None,
-                            data: None,
+                            data: fix.into_data(),
                         },
                     );
                     Some(())
@@ -59,6 +66,14 @@ fn analyze_environment(
     let name1 = environment.begin()?.name()?.key()?;
     let name2 = environment.end()?.name()?.key()?;
     if name1 != name2 {
+        let fix = DiagnosticFix::new(
+            format!("Change environment name to \"{}\"", name1.to_string()),
+            vec![TextEdit::new(
+                document.line_index.line_col_lsp_range(name2.small_range()),
+                name1.to_string(),
+            )],
+        );
+
         diagnostics_by_uri.insert(
             Arc::clone(&document.uri),
             Diagnostic {
@@ -70,7 +85,7 @@ fn analyze_environment(
                 message: "Mismatched environment".to_string(),
                 related_information: None,
                 tags: None,
-                data: None,
+                data: fix.into_data(),
             },
         );
     }
@@ -109,12 +124,15 @@ fn analyze_curly_group(
             .filter_map(|element| element.into_token())
             .any(|token| token.kind() == latex::R_CURLY)
     {
+        let range = document
+            .line_index
+            .line_col_lsp_range(TextRange::empty(node.text_range().end()));
+        let fix = DiagnosticFix::new("Insert \"}\"", vec![TextEdit::new(range, "}".into())]);
+
         diagnostics_by_uri.insert(
             Arc::clone(&document.uri),
             Diagnostic {
-                range: document
-                    .line_index
-                    .line_col_lsp_range(TextRange::empty(node.text_range().end())),
+                range,
                 severity: Some(DiagnosticSeverity::ERROR),
                 code: Some(NumberOrString::Number(2)),
                 code_description: None,
@@ -122,7 +140,7 @@ fn analyze_curly_group(
                 message: "Missing \"}\" inserted".to_string(),
                 related_information: None,
                 tags: None,
-                data: None,
+                data: fix.into_data(),
             },
         );
     }
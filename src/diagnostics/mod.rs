@@ -1,43 +1,121 @@
 mod bibtex;
 mod build_log;
 mod chktex;
+mod citation;
 mod debouncer;
+mod fix;
+mod label;
 mod latex;
+mod project;
+mod pull;
 
 use std::sync::Arc;
 
 use lsp_types::Diagnostic;
 use multimap::MultiMap;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 
 use crate::{Options, Uri, Workspace};
 
-pub use self::debouncer::{DiagnosticsDebouncer, DiagnosticsMessage};
+pub use self::{
+    debouncer::{DiagnosticsDebouncer, DiagnosticsMessage},
+    fix::DiagnosticFix,
+    pull::{
+        DiagnosticPullSupport, DocumentDiagnosticParams, DocumentDiagnosticReport,
+        PreviousResultId, WorkspaceDiagnosticParams, WorkspaceDiagnosticReport,
+        WorkspaceDocumentDiagnosticReport,
+    },
+};
 
 use self::{
     bibtex::analyze_bibtex_static, build_log::analyze_build_log_static,
-    chktex::analyze_latex_chktex, latex::analyze_latex_static,
+    chktex::analyze_latex_chktex, citation::analyze_citation_static, label::analyze_label_static,
+    latex::analyze_latex_static,
 };
 
+/// The diagnostics that were computed for all documents of a workspace subset.
+struct ProjectDiagnostics {
+    uris: FxHashSet<Arc<Uri>>,
+    diagnostics_by_uri: MultiMap<Arc<Uri>, Diagnostic>,
+}
+
 #[derive(Default)]
 pub struct DiagnosticsManager {
     static_diagnostics: FxHashMap<Arc<Uri>, MultiMap<Arc<Uri>, Diagnostic>>,
     chktex_diagnostics: MultiMap<Arc<Uri>, Diagnostic>,
+    project_diagnostics: Vec<ProjectDiagnostics>,
+    pulled_diagnostics: FxHashMap<Arc<Uri>, (String, Vec<Diagnostic>)>,
+    next_result_id: u64,
 }
 
 impl DiagnosticsManager {
-    pub fn update_static(&mut self, workspace: &dyn Workspace, uri: Arc<Uri>) {
+    pub fn update_static(&mut self, workspace: &dyn Workspace, uri: Arc<Uri>, options: &Options) {
         let mut diagnostics_by_uri = MultiMap::new();
         analyze_build_log_static(workspace, &mut diagnostics_by_uri, &uri);
         analyze_bibtex_static(workspace, &mut diagnostics_by_uri, &uri);
         analyze_latex_static(workspace, &mut diagnostics_by_uri, &uri);
+        self.update_project(workspace, &uri, options);
         self.static_diagnostics.insert(uri, diagnostics_by_uri);
     }
 
+    fn update_project(&mut self, workspace: &dyn Workspace, uri: &Arc<Uri>, options: &Options) {
+        if let Some(subset) = workspace.subset(Arc::clone(uri)) {
+            let uris: FxHashSet<Arc<Uri>> = subset
+                .documents
+                .iter()
+                .map(|document| Arc::clone(&document.uri))
+                .collect();
+
+            // Documents that dropped out of the subset lose their diagnostics as well.
+            self.project_diagnostics
+                .retain(|project| project.uris.is_disjoint(&uris));
+
+            let mut diagnostics_by_uri = MultiMap::new();
+            analyze_label_static(&subset, &mut diagnostics_by_uri);
+            analyze_citation_static(&subset, &mut diagnostics_by_uri, &options.diagnostics);
+            self.project_diagnostics.push(ProjectDiagnostics {
+                uris,
+                diagnostics_by_uri,
+            });
+        }
+    }
+
     pub fn update_chktex(&mut self, workspace: &dyn Workspace, uri: Arc<Uri>, options: &Options) {
         analyze_latex_chktex(workspace, &mut self.chktex_diagnostics, &uri, options);
     }
 
+    /// Forgets the diagnostics of documents that are no longer part of the workspace
+    /// and returns their URIs.
+    pub fn prune(&mut self, workspace: &dyn Workspace) -> Vec<Arc<Uri>> {
+        let removed_uris: FxHashSet<Arc<Uri>> = self
+            .static_diagnostics
+            .keys()
+            .chain(self.chktex_diagnostics.keys())
+            .chain(
+                self.project_diagnostics
+                    .iter()
+                    .flat_map(|project| project.diagnostics_by_uri.keys()),
+            )
+            .filter(|uri| !workspace.has(uri))
+            .cloned()
+            .collect();
+
+        for uri in &removed_uris {
+            self.static_diagnostics.remove(uri);
+            self.chktex_diagnostics.remove(uri);
+            self.pulled_diagnostics.remove(uri);
+        }
+
+        for project in &mut self.project_diagnostics {
+            project.uris.retain(|uri| !removed_uris.contains(uri));
+            for uri in &removed_uris {
+                project.diagnostics_by_uri.remove(uri);
+            }
+        }
+
+        removed_uris.into_iter().collect()
+    }
+
     pub fn publish(&self, uri: Arc<Uri>) -> Vec<Diagnostic> {
         let mut all_diagnostics = Vec::new();
         for diagnostics_by_uri in self.static_diagnostics.values() {
@@ -46,10 +124,332 @@ impl DiagnosticsManager {
             }
         }
 
+        for project in &self.project_diagnostics {
+            if let Some(diagnostics) = project.diagnostics_by_uri.get_vec(&uri) {
+                all_diagnostics.append(&mut diagnostics.clone());
+            }
+        }
+
         if let Some(diagnostics) = self.chktex_diagnostics.get_vec(&uri) {
             all_diagnostics.append(&mut diagnostics.clone());
         }
 
         all_diagnostics
     }
+
+    /// Returns `true` if the client has pulled the diagnostics of the document.
+    /// These diagnostics must not be published as well.
+    pub fn is_pulled(&self, uri: &Uri) -> bool {
+        self.pulled_diagnostics.contains_key(uri)
+    }
+
+    /// Returns the diagnostics of a document unless they are the same as the ones
+    /// the client already has under `previous_result_id`.
+    pub fn pull(
+        &mut self,
+        uri: Arc<Uri>,
+        previous_result_id: Option<&str>,
+    ) -> DocumentDiagnosticReport {
+        let diagnostics = self.publish(Arc::clone(&uri));
+        let result_id = match self.pulled_diagnostics.get(&uri) {
+            Some((result_id, last_diagnostics)) if *last_diagnostics == diagnostics => {
+                result_id.clone()
+            }
+            _ => {
+                self.next_result_id += 1;
+                let result_id = self.next_result_id.to_string();
+                self.pulled_diagnostics
+                    .insert(uri, (result_id.clone(), diagnostics.clone()));
+                result_id
+            }
+        };
+
+        if previous_result_id == Some(result_id.as_str()) {
+            DocumentDiagnosticReport::Unchanged { result_id }
+        } else {
+            DocumentDiagnosticReport::Full {
+                result_id: Some(result_id),
+                items: diagnostics,
+            }
+        }
+    }
+
+    /// Returns the diagnostics of all documents in the workspace.
+    /// Documents that have been removed since the last pull lose their diagnostics.
+    pub fn pull_workspace(
+        &mut self,
+        workspace: &dyn Workspace,
+        previous_result_ids: &[PreviousResultId],
+    ) -> WorkspaceDiagnosticReport {
+        let mut items: Vec<_> = workspace
+            .documents()
+            .into_iter()
+            .map(|document| {
+                let previous_result_id = previous_result_ids
+                    .iter()
+                    .find(|previous| Uri::from(previous.uri.clone()) == *document.uri)
+                    .map(|previous| previous.value.as_str());
+
+                let report = self.pull(Arc::clone(&document.uri), previous_result_id);
+                WorkspaceDocumentDiagnosticReport {
+                    uri: document.uri.as_ref().clone().into(),
+                    version: None,
+                    report,
+                }
+            })
+            .collect();
+
+        for previous in previous_result_ids {
+            let uri = Uri::from(previous.uri.clone());
+            if !workspace.has(&uri) {
+                items.push(WorkspaceDocumentDiagnosticReport {
+                    uri: previous.uri.clone(),
+                    version: None,
+                    report: DocumentDiagnosticReport::Full {
+                        result_id: None,
+                        items: Vec::new(),
+                    },
+                });
+            }
+        }
+
+        WorkspaceDiagnosticReport { items }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Url;
+
+    use crate::{
+        diagnostics::project::testing::{create_workspace, uri},
+        DocumentLanguage, WorkspaceSource,
+    };
+
+    use super::*;
+
+    fn update(workspace: &dyn Workspace, manager: &mut DiagnosticsManager, name: &str) {
+        manager.update_static(workspace, Arc::new(uri(name)), &Options::default());
+    }
+
+    #[test]
+    fn test_project_diagnostics_of_excluded_document() {
+        let workspace = create_workspace(&[
+            ("chapter.tex", "\\ref{foo}"),
+            (
+                "main.tex",
+                "\\begin{document}\\input{chapter}\\end{document}",
+            ),
+        ]);
+
+        let mut manager = DiagnosticsManager::default();
+        update(&workspace, &mut manager, "main.tex");
+        assert_eq!(manager.publish(Arc::new(uri("chapter.tex"))).len(), 1);
+
+        workspace.open(
+            Arc::new(uri("main.tex")),
+            "\\begin{document}\\end{document}".into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Client,
+        );
+        update(&workspace, &mut manager, "main.tex");
+        assert_eq!(manager.publish(Arc::new(uri("chapter.tex"))), Vec::new());
+    }
+
+    #[test]
+    fn test_pull_unchanged() {
+        let workspace =
+            create_workspace(&[("main.tex", "\\begin{document}\\ref{foo}\\end{document}")]);
+
+        let mut manager = DiagnosticsManager::default();
+        update(&workspace, &mut manager, "main.tex");
+        let uri = Arc::new(uri("main.tex"));
+        let items = match manager.pull(Arc::clone(&uri), None) {
+            DocumentDiagnosticReport::Full { result_id, items } => {
+                assert_eq!(result_id.as_deref(), Some("1"));
+                items
+            }
+            report => panic!("unexpected report: {:?}", report),
+        };
+        assert_eq!(items.len(), 1);
+
+        update(&workspace, &mut manager, "main.tex");
+        assert_eq!(
+            manager.pull(Arc::clone(&uri), Some("1")),
+            DocumentDiagnosticReport::Unchanged {
+                result_id: "1".into()
+            }
+        );
+
+        assert_eq!(
+            manager.pull(uri, Some("0")),
+            DocumentDiagnosticReport::Full {
+                result_id: Some("1".into()),
+                items,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pull_changed() {
+        let workspace =
+            create_workspace(&[("main.tex", "\\begin{document}\\ref{foo}\\end{document}")]);
+
+        let mut manager = DiagnosticsManager::default();
+        update(&workspace, &mut manager, "main.tex");
+        let uri = Arc::new(uri("main.tex"));
+        manager.pull(Arc::clone(&uri), None);
+
+        workspace.open(
+            Arc::clone(&uri),
+            "\\begin{document}\\label{foo}\\ref{foo}\\end{document}".into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Client,
+        );
+        update(&workspace, &mut manager, "main.tex");
+        assert_eq!(
+            manager.pull(uri, Some("1")),
+            DocumentDiagnosticReport::Full {
+                result_id: Some("2".into()),
+                items: Vec::new(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_pull_after_prune() {
+        let text = "\\begin{document}\\ref{foo}\\end{document}";
+        let workspace = create_workspace(&[]);
+        let uri = Arc::new(uri("main.tex"));
+        workspace.open(
+            Arc::clone(&uri),
+            text.into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Server,
+        );
+
+        let mut manager = DiagnosticsManager::default();
+        update(&workspace, &mut manager, "main.tex");
+        manager.pull(Arc::clone(&uri), None);
+
+        workspace.remove(&uri);
+        assert_eq!(manager.prune(&workspace), vec![Arc::clone(&uri)]);
+        assert_eq!(manager.publish(Arc::clone(&uri)), Vec::new());
+
+        // The client must not be told that the diagnostics of the new document are unchanged.
+        workspace.open(
+            Arc::clone(&uri),
+            text.into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Server,
+        );
+        update(&workspace, &mut manager, "main.tex");
+        match manager.pull(uri, Some("1")) {
+            DocumentDiagnosticReport::Full { result_id, items } => {
+                assert_eq!(result_id.as_deref(), Some("2"));
+                assert_eq!(items.len(), 1);
+            }
+            report => panic!("unexpected report: {:?}", report),
+        }
+    }
+
+    #[test]
+    fn test_prune() {
+        let workspace =
+            create_workspace(&[("main.tex", "\\begin{document}\\ref{foo}\\end{document}")]);
+        let chapter_uri = Arc::new(uri("chapter.tex"));
+        workspace.open(
+            Arc::clone(&chapter_uri),
+            "\\begin{document}\\ref{bar}\\end{document}".into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Server,
+        );
+
+        let mut manager = DiagnosticsManager::default();
+        update(&workspace, &mut manager, "main.tex");
+        update(&workspace, &mut manager, "chapter.tex");
+        assert_eq!(manager.prune(&workspace), Vec::new());
+        assert_eq!(manager.publish(Arc::clone(&chapter_uri)).len(), 1);
+
+        workspace.remove(&chapter_uri);
+        assert_eq!(manager.prune(&workspace), vec![Arc::clone(&chapter_uri)]);
+        assert_eq!(manager.publish(chapter_uri), Vec::new());
+        assert_eq!(manager.publish(Arc::new(uri("main.tex"))).len(), 1);
+    }
+
+    #[test]
+    fn test_pull_workspace() {
+        let workspace = create_workspace(&[
+            ("chapter.tex", "\\ref{foo}"),
+            (
+                "main.tex",
+                "\\begin{document}\\input{chapter}\\end{document}",
+            ),
+        ]);
+
+        let mut manager = DiagnosticsManager::default();
+        update(&workspace, &mut manager, "main.tex");
+        let chapter_uri = uri("chapter.tex");
+        assert!(!manager.is_pulled(&chapter_uri));
+
+        let report = manager.pull_workspace(&workspace, &[]);
+        assert!(manager.is_pulled(&chapter_uri));
+        let previous_result_ids: Vec<_> = report
+            .items
+            .into_iter()
+            .map(|item| match item.report {
+                DocumentDiagnosticReport::Full { result_id, items } => {
+                    let expected_len = if Uri::from(item.uri.clone()) == chapter_uri {
+                        1
+                    } else {
+                        0
+                    };
+                    assert_eq!(items.len(), expected_len);
+                    PreviousResultId {
+                        uri: item.uri,
+                        value: result_id.unwrap(),
+                    }
+                }
+                report => panic!("unexpected report: {:?}", report),
+            })
+            .collect();
+        assert_eq!(previous_result_ids.len(), 2);
+
+        let report = manager.pull_workspace(&workspace, &previous_result_ids);
+        assert_eq!(report.items.len(), 2);
+        assert!(report
+            .items
+            .iter()
+            .all(|item| matches!(item.report, DocumentDiagnosticReport::Unchanged { .. })));
+    }
+
+    #[test]
+    fn test_pull_workspace_removed_document() {
+        let workspace = create_workspace(&[("main.tex", "\\begin{document}\\end{document}")]);
+        let mut manager = DiagnosticsManager::default();
+        update(&workspace, &mut manager, "main.tex");
+
+        let removed_uri: Url = uri("removed.tex").into();
+        let report = manager.pull_workspace(
+            &workspace,
+            &[PreviousResultId {
+                uri: removed_uri.clone(),
+                value: "1".into(),
+            }],
+        );
+
+        let item = report
+            .items
+            .into_iter()
+            .find(|item| item.uri == removed_uri)
+            .unwrap();
+
+        assert_eq!(
+            item.report,
+            DocumentDiagnosticReport::Full {
+                result_id: None,
+                items: Vec::new(),
+            }
+        );
+    }
 }
@@ -0,0 +1,134 @@
+use std::sync::Arc;
+
+use lsp_types::Diagnostic;
+use multimap::MultiMap;
+
+use crate::{Document, Uri, WorkspaceSubset};
+
+/// A root document together with the documents it includes.
+pub struct Project<'a> {
+    pub documents: Vec<&'a Document>,
+    pub has_root: bool,
+}
+
+/// Splits a workspace subset into the documents that are reachable from each root document
+/// through explicit links. Without any root document, the whole subset forms a single project.
+pub fn find_projects(subset: &WorkspaceSubset) -> Vec<Project> {
+    let roots: Vec<&Document> = subset
+        .documents
+        .iter()
+        .filter(|document| {
+            document
+                .data
+                .as_latex()
+                .map_or(false, |data| data.extras.has_document_environment)
+        })
+        .map(AsRef::as_ref)
+        .collect();
+
+    if roots.is_empty() {
+        return vec![Project {
+            documents: subset.documents.iter().map(AsRef::as_ref).collect(),
+            has_root: false,
+        }];
+    }
+
+    roots
+        .into_iter()
+        .map(|root| {
+            let mut documents = vec![root];
+            let mut i = 0;
+            while i < documents.len() {
+                if let Some(data) = documents[i].data.as_latex() {
+                    for link in &data.extras.explicit_links {
+                        let target = link.targets.iter().find_map(|target| {
+                            subset
+                                .documents
+                                .iter()
+                                .find(|document| document.uri == *target)
+                        });
+
+                        if let Some(target) = target {
+                            if !documents.iter().any(|document| document.uri == target.uri) {
+                                documents.push(target);
+                            }
+                        }
+                    }
+                }
+
+                i += 1;
+            }
+
+            Project {
+                documents,
+                has_root: true,
+            }
+        })
+        .collect()
+}
+
+/// Adds a diagnostic unless the document already has the same one,
+/// which happens for documents that belong to several projects.
+pub fn insert_unique(
+    diagnostics_by_uri: &mut MultiMap<Arc<Uri>, Diagnostic>,
+    uri: &Arc<Uri>,
+    diagnostic: Diagnostic,
+) {
+    if !diagnostics_by_uri
+        .get_vec(uri)
+        .map_or(false, |diagnostics| diagnostics.contains(&diagnostic))
+    {
+        diagnostics_by_uri.insert(Arc::clone(uri), diagnostic);
+    }
+}
+
+#[cfg(test)]
+pub mod testing {
+    use std::env;
+
+    use crate::{
+        create_workspace_fast, DocumentLanguage, ServerContext, Workspace, WorkspaceSource,
+    };
+
+    use super::*;
+
+    pub fn uri(name: &str) -> Uri {
+        Uri::from_file_path(env::temp_dir().join(name)).unwrap()
+    }
+
+    pub fn create_workspace(files: &[(&str, &str)]) -> impl Workspace {
+        let context = Arc::new(ServerContext::new(env::temp_dir()));
+        let workspace = create_workspace_fast(context).unwrap();
+        for (name, text) in files {
+            let path = env::temp_dir().join(name);
+            workspace.open(
+                Arc::new(uri(name)),
+                text.to_string(),
+                DocumentLanguage::by_path(&path).unwrap(),
+                WorkspaceSource::Client,
+            );
+        }
+        workspace
+    }
+
+    /// Opens the given files and returns the subset of the first one.
+    pub fn create_subset(files: &[(&str, &str)]) -> WorkspaceSubset {
+        create_workspace(files)
+            .subset(Arc::new(uri(files[0].0)))
+            .unwrap()
+    }
+
+    /// Returns the file names and messages of the diagnostics in the order of the given files.
+    pub fn messages(
+        files: &[(&str, &str)],
+        diagnostics_by_uri: &MultiMap<Arc<Uri>, Diagnostic>,
+    ) -> Vec<(String, String)> {
+        let mut messages = Vec::new();
+        for (name, _) in files {
+            for diagnostic in diagnostics_by_uri.get_vec(&uri(name)).into_iter().flatten() {
+                messages.push((name.to_string(), diagnostic.message.clone()));
+            }
+        }
+        messages
+    }
+}
@@ -0,0 +1,89 @@
+use lsp_types::{
+    Diagnostic, PartialResultParams, TextDocumentIdentifier, Url, WorkDoneProgressParams,
+};
+use serde::{Deserialize, Serialize};
+
+/// The diagnostic capabilities of the client, which `lsp-types` does not know about yet.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct DiagnosticPullSupport {
+    pub enabled: bool,
+    pub refresh: bool,
+}
+
+impl DiagnosticPullSupport {
+    pub fn from_capabilities(capabilities: &serde_json::Value) -> Self {
+        Self {
+            enabled: capabilities["textDocument"]["diagnostic"].is_object(),
+            refresh: capabilities["workspace"]["diagnostics"]["refreshSupport"] == true,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentDiagnosticParams {
+    pub text_document: TextDocumentIdentifier,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub previous_result_id: Option<String>,
+
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+
+    #[serde(flatten)]
+    pub partial_result_params: PartialResultParams,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum DocumentDiagnosticReport {
+    #[serde(rename_all = "camelCase")]
+    Full {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result_id: Option<String>,
+        items: Vec<Diagnostic>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Unchanged { result_id: String },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreviousResultId {
+    pub uri: Url,
+    pub value: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDiagnosticParams {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identifier: Option<String>,
+
+    pub previous_result_ids: Vec<PreviousResultId>,
+
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+
+    #[serde(flatten)]
+    pub partial_result_params: PartialResultParams,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDocumentDiagnosticReport {
+    pub uri: Url,
+    pub version: Option<i32>,
+
+    #[serde(flatten)]
+    pub report: DocumentDiagnosticReport,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceDiagnosticReport {
+    pub items: Vec<WorkspaceDocumentDiagnosticReport>,
+}
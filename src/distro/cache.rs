@@ -0,0 +1,123 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{anyhow, Result};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+
+use super::kpsewhich::Resolver;
+
+const CACHE_FILE_NAME: &str = "resolver.json";
+
+/// Identifies the state of the file databases the resolver has been built from.
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+pub struct CacheKey {
+    root_directories: Vec<PathBuf>,
+    database_times: Vec<(PathBuf, SystemTime)>,
+}
+
+impl CacheKey {
+    pub fn new(root_directories: Vec<PathBuf>, database_files: Vec<PathBuf>) -> Self {
+        let database_times = database_files
+            .into_iter()
+            .filter_map(|file| {
+                let time = fs::metadata(&file).and_then(|meta| meta.modified()).ok()?;
+                Some((file, time))
+            })
+            .collect();
+
+        Self {
+            root_directories,
+            database_times,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheData {
+    key: CacheKey,
+    files_by_name: FxHashMap<SmolStr, PathBuf>,
+}
+
+/// Reads the resolver of the last run without checking whether it is still up to date.
+pub fn load() -> Option<(CacheKey, Resolver)> {
+    load_from(&cache_file()?)
+}
+
+pub fn store(key: CacheKey, resolver: &Resolver) -> Result<()> {
+    let file = cache_file().ok_or_else(|| anyhow!("no cache directory available"))?;
+    store_to(&file, key, resolver)
+}
+
+fn load_from(file: &Path) -> Option<(CacheKey, Resolver)> {
+    let text = fs::read_to_string(file).ok()?;
+    let data: CacheData = serde_json::from_str(&text).ok()?;
+    Some((data.key, Resolver::new(data.files_by_name)))
+}
+
+fn store_to(file: &Path, key: CacheKey, resolver: &Resolver) -> Result<()> {
+    fs::create_dir_all(file.parent().unwrap())?;
+
+    let data = CacheData {
+        key,
+        files_by_name: resolver.files_by_name.clone(),
+    };
+
+    // Write to a temporary file first so that a concurrent server never reads a partial cache.
+    let temp_file = file.with_extension(format!("{}.tmp", std::process::id()));
+    fs::write(&temp_file, serde_json::to_vec(&data)?)?;
+    fs::rename(&temp_file, file)?;
+    Ok(())
+}
+
+fn cache_file() -> Option<PathBuf> {
+    dirs::cache_dir().map(|dir| dir.join("texlab").join(CACHE_FILE_NAME))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn create_resolver(directory: &Path) -> Resolver {
+        let mut files_by_name = FxHashMap::default();
+        files_by_name.insert("article.cls".into(), directory.join("article.cls"));
+        Resolver::new(files_by_name)
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let directory = tempdir().unwrap();
+        let database_file = directory.path().join("ls-R");
+        fs::write(&database_file, "").unwrap();
+        let key = CacheKey::new(vec![directory.path().into()], vec![database_file]);
+        let resolver = create_resolver(directory.path());
+
+        let file = directory.path().join("cache").join(CACHE_FILE_NAME);
+        store_to(&file, key.clone(), &resolver).unwrap();
+        assert_eq!(load_from(&file), Some((key, resolver)));
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        let directory = tempdir().unwrap();
+        assert_eq!(load_from(&directory.path().join(CACHE_FILE_NAME)), None);
+    }
+
+    #[test]
+    fn test_changed_database() {
+        let directory = tempdir().unwrap();
+        let database_file = directory.path().join("ls-R");
+        let root_directories = vec![directory.path().to_path_buf()];
+        let old_key = CacheKey::new(root_directories.clone(), vec![database_file.clone()]);
+
+        fs::write(&database_file, "").unwrap();
+        let new_key = CacheKey::new(root_directories, vec![database_file]);
+        assert_ne!(old_key, new_key);
+    }
+}
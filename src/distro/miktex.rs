@@ -10,9 +10,8 @@ use byteorder::{LittleEndian, ReadBytesExt};
 
 use super::kpsewhich::{self, Resolver};
 
-pub fn load_resolver() -> Result<Resolver> {
-    let root_directories = kpsewhich::root_directories()?;
-    let resolver = kpsewhich::parse_database(&root_directories, read_database)?;
+pub fn load_resolver(root_directories: &[PathBuf]) -> Result<Resolver> {
+    let resolver = kpsewhich::parse_database(root_directories, read_database)?;
     Ok(resolver)
 }
 
@@ -23,6 +22,15 @@ const FNDB_TABLE_POINTER_OFFSET: usize = 4 * FNDB_WORD_SIZE;
 const FNDB_TABLE_SIZE_OFFSET: usize = 6 * FNDB_WORD_SIZE;
 const FNDB_ENTRY_SIZE: usize = 4 * FNDB_WORD_SIZE;
 
+pub fn database_files(root_directories: &[PathBuf]) -> Vec<PathBuf> {
+    root_directories
+        .iter()
+        .filter_map(|directory| fs::read_dir(directory.join(DATABASE_PATH)).ok())
+        .flat_map(|entries| entries.filter_map(Result::ok).map(|entry| entry.path()))
+        .filter(|file| file.extension().and_then(OsStr::to_str) == Some("fndb-5"))
+        .collect()
+}
+
 fn read_database(directory: &Path) -> Result<Vec<PathBuf>> {
     let database_directory = directory.join(DATABASE_PATH);
     if !database_directory.exists() {
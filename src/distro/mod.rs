@@ -1,8 +1,12 @@
+mod cache;
 mod kpsewhich;
 mod miktex;
 mod texlive;
 
-use std::process::{Command, Stdio};
+use std::{
+    path::PathBuf,
+    process::{Command, Stdio},
+};
 
 use anyhow::Result;
 use derive_more::Display;
@@ -22,6 +26,24 @@ pub enum DistributionKind {
     Unknown,
 }
 
+/// A resolver of a previous run together with the state of the file databases it was built from.
+#[derive(Debug)]
+pub struct CachedResolver {
+    key: cache::CacheKey,
+    pub resolver: Resolver,
+}
+
+impl CachedResolver {
+    /// Returns the resolver unless the file databases have changed since it was built.
+    fn into_current(self, key: &cache::CacheKey) -> Option<Resolver> {
+        if self.key == *key {
+            Some(self.resolver)
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Distribution {
     pub kind: DistributionKind,
@@ -29,7 +51,9 @@ pub struct Distribution {
 }
 
 impl Distribution {
-    pub fn detect() -> Self {
+    /// Detects the installed distribution and loads its files.
+    /// The `cached` resolver is reused if the file databases have not changed.
+    pub fn detect(cached: Option<CachedResolver>) -> Self {
         let kind = match Command::new("latex").arg("--version").output() {
             Ok(output) => {
                 let stdout = String::from_utf8_lossy(&output.stdout);
@@ -57,18 +81,81 @@ impl Distribution {
         };
 
         let resolver = match kind {
-            DistributionKind::Texlive => Self::load_resolver(texlive::load_resolver),
-            DistributionKind::Miktex => Self::load_resolver(miktex::load_resolver),
+            DistributionKind::Texlive => {
+                Self::load_resolver(cached, texlive::database_files, texlive::load_resolver)
+            }
+            DistributionKind::Miktex => {
+                Self::load_resolver(cached, miktex::database_files, miktex::load_resolver)
+            }
             DistributionKind::Tectonic | DistributionKind::Unknown => Resolver::default(),
         };
         Self { kind, resolver }
     }
 
-    fn load_resolver(loader: impl FnOnce() -> Result<Resolver>) -> Resolver {
-        match loader() {
+    /// Returns the resolver of the last run, which may be outdated.
+    /// It is available immediately while `detect` still has to query the distribution.
+    pub fn cached_resolver() -> Option<CachedResolver> {
+        cache::load().map(|(key, resolver)| CachedResolver { key, resolver })
+    }
+
+    fn load_resolver(
+        cached: Option<CachedResolver>,
+        database_files: fn(&[PathBuf]) -> Vec<PathBuf>,
+        loader: fn(&[PathBuf]) -> Result<Resolver>,
+    ) -> Resolver {
+        let result = kpsewhich::root_directories().and_then(|root_directories| {
+            let files = database_files(&root_directories);
+            let key = cache::CacheKey::new(root_directories.clone(), files);
+            match cached.and_then(|cached| cached.into_current(&key)) {
+                Some(resolver) => Ok(resolver),
+                None => {
+                    let resolver = loader(&root_directories)?;
+                    if let Err(why) = cache::store(key, &resolver) {
+                        warn!("Failed to cache resolver: {}", why);
+                    }
+                    Ok(resolver)
+                }
+            }
+        });
+
+        match result {
             Ok(resolver) => return resolver,
             Err(why) => warn!("Failed to load resolver: {}", why),
         };
         Resolver::default()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use super::*;
+
+    #[test]
+    fn test_cached_resolver_up_to_date() {
+        let directory = tempdir().unwrap();
+        let key = cache::CacheKey::new(vec![directory.path().into()], Vec::new());
+        let cached = CachedResolver {
+            key: key.clone(),
+            resolver: Resolver::default(),
+        };
+
+        assert_eq!(cached.into_current(&key), Some(Resolver::default()));
+    }
+
+    #[test]
+    fn test_cached_resolver_stale() {
+        let directory = tempdir().unwrap();
+        let database_file = directory.path().join("ls-R");
+        let root_directories = vec![directory.path().to_path_buf()];
+        let cached = CachedResolver {
+            key: cache::CacheKey::new(root_directories.clone(), vec![database_file.clone()]),
+            resolver: Resolver::default(),
+        };
+
+        std::fs::write(&database_file, "").unwrap();
+        let key = cache::CacheKey::new(root_directories, vec![database_file]);
+        assert_eq!(cached.into_current(&key), None);
+    }
+}
@@ -8,14 +8,21 @@ use anyhow::Result;
 
 use super::kpsewhich::{self, Resolver};
 
-pub fn load_resolver() -> Result<Resolver> {
-    let root_directories = kpsewhich::root_directories()?;
-    let resolver = kpsewhich::parse_database(&root_directories, read_database)?;
+pub fn load_resolver(root_directories: &[PathBuf]) -> Result<Resolver> {
+    let resolver = kpsewhich::parse_database(root_directories, read_database)?;
     Ok(resolver)
 }
 
 const DATABASE_PATH: &str = "ls-R";
 
+pub fn database_files(root_directories: &[PathBuf]) -> Vec<PathBuf> {
+    root_directories
+        .iter()
+        .map(|directory| directory.join(DATABASE_PATH))
+        .filter(|file| file.is_file())
+        .collect()
+}
+
 fn read_database(directory: &Path) -> Result<Vec<PathBuf>> {
     let file = directory.join(DATABASE_PATH);
     if !file.is_file() {
@@ -1,13 +1,14 @@
 use std::{
-    io::{BufRead, BufReader, Read},
+    io::{self, BufRead, BufReader, Read},
     path::Path,
-    process::{Command, Stdio},
+    process::{Child, Command, Stdio},
     sync::{Arc, Mutex},
     thread::{self, JoinHandle},
+    time::Duration,
 };
 
 use anyhow::Result;
-use cancellation::CancellationToken;
+use cancellation::{CancellationToken, CancellationTokenSource};
 use crossbeam_channel::{Receiver, Sender};
 use dashmap::DashMap;
 use encoding_rs_io::DecodeReaderBytesBuilder;
@@ -17,11 +18,15 @@ use lsp_types::{
     TextDocumentIdentifier, TextDocumentPositionParams, WorkDoneProgress, WorkDoneProgressBegin,
     WorkDoneProgressCreateParams, WorkDoneProgressEnd,
 };
+use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 use uuid::Uuid;
 
-use crate::{client, req_queue::ReqQueue, ClientCapabilitiesExt, DocumentLanguage, Uri};
+use crate::{
+    client, req_queue::ReqQueue, BuildOptions, ClientCapabilitiesExt, Document, DocumentLanguage,
+    Uri, Workspace,
+};
 
 use super::{forward_search, FeatureRequest};
 
@@ -71,7 +76,7 @@ impl<'a> ProgressReporter<'a> {
                         WorkDoneProgressBegin {
                             title: "Building".to_string(),
                             message: Some(uri.as_str().to_string()),
-                            cancellable: Some(false),
+                            cancellable: Some(true),
                             percentage: None,
                         },
                     )),
@@ -98,9 +103,106 @@ impl<'a> Drop for ProgressReporter<'a> {
     }
 }
 
+struct ContinuousBuild {
+    pid: u32,
+    status: Arc<Mutex<Option<BuildStatus>>>,
+    exit_sender: Sender<()>,
+    options: BuildOptions,
+}
+
+impl ContinuousBuild {
+    fn start(
+        mut command: Command,
+        options: BuildOptions,
+        lsp_sender: &Sender<lsp_server::Message>,
+        on_line: impl Fn(&str) + Send + 'static,
+    ) -> Result<Self> {
+        let mut process = spawn(&mut command)?;
+        let (exit_sender, exit_receiver) = crossbeam_channel::bounded(1);
+        capture_output(&mut process, lsp_sender, exit_receiver, on_line);
+
+        // The process is reaped as soon as it exits, even if nobody stops the build.
+        let pid = process.id();
+        let status: Arc<Mutex<Option<BuildStatus>>> = Arc::default();
+        {
+            let status = Arc::clone(&status);
+            thread::spawn(move || {
+                let exit_status = match process.wait() {
+                    Ok(exit_status) if exit_status.success() => BuildStatus::SUCCESS,
+                    Ok(_) => BuildStatus::ERROR,
+                    Err(_) => BuildStatus::FAILURE,
+                };
+                *status.lock().unwrap() = Some(exit_status);
+            });
+        }
+
+        Ok(Self {
+            pid,
+            status,
+            exit_sender,
+            options,
+        })
+    }
+
+    fn status(&self) -> Option<BuildStatus> {
+        *self.status.lock().unwrap()
+    }
+
+    fn is_running(&self) -> bool {
+        self.status().is_none()
+    }
+
+    /// Gives the process some time to fail at startup
+    /// and reports the result of the build if it has already exited.
+    fn startup_status(&self) -> BuildStatus {
+        for _ in 0..10 {
+            if let Some(status) = self.status() {
+                return status;
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        BuildStatus::SUCCESS
+    }
+
+    fn stop(self) {
+        if self.is_running() {
+            kill_process_tree(self.pid);
+        }
+
+        let _ = self.exit_sender.send(());
+    }
+}
+
+struct RunningBuild {
+    progress_token: String,
+    token_source: CancellationTokenSource,
+}
+
+struct RunningBuildGuard<'a> {
+    engine: &'a BuildEngine,
+    uri: Arc<Uri>,
+    progress_token: &'a str,
+}
+
+impl<'a> Drop for RunningBuildGuard<'a> {
+    fn drop(&mut self) {
+        let mut running_builds = self.engine.running_builds.lock().unwrap();
+        if running_builds
+            .get(&self.uri)
+            .map_or(false, |build| build.progress_token == self.progress_token)
+        {
+            running_builds.remove(&self.uri);
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct BuildEngine {
     lock: Mutex<()>,
+    running_builds: Mutex<FxHashMap<Arc<Uri>, RunningBuild>>,
+    continuous_builds: Mutex<FxHashMap<Arc<Uri>, ContinuousBuild>>,
     pub positions_by_uri: DashMap<Arc<Uri>, Position>,
 }
 
@@ -112,8 +214,6 @@ impl BuildEngine {
         req_queue: &Mutex<ReqQueue>,
         lsp_sender: &Sender<lsp_server::Message>,
     ) -> Result<BuildResult> {
-        let lock = self.lock.lock().unwrap();
-
         let document = request
             .subset
             .documents
@@ -141,6 +241,13 @@ impl BuildEngine {
         }
         let path = document.uri.to_file_path().unwrap();
 
+        let options = request.context.options_for(&document.uri);
+        if options.build.is_continuous {
+            let status =
+                self.build_continuously(&request, document, &path, &options.build, lsp_sender)?;
+            return Ok(BuildResult { status });
+        }
+
         let supports_progress = {
             request
                 .context
@@ -151,6 +258,31 @@ impl BuildEngine {
         };
 
         let token = format!("texlab-build-{}", Uuid::new_v4());
+        let token_source = CancellationTokenSource::new();
+        let build_token = Arc::clone(token_source.token());
+        if let Some(stale_build) = self.running_builds.lock().unwrap().insert(
+            Arc::clone(&document.uri),
+            RunningBuild {
+                progress_token: token.clone(),
+                token_source,
+            },
+        ) {
+            stale_build.token_source.cancel();
+        }
+
+        let _guard = RunningBuildGuard {
+            engine: self,
+            uri: Arc::clone(&document.uri),
+            progress_token: &token,
+        };
+
+        let lock = self.lock.lock().unwrap();
+        if build_token.is_canceled() || cancellation_token.is_canceled() {
+            return Ok(BuildResult {
+                status: BuildStatus::CANCELLED,
+            });
+        }
+
         let progress_reporter = ProgressReporter {
             supports_progress,
             req_queue,
@@ -159,13 +291,11 @@ impl BuildEngine {
         };
         progress_reporter.start(&document.uri)?;
 
-        let options = { request.context.options.read().unwrap().clone() };
-
         let build_dir = options
             .root_directory
             .as_ref()
-            .map(AsRef::as_ref)
-            .or_else(|| path.parent())
+            .map(|root_dir| request.context.directory_for(&document.uri).join(root_dir))
+            .or_else(|| path.parent().map(ToOwned::to_owned))
             .unwrap();
 
         let args: Vec<_> = options
@@ -175,31 +305,40 @@ impl BuildEngine {
             .map(|arg| replace_placeholder(arg, &path))
             .collect();
 
-        let mut process = Command::new(options.build.executable())
-            .args(args)
-            .stdin(Stdio::null())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .current_dir(build_dir)
-            .spawn()?;
+        let mut process = spawn(
+            Command::new(options.build.executable())
+                .args(args)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .current_dir(build_dir),
+        )?;
 
         let (exit_sender, exit_receiver) = crossbeam_channel::bounded(1);
-        let log_handle = capture_output(&mut process, lsp_sender, exit_receiver);
-        let success = process.wait().map(|status| status.success())?;
+        let log_handle = capture_output(&mut process, lsp_sender, exit_receiver, |_| {});
+        let status = loop {
+            if build_token.is_canceled() || cancellation_token.is_canceled() {
+                kill_process_tree(process.id());
+                let _ = process.wait();
+                break BuildStatus::CANCELLED;
+            }
+
+            match process.try_wait()? {
+                Some(exit_status) if exit_status.success() => break BuildStatus::SUCCESS,
+                Some(_) => break BuildStatus::ERROR,
+                None => thread::sleep(Duration::from_millis(50)),
+            }
+        };
+
         exit_sender.send(())?;
         drop(exit_sender);
 
         log_handle.join().unwrap();
-        let status = if success {
-            BuildStatus::SUCCESS
-        } else {
-            BuildStatus::ERROR
-        };
 
         drop(progress_reporter);
         drop(lock);
 
-        if options.build.forward_search_after {
+        if options.build.forward_search_after && status != BuildStatus::CANCELLED {
             let request = FeatureRequest {
                 params: TextDocumentPositionParams {
                     position: self
@@ -220,12 +359,166 @@ impl BuildEngine {
 
         Ok(BuildResult { status })
     }
+
+    fn build_continuously(
+        &self,
+        request: &FeatureRequest<BuildParams>,
+        document: &Document,
+        path: &Path,
+        options: &BuildOptions,
+        lsp_sender: &Sender<lsp_server::Message>,
+    ) -> Result<BuildStatus> {
+        let mut continuous_builds = self.continuous_builds.lock().unwrap();
+        if let Some(build) = continuous_builds.get(&document.uri) {
+            if &build.options == options && build.is_running() {
+                return Ok(BuildStatus::SUCCESS);
+            }
+        }
+
+        if let Some(build) = continuous_builds.remove(&document.uri) {
+            build.stop();
+        }
+
+        let build_dir = request
+            .context
+            .options_for(&document.uri)
+            .root_directory
+            .as_ref()
+            .map(|root_dir| request.context.directory_for(&document.uri).join(root_dir))
+            .or_else(|| path.parent().map(ToOwned::to_owned))
+            .unwrap();
+
+        let executable = options.executable();
+        let mut args: Vec<_> = options
+            .args()
+            .into_iter()
+            .map(|arg| replace_placeholder(arg, path))
+            .collect();
+
+        let is_latexmk = Path::new(&executable)
+            .file_stem()
+            .map_or(false, |stem| stem == "latexmk");
+
+        if is_latexmk && !args.iter().any(|arg| arg == "-pvc") {
+            args.insert(0, "-pvc".to_string());
+        }
+
+        let mut command = Command::new(executable);
+        command
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(build_dir);
+
+        let log_uris = document
+            .data
+            .as_latex()
+            .map(|data| data.extras.implicit_links.log.clone())
+            .unwrap_or_default();
+
+        let workspace = Arc::clone(&request.workspace);
+        let build = ContinuousBuild::start(command, options.clone(), lsp_sender, move |line| {
+            if line.contains("Watching for updated files") {
+                reload_build_logs(workspace.as_ref(), &log_uris);
+            }
+        })?;
+
+        let status = build.startup_status();
+        continuous_builds.insert(Arc::clone(&document.uri), build);
+        Ok(status)
+    }
+
+    pub fn cancel_progress(&self, progress_token: &NumberOrString) {
+        let progress_token = match progress_token {
+            NumberOrString::Number(_) => return,
+            NumberOrString::String(progress_token) => progress_token,
+        };
+
+        for build in self.running_builds.lock().unwrap().values() {
+            if &build.progress_token == progress_token {
+                build.token_source.cancel();
+            }
+        }
+    }
+
+    pub fn continuous_roots(&self) -> Vec<Arc<Uri>> {
+        self.continuous_builds
+            .lock()
+            .unwrap()
+            .keys()
+            .map(Arc::clone)
+            .collect()
+    }
+
+    pub fn stop_continuous_build(&self, uri: &Uri) {
+        if let Some(build) = self.continuous_builds.lock().unwrap().remove(uri) {
+            build.stop();
+        }
+    }
+
+    pub fn stop_continuous_builds(&self) {
+        let mut continuous_builds = self.continuous_builds.lock().unwrap();
+        for (_, build) in continuous_builds.drain() {
+            build.stop();
+        }
+    }
+}
+
+#[cfg(windows)]
+fn spawn(command: &mut Command) -> io::Result<Child> {
+    command.spawn()
+}
+
+#[cfg(not(windows))]
+fn spawn(command: &mut Command) -> io::Result<Child> {
+    use std::os::unix::process::CommandExt;
+
+    // The build runs in its own process group so that it can be killed along with its children.
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setpgid(0, 0) == 0 {
+                Ok(())
+            } else {
+                Err(io::Error::last_os_error())
+            }
+        });
+    }
+
+    command.spawn()
+}
+
+#[cfg(windows)]
+fn kill_process_tree(pid: u32) {
+    let _ = Command::new("taskkill")
+        .args(&["/PID", &pid.to_string(), "/T", "/F"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(not(windows))]
+fn kill_process_tree(pid: u32) {
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+fn reload_build_logs(workspace: &dyn Workspace, log_uris: &[Arc<Uri>]) {
+    for path in log_uris
+        .iter()
+        .filter_map(|uri| uri.to_file_path().ok())
+        .filter(|path| path.exists())
+    {
+        let _ = workspace.reload(path);
+    }
 }
 
 fn capture_output(
-    process: &mut std::process::Child,
+    process: &mut Child,
     lsp_sender: &Sender<lsp_server::Message>,
     exit_receiver: Receiver<()>,
+    on_line: impl Fn(&str) + Send + 'static,
 ) -> JoinHandle<()> {
     let (log_sender, log_receiver) = crossbeam_channel::unbounded();
     track_output(process.stdout.take().unwrap(), log_sender.clone());
@@ -235,16 +528,20 @@ fn capture_output(
         thread::spawn(move || loop {
             crossbeam_channel::select! {
                 recv(&log_receiver) -> message => {
-                    if let Ok(message) = message {
-                        client::send_notification::<LogMessage>(
-                            &lsp_sender,
-                            LogMessageParams {
-                                message,
-                                typ: lsp_types::MessageType::LOG,
-                            },
-                        )
-                        .unwrap();
-                    }
+                    // Both output streams have been closed once the channel is disconnected.
+                    let message = match message {
+                        Ok(message) => message,
+                        Err(_) => break,
+                    };
+
+                    on_line(&message);
+                    let _ = client::send_notification::<LogMessage>(
+                        &lsp_sender,
+                        LogMessageParams {
+                            message,
+                            typ: lsp_types::MessageType::LOG,
+                        },
+                    );
                 },
                 recv(&exit_receiver) -> _ => break,
             };
@@ -272,7 +569,213 @@ fn track_output(output: impl Read + Send + 'static, sender: Sender<String>) -> J
 
     thread::spawn(move || {
         for line in reader.lines() {
-            sender.send(line.unwrap()).unwrap();
+            match line {
+                Ok(line) if sender.send(line).is_ok() => {}
+                _ => break,
+            }
         }
     })
 }
+
+#[cfg(all(test, not(windows)))]
+mod tests {
+    use std::time::Instant;
+
+    use crate::features::testing::FeatureTester;
+
+    use super::*;
+
+    fn create_request(
+        executable: &str,
+        args: &[&str],
+        is_continuous: bool,
+    ) -> FeatureRequest<BuildParams> {
+        FeatureTester::builder()
+            .files(vec![("main.tex", "\\begin{document}\n\\end{document}")])
+            .main("main.tex")
+            .build_options(BuildOptions {
+                executable: Some(executable.to_string()),
+                args: Some(args.iter().map(ToString::to_string).collect()),
+                is_continuous,
+                ..BuildOptions::default()
+            })
+            .build()
+            .build_document()
+    }
+
+    fn run_build(
+        engine: &BuildEngine,
+        request: FeatureRequest<BuildParams>,
+        cancellation_token: &CancellationToken,
+    ) -> BuildStatus {
+        let req_queue = Mutex::default();
+        let (lsp_sender, _lsp_receiver) = crossbeam_channel::unbounded();
+        engine
+            .build(request, cancellation_token, &req_queue, &lsp_sender)
+            .unwrap()
+            .status
+    }
+
+    fn spawn_build(
+        engine: &Arc<BuildEngine>,
+        request: FeatureRequest<BuildParams>,
+        cancellation_token: &Arc<CancellationToken>,
+    ) -> JoinHandle<BuildStatus> {
+        let engine = Arc::clone(engine);
+        let cancellation_token = Arc::clone(cancellation_token);
+        thread::spawn(move || run_build(&engine, request, &cancellation_token))
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(5) {
+            if condition() {
+                return true;
+            }
+
+            thread::sleep(Duration::from_millis(50));
+        }
+
+        false
+    }
+
+    #[test]
+    fn test_cancel_build() {
+        let engine = Arc::new(BuildEngine::default());
+        let token_source = CancellationTokenSource::new();
+        let start = Instant::now();
+        let handle = spawn_build(
+            &engine,
+            create_request("sleep", &["10"], false),
+            token_source.token(),
+        );
+
+        thread::sleep(Duration::from_millis(200));
+        token_source.cancel();
+
+        assert_eq!(handle.join().unwrap(), BuildStatus::CANCELLED);
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_cancel_stale_build() {
+        let engine = Arc::new(BuildEngine::default());
+        let stale_token_source = CancellationTokenSource::new();
+        let stale_handle = spawn_build(
+            &engine,
+            create_request("sleep", &["10"], false),
+            stale_token_source.token(),
+        );
+
+        thread::sleep(Duration::from_millis(200));
+        let token_source = CancellationTokenSource::new();
+        let handle = spawn_build(
+            &engine,
+            create_request("sleep", &["10"], false),
+            token_source.token(),
+        );
+
+        assert_eq!(stale_handle.join().unwrap(), BuildStatus::CANCELLED);
+        token_source.cancel();
+        assert_eq!(handle.join().unwrap(), BuildStatus::CANCELLED);
+    }
+
+    #[test]
+    fn test_cancel_build_kills_children() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        let script = format!("sleep 30 & echo $! > '{}'; wait", pid_file.display());
+
+        let engine = Arc::new(BuildEngine::default());
+        let token_source = CancellationTokenSource::new();
+        let handle = spawn_build(
+            &engine,
+            create_request("sh", &["-c", &script], false),
+            token_source.token(),
+        );
+
+        assert!(wait_until(|| pid_file.exists()));
+        thread::sleep(Duration::from_millis(100));
+        token_source.cancel();
+        assert_eq!(handle.join().unwrap(), BuildStatus::CANCELLED);
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        assert!(wait_until(|| {
+            let output = Command::new("ps")
+                .args(&["-o", "stat=", "-p", pid.trim()])
+                .output()
+                .unwrap();
+            let state = String::from_utf8_lossy(&output.stdout);
+            state.trim().is_empty() || state.trim().starts_with('Z')
+        }));
+    }
+
+    #[test]
+    fn test_continuous_build_exits_with_error() {
+        let engine = BuildEngine::default();
+        let request = create_request("sh", &["-c", "exit 1"], true);
+        let uri = Arc::clone(&request.main_document().uri);
+        assert_eq!(
+            run_build(&engine, request, CancellationToken::none()),
+            BuildStatus::ERROR
+        );
+
+        let continuous_builds = engine.continuous_builds.lock().unwrap();
+        assert!(!continuous_builds[&uri].is_running());
+    }
+
+    #[test]
+    fn test_continuous_build_exits() {
+        let engine = BuildEngine::default();
+        let request = create_request("sh", &["-c", "echo foo"], true);
+        let uri = Arc::clone(&request.main_document().uri);
+        assert_eq!(
+            run_build(&engine, request, CancellationToken::none()),
+            BuildStatus::SUCCESS
+        );
+
+        let status = Arc::clone(&engine.continuous_builds.lock().unwrap()[&uri].status);
+        assert!(wait_until(|| status.lock().unwrap().is_some()));
+    }
+
+    #[test]
+    fn test_stop_continuous_build() {
+        let engine = BuildEngine::default();
+        let request = create_request("sleep", &["10"], true);
+        let uri = Arc::clone(&request.main_document().uri);
+        assert_eq!(
+            run_build(&engine, request, CancellationToken::none()),
+            BuildStatus::SUCCESS
+        );
+
+        let status = Arc::clone(&engine.continuous_builds.lock().unwrap()[&uri].status);
+        assert_eq!(*status.lock().unwrap(), None);
+
+        engine.stop_continuous_build(&uri);
+        assert!(engine.continuous_roots().is_empty());
+        assert!(wait_until(|| status.lock().unwrap().is_some()));
+    }
+
+    #[test]
+    fn test_capture_output_after_exit() {
+        let mut process = spawn(
+            Command::new("sh")
+                .args(&["-c", "echo foo"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped()),
+        )
+        .unwrap();
+
+        let (lsp_sender, _lsp_receiver) = crossbeam_channel::unbounded();
+        let (_exit_sender, exit_receiver) = crossbeam_channel::bounded(1);
+        let handle = capture_output(&mut process, &lsp_sender, exit_receiver, |_| {});
+        process.wait().unwrap();
+
+        let (done_sender, done_receiver) = crossbeam_channel::bounded(1);
+        thread::spawn(move || {
+            handle.join().unwrap();
+            done_sender.send(()).unwrap();
+        });
+        assert!(done_receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+    }
+}
@@ -0,0 +1,221 @@
+use std::collections::HashMap;
+
+use cancellation::CancellationToken;
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, Diagnostic, NumberOrString,
+    WorkspaceEdit,
+};
+
+use crate::diagnostics::{DiagnosticFix, DiagnosticsManager};
+
+use super::FeatureRequest;
+
+pub fn find_code_actions(
+    request: FeatureRequest<CodeActionParams>,
+    cancellation_token: &CancellationToken,
+) -> Vec<CodeActionOrCommand> {
+    let mut actions = Vec::new();
+    let mut diagnostics: Vec<Diagnostic> = request
+        .params
+        .context
+        .diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.source.as_deref() == Some("texlab"))
+        .cloned()
+        .collect();
+
+    if diagnostics
+        .iter()
+        .any(|diagnostic| diagnostic.data.is_none())
+        || diagnostics.is_empty()
+    {
+        diagnostics = find_static_diagnostics(&request);
+    }
+
+    let uri = request.main_document().uri.as_ref().clone();
+    for diagnostic in diagnostics {
+        if cancellation_token.is_canceled() {
+            break;
+        }
+
+        if !is_quick_fix_code(&diagnostic) {
+            continue;
+        }
+
+        let fix = match diagnostic.data.as_ref().and_then(DiagnosticFix::from_data) {
+            Some(fix) => fix,
+            None => continue,
+        };
+
+        let mut changes = HashMap::new();
+        changes.insert(uri.clone().into(), fix.edits);
+        actions.push(CodeActionOrCommand::CodeAction(CodeAction {
+            title: fix.title,
+            kind: Some(CodeActionKind::QUICKFIX),
+            diagnostics: Some(vec![diagnostic]),
+            edit: Some(WorkspaceEdit::new(changes)),
+            command: None,
+            is_preferred: Some(true),
+            disabled: None,
+            data: None,
+        }));
+    }
+
+    actions
+}
+
+fn find_static_diagnostics(request: &FeatureRequest<CodeActionParams>) -> Vec<Diagnostic> {
+    let uri = &request.main_document().uri;
+    let mut manager = DiagnosticsManager::default();
+    let options = request.context.options_for(uri);
+    manager.update_static(request.workspace.as_ref(), uri.clone(), &options);
+    manager
+        .publish(uri.clone())
+        .into_iter()
+        .filter(|diagnostic| {
+            diagnostic.range.start <= request.params.range.end
+                && request.params.range.start <= diagnostic.range.end
+        })
+        .collect()
+}
+
+fn is_quick_fix_code(diagnostic: &Diagnostic) -> bool {
+    matches!(
+        diagnostic.code,
+        Some(NumberOrString::Number(1..=8)) | Some(NumberOrString::Number(15))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::{Position, Range, TextEdit};
+
+    use crate::features::testing::FeatureTester;
+
+    use super::*;
+
+    fn find_edits(actions: Vec<CodeActionOrCommand>) -> Vec<(String, Vec<TextEdit>)> {
+        actions
+            .into_iter()
+            .filter_map(|action| match action {
+                CodeActionOrCommand::CodeAction(action) => Some(action),
+                CodeActionOrCommand::Command(_) => None,
+            })
+            .map(|action| {
+                let edits = action
+                    .edit
+                    .and_then(|edit| edit.changes)
+                    .and_then(|changes| changes.into_values().next())
+                    .unwrap_or_default();
+                (action.title, edits)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_latex_document() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", "")])
+            .main("main.tex")
+            .build()
+            .code_action();
+
+        let actions = find_code_actions(request, CancellationToken::none());
+        assert!(actions.is_empty());
+    }
+
+    #[test]
+    fn test_mismatched_environment() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", r#"\begin{foo}\end{bar}"#)])
+            .main("main.tex")
+            .line(0)
+            .character(8)
+            .build()
+            .code_action();
+
+        let actions = find_edits(find_code_actions(request, CancellationToken::none()));
+        assert_eq!(
+            actions,
+            vec![(
+                "Change environment name to \"foo\"".to_string(),
+                vec![TextEdit::new(
+                    Range::new(Position::new(0, 16), Position::new(0, 19)),
+                    "foo".to_string()
+                )]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_unexpected_brace() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", r#"foo}"#)])
+            .main("main.tex")
+            .line(0)
+            .character(3)
+            .build()
+            .code_action();
+
+        let actions = find_edits(find_code_actions(request, CancellationToken::none()));
+        assert_eq!(
+            actions,
+            vec![(
+                "Remove \"}\"".to_string(),
+                vec![TextEdit::new(
+                    Range::new(Position::new(0, 3), Position::new(0, 4)),
+                    String::new()
+                )]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_missing_equality_sign() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.bib", r#"@article{foo, bar {baz}}"#)])
+            .main("main.bib")
+            .line(0)
+            .character(17)
+            .build()
+            .code_action();
+
+        let actions = find_edits(find_code_actions(request, CancellationToken::none()));
+        assert_eq!(
+            actions,
+            vec![(
+                "Insert \"=\"".to_string(),
+                vec![TextEdit::new(
+                    Range::new(Position::new(0, 17), Position::new(0, 17)),
+                    " =".to_string()
+                )]
+            )]
+        );
+    }
+
+    #[test]
+    fn test_missing_required_field() {
+        let request = FeatureTester::builder()
+            .files(vec![(
+                "main.bib",
+                r#"@article{foo, author = {A}, title = {B}, year = {2020}}"#,
+            )])
+            .main("main.bib")
+            .line(0)
+            .character(10)
+            .build()
+            .code_action();
+
+        let actions = find_edits(find_code_actions(request, CancellationToken::none()));
+        assert_eq!(
+            actions,
+            vec![(
+                "Insert field \"journaltitle\"".to_string(),
+                vec![TextEdit::new(
+                    Range::new(Position::new(0, 54), Position::new(0, 54)),
+                    ",\n  journaltitle = {}".to_string()
+                )]
+            )]
+        );
+    }
+}
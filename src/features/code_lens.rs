@@ -0,0 +1,224 @@
+use std::sync::Arc;
+
+use cancellation::CancellationToken;
+use lsp_types::{
+    CodeLens, CodeLensParams, Command, PartialResultParams, Position, ReferenceContext,
+    ReferenceParams, TextDocumentPositionParams, WorkDoneProgressParams,
+};
+
+use crate::{
+    syntax::{bibtex, latex, CstNode},
+    DocumentData, LineIndexExt,
+};
+
+use super::{find_all_references, FeatureRequest};
+
+pub fn find_code_lenses(
+    request: FeatureRequest<CodeLensParams>,
+    cancellation_token: &CancellationToken,
+) -> Vec<CodeLens> {
+    let main_document = request.main_document();
+    let ranges = match &main_document.data {
+        DocumentData::Latex(data) => {
+            let mut ranges: Vec<_> = data
+                .extras
+                .label_names
+                .iter()
+                .filter(|name| name.is_definition)
+                .map(|name| name.range)
+                .collect();
+
+            ranges.extend(
+                data.root
+                    .descendants()
+                    .filter_map(latex::CommandDefinition::cast)
+                    .filter_map(|def| def.name()?.command())
+                    .filter(|name| name.kind() == latex::GENERIC_COMMAND_NAME)
+                    .map(|name| name.text_range()),
+            );
+
+            ranges.sort_by_key(|range| range.start());
+            ranges
+        }
+        DocumentData::Bibtex(data) => data
+            .root
+            .children()
+            .filter_map(bibtex::Entry::cast)
+            .filter_map(|entry| entry.key())
+            .map(|key| key.small_range())
+            .collect(),
+        DocumentData::BuildLog(_) => Vec::new(),
+    };
+
+    let mut lenses = Vec::new();
+    for range in ranges {
+        if cancellation_token.is_canceled() {
+            break;
+        }
+
+        let range = main_document.line_index.line_col_lsp_range(range);
+        if let Some(lens) = create_lens(&request, range.start, cancellation_token) {
+            lenses.push(CodeLens {
+                range,
+                command: Some(lens),
+                data: None,
+            });
+        }
+    }
+
+    lenses
+}
+
+fn create_lens(
+    request: &FeatureRequest<CodeLensParams>,
+    position: Position,
+    cancellation_token: &CancellationToken,
+) -> Option<Command> {
+    let text_document = request.params.text_document.clone();
+    let reference_request = FeatureRequest {
+        context: Arc::clone(&request.context),
+        workspace: Arc::clone(&request.workspace),
+        subset: request.subset.clone(),
+        params: ReferenceParams {
+            text_document_position: TextDocumentPositionParams::new(
+                text_document.clone(),
+                position,
+            ),
+            context: ReferenceContext {
+                include_declaration: false,
+            },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        },
+    };
+
+    let references = find_all_references(reference_request, cancellation_token)?;
+    let title = match references.len() {
+        1 => "1 reference".to_string(),
+        count => format!("{} references", count),
+    };
+
+    Some(Command {
+        title,
+        command: "texlab.showReferences".to_string(),
+        arguments: Some(vec![
+            serde_json::to_value(text_document.uri).ok()?,
+            serde_json::to_value(position).ok()?,
+            serde_json::to_value(references).ok()?,
+        ]),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use lsp_types::{Location, Range, Url};
+
+    use crate::{features::testing::FeatureTester, RangeExt};
+
+    use super::*;
+
+    fn titles(lenses: &[CodeLens]) -> Vec<(Range, &str)> {
+        lenses
+            .iter()
+            .map(|lens| (lens.range, lens.command.as_ref().unwrap().title.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_empty_latex_document() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", "")])
+            .main("main.tex")
+            .build()
+            .code_lens();
+
+        let actual_lenses = find_code_lenses(request, CancellationToken::none());
+
+        assert!(actual_lenses.is_empty());
+    }
+
+    #[test]
+    fn test_latex() {
+        let request = FeatureTester::builder()
+            .files(vec![(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \newcommand{\foo}{foo}
+                        \section{Foo}
+                        \label{sec:foo}
+                        \foo \ref{sec:foo} \ref{sec:foo}
+                        \label{sec:bar}
+                    "#
+                ),
+            )])
+            .main("main.tex")
+            .build()
+            .code_lens();
+
+        let actual_lenses = find_code_lenses(request, CancellationToken::none());
+
+        assert_eq!(
+            titles(&actual_lenses),
+            vec![
+                (Range::new_simple(0, 12, 0, 16), "1 reference"),
+                (Range::new_simple(2, 7, 2, 14), "2 references"),
+                (Range::new_simple(4, 7, 4, 14), "0 references"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bibtex() {
+        let tester = FeatureTester::builder()
+            .files(vec![
+                ("main.bib", "@article{foo, title = {Foo}}\n@book{bar,}"),
+                (
+                    "main.tex",
+                    indoc!(
+                        r#"
+                            \addbibresource{main.bib}
+                            \cite{foo}
+                        "#
+                    ),
+                ),
+            ])
+            .main("main.bib")
+            .build();
+        let uri = tester.uri("main.tex");
+        let bib_uri = tester.uri("main.bib");
+
+        let request = tester.code_lens();
+        let actual_lenses = find_code_lenses(request, CancellationToken::none());
+
+        assert_eq!(
+            titles(&actual_lenses),
+            vec![
+                (Range::new_simple(0, 9, 0, 12), "1 reference"),
+                (Range::new_simple(1, 6, 1, 9), "0 references"),
+            ]
+        );
+
+        let command = actual_lenses[0].command.as_ref().unwrap();
+        assert_eq!(command.command, "texlab.showReferences");
+        let arguments = command.arguments.clone().unwrap();
+        assert_eq!(
+            arguments[0],
+            serde_json::to_value(Url::from(bib_uri.as_ref().clone())).unwrap()
+        );
+        assert_eq!(
+            arguments[1],
+            serde_json::to_value(Position::new(0, 9)).unwrap()
+        );
+
+        let expected_locations = vec![Location::new(
+            uri.as_ref().clone().into(),
+            Range::new_simple(1, 6, 1, 9),
+        )];
+        assert_eq!(
+            arguments[2],
+            serde_json::to_value(expected_locations).unwrap()
+        );
+    }
+}
@@ -0,0 +1,499 @@
+use std::cmp::Ordering;
+
+use cancellation::CancellationToken;
+use cstree::TextRange;
+use lsp_types::{
+    Color, ColorInformation, ColorPresentation, ColorPresentationParams, DocumentColorParams,
+    TextEdit,
+};
+
+use crate::{
+    syntax::{
+        latex::{self, HasCurly},
+        CstNode,
+    },
+    Document, LineIndexExt, LANGUAGE_DATA,
+};
+
+use super::FeatureRequest;
+
+/// The predefined colors of `xcolor` (including the `dvipsnames` option) in their original model.
+const PREDEFINED_COLORS: &[(&str, &str, &str)] = &[
+    ("black", "rgb", "0,0,0"),
+    ("blue", "rgb", "0,0,1"),
+    ("brown", "rgb", "0.75,0.5,0.25"),
+    ("cyan", "cmyk", "1,0,0,0"),
+    ("darkgray", "gray", "0.25"),
+    ("gray", "gray", "0.5"),
+    ("green", "rgb", "0,1,0"),
+    ("lightgray", "gray", "0.75"),
+    ("lime", "rgb", "0.75,1,0"),
+    ("magenta", "cmyk", "0,1,0,0"),
+    ("olive", "rgb", "0.5,0.5,0"),
+    ("orange", "rgb", "1,0.5,0"),
+    ("pink", "rgb", "1,0.75,0.75"),
+    ("purple", "rgb", "0.75,0,0.25"),
+    ("red", "rgb", "1,0,0"),
+    ("teal", "rgb", "0,0.5,0.5"),
+    ("violet", "rgb", "0.5,0,0.5"),
+    ("white", "rgb", "1,1,1"),
+    ("yellow", "cmyk", "0,0,1,0"),
+    ("Apricot", "cmyk", "0,0.32,0.52,0"),
+    ("Aquamarine", "cmyk", "0.82,0,0.30,0"),
+    ("Bittersweet", "cmyk", "0,0.75,1,0.24"),
+    ("Black", "cmyk", "0,0,0,1"),
+    ("Blue", "cmyk", "1,1,0,0"),
+    ("BlueGreen", "cmyk", "0.85,0,0.33,0"),
+    ("BlueViolet", "cmyk", "0.86,0.91,0,0.04"),
+    ("BrickRed", "cmyk", "0,0.89,0.94,0.28"),
+    ("Brown", "cmyk", "0,0.81,1,0.60"),
+    ("BurntOrange", "cmyk", "0,0.51,1,0"),
+    ("CadetBlue", "cmyk", "0.62,0.57,0.23,0"),
+    ("CarnationPink", "cmyk", "0,0.63,0,0"),
+    ("Cerulean", "cmyk", "0.94,0.11,0,0"),
+    ("CornflowerBlue", "cmyk", "0.65,0.13,0,0"),
+    ("Cyan", "cmyk", "1,0,0,0"),
+    ("Dandelion", "cmyk", "0,0.29,0.84,0"),
+    ("DarkOrchid", "cmyk", "0.40,0.80,0.20,0"),
+    ("Emerald", "cmyk", "1,0,0.50,0"),
+    ("ForestGreen", "cmyk", "0.91,0,0.88,0.12"),
+    ("Fuchsia", "cmyk", "0.47,0.91,0,0.08"),
+    ("Goldenrod", "cmyk", "0,0.10,0.84,0"),
+    ("Gray", "cmyk", "0,0,0,0.50"),
+    ("Green", "cmyk", "1,0,1,0"),
+    ("GreenYellow", "cmyk", "0.15,0,0.69,0"),
+    ("JungleGreen", "cmyk", "0.99,0,0.52,0"),
+    ("Lavender", "cmyk", "0,0.48,0,0"),
+    ("LimeGreen", "cmyk", "0.50,0,1,0"),
+    ("Magenta", "cmyk", "0,1,0,0"),
+    ("Mahogany", "cmyk", "0,0.85,0.87,0.35"),
+    ("Maroon", "cmyk", "0,0.87,0.68,0.32"),
+    ("Melon", "cmyk", "0,0.46,0.50,0"),
+    ("MidnightBlue", "cmyk", "0.98,0.13,0,0.43"),
+    ("Mulberry", "cmyk", "0.34,0.90,0,0.02"),
+    ("NavyBlue", "cmyk", "0.94,0.54,0,0"),
+    ("OliveGreen", "cmyk", "0.64,0,0.95,0.40"),
+    ("Orange", "cmyk", "0,0.61,0.87,0"),
+    ("OrangeRed", "cmyk", "0,1,0.50,0"),
+    ("Orchid", "cmyk", "0.32,0.64,0,0"),
+    ("Peach", "cmyk", "0,0.50,0.70,0"),
+    ("Periwinkle", "cmyk", "0.57,0.55,0,0"),
+    ("PineGreen", "cmyk", "0.92,0,0.59,0.25"),
+    ("Plum", "cmyk", "0.50,1,0,0"),
+    ("ProcessBlue", "cmyk", "0.96,0,0,0"),
+    ("Purple", "cmyk", "0.45,0.86,0,0"),
+    ("RawSienna", "cmyk", "0,0.72,1,0.45"),
+    ("Red", "cmyk", "0,1,1,0"),
+    ("RedOrange", "cmyk", "0,0.77,0.87,0"),
+    ("RedViolet", "cmyk", "0.07,0.90,0,0.34"),
+    ("Rhodamine", "cmyk", "0,0.82,0,0"),
+    ("RoyalBlue", "cmyk", "1,0.50,0,0"),
+    ("RoyalPurple", "cmyk", "0.75,0.90,0,0"),
+    ("RubineRed", "cmyk", "0,1,0.13,0"),
+    ("Salmon", "cmyk", "0,0.53,0.38,0"),
+    ("SeaGreen", "cmyk", "0.69,0,0.50,0"),
+    ("Sepia", "cmyk", "0,0.83,1,0.70"),
+    ("SkyBlue", "cmyk", "0.62,0,0.12,0"),
+    ("SpringGreen", "cmyk", "0.26,0,0.76,0"),
+    ("Tan", "cmyk", "0.14,0.42,0.56,0"),
+    ("TealBlue", "cmyk", "0.86,0,0.34,0.02"),
+    ("Thistle", "cmyk", "0.12,0.59,0,0"),
+    ("Turquoise", "cmyk", "0.85,0,0.20,0"),
+    ("Violet", "cmyk", "0.79,0.88,0,0"),
+    ("VioletRed", "cmyk", "0,0.81,0,0"),
+    ("White", "cmyk", "0,0,0,0"),
+    ("WildStrawberry", "cmyk", "0,0.96,0.39,0"),
+    ("Yellow", "cmyk", "0,0,1,0"),
+    ("YellowGreen", "cmyk", "0.44,0,0.74,0"),
+    ("YellowOrange", "cmyk", "0,0.42,1,0"),
+];
+
+pub fn find_document_colors(
+    request: FeatureRequest<DocumentColorParams>,
+    cancellation_token: &CancellationToken,
+) -> Vec<ColorInformation> {
+    let document = request.main_document();
+    find_colors(document, cancellation_token)
+        .into_iter()
+        .map(|color| ColorInformation {
+            range: document.line_index.line_col_lsp_range(color.range),
+            color: color.color,
+        })
+        .collect()
+}
+
+pub fn find_color_presentations(
+    request: FeatureRequest<ColorPresentationParams>,
+    cancellation_token: &CancellationToken,
+) -> Vec<ColorPresentation> {
+    let document = request.main_document();
+    let params = &request.params;
+    let color = match find_colors(document, cancellation_token)
+        .into_iter()
+        .find(|color| document.line_index.line_col_lsp_range(color.range) == params.range)
+    {
+        Some(color) => color,
+        None => return Vec::new(),
+    };
+
+    let label = match &color.model {
+        Some(model) => format_color(model, params.color),
+        None => find_nearest_color_name(params.color).map(ToString::to_string),
+    };
+
+    label
+        .into_iter()
+        .map(|label| ColorPresentation {
+            text_edit: Some(TextEdit::new(params.range, label.clone())),
+            label,
+            additional_text_edits: None,
+        })
+        .collect()
+}
+
+#[derive(Debug, PartialEq, Clone)]
+struct DocumentColor {
+    range: TextRange,
+    color: Color,
+
+    /// The model of a color definition or `None` for a reference to a predefined color.
+    model: Option<String>,
+}
+
+fn find_colors(document: &Document, cancellation_token: &CancellationToken) -> Vec<DocumentColor> {
+    let mut colors = Vec::new();
+    let data = match document.data.as_latex() {
+        Some(data) => data,
+        None => return colors,
+    };
+
+    for node in data.root.descendants() {
+        if cancellation_token.is_canceled() {
+            break;
+        }
+
+        if let Some(color) =
+            find_color_definition(document, node).or_else(|| find_color_reference(node))
+        {
+            colors.push(color);
+        }
+    }
+
+    colors
+}
+
+fn find_color_definition(document: &Document, node: &latex::SyntaxNode) -> Option<DocumentColor> {
+    let definition = latex::ColorDefinition::cast(node)?;
+    let model = definition.model()?.key()?.to_string();
+    let spec = definition.spec()?;
+    let range = TextRange::new(
+        spec.left_curly()?.text_range().end(),
+        spec.right_curly()?.text_range().start(),
+    );
+
+    let color = parse_color(&model, &document.text[range])?;
+    Some(DocumentColor {
+        range,
+        color,
+        model: Some(model),
+    })
+}
+
+fn find_color_reference(node: &latex::SyntaxNode) -> Option<DocumentColor> {
+    let name = latex::ColorReference::cast(node)?.name()?.key()?;
+    let color = find_predefined_color(&name.to_string())?;
+    Some(DocumentColor {
+        range: name.small_range(),
+        color,
+        model: None,
+    })
+}
+
+fn find_predefined_color(name: &str) -> Option<Color> {
+    if !LANGUAGE_DATA.colors.iter().any(|color| color == name) {
+        return None;
+    }
+
+    let (_, model, spec) = PREDEFINED_COLORS
+        .iter()
+        .find(|(color_name, _, _)| *color_name == name)?;
+
+    parse_color(model, spec)
+}
+
+fn find_nearest_color_name(color: Color) -> Option<&'static str> {
+    let distance = |other: Color| {
+        (color.red - other.red).powi(2)
+            + (color.green - other.green).powi(2)
+            + (color.blue - other.blue).powi(2)
+    };
+
+    PREDEFINED_COLORS
+        .iter()
+        .filter_map(|(name, _, _)| Some((*name, distance(find_predefined_color(name)?))))
+        .filter(|(_, distance)| distance.is_finite())
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+        .map(|(name, _)| name)
+}
+
+fn parse_color(model: &str, spec: &str) -> Option<Color> {
+    let values: Option<Vec<f32>> = spec
+        .split(',')
+        .map(|value| value.trim().parse().ok())
+        .collect();
+
+    let rgb = |red: f32, green: f32, blue: f32| {
+        if [red, green, blue]
+            .iter()
+            .all(|value| (0.0..=1.0).contains(value))
+        {
+            Some(Color {
+                red,
+                green,
+                blue,
+                alpha: 1.0,
+            })
+        } else {
+            None
+        }
+    };
+
+    match model {
+        "rgb" => match values?.as_slice() {
+            [red, green, blue] => rgb(*red, *green, *blue),
+            _ => None,
+        },
+        "RGB" => match values?.as_slice() {
+            [red, green, blue] => rgb(red / 255.0, green / 255.0, blue / 255.0),
+            _ => None,
+        },
+        "HTML" => {
+            let spec = spec.trim();
+            if spec.len() != 6 || !spec.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+
+            let component = |i: usize| u8::from_str_radix(&spec[i..i + 2], 16).ok();
+            rgb(
+                f32::from(component(0)?) / 255.0,
+                f32::from(component(2)?) / 255.0,
+                f32::from(component(4)?) / 255.0,
+            )
+        }
+        "cmyk" => match values?.as_slice() {
+            [cyan, magenta, yellow, black] => rgb(
+                (1.0 - cyan) * (1.0 - black),
+                (1.0 - magenta) * (1.0 - black),
+                (1.0 - yellow) * (1.0 - black),
+            ),
+            _ => None,
+        },
+        "gray" => match values?.as_slice() {
+            [gray] => rgb(*gray, *gray, *gray),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn format_color(model: &str, color: Color) -> Option<String> {
+    let Color {
+        red, green, blue, ..
+    } = color;
+
+    match model {
+        "rgb" => Some(format_numbers(&[red, green, blue])),
+        "RGB" => Some(format!(
+            "{},{},{}",
+            to_byte(red),
+            to_byte(green),
+            to_byte(blue)
+        )),
+        "HTML" => Some(format!(
+            "{:02X}{:02X}{:02X}",
+            to_byte(red),
+            to_byte(green),
+            to_byte(blue)
+        )),
+        "cmyk" => {
+            let black = 1.0 - red.max(green).max(blue);
+            if black >= 1.0 {
+                Some(format_numbers(&[0.0, 0.0, 0.0, 1.0]))
+            } else {
+                Some(format_numbers(&[
+                    (1.0 - red - black) / (1.0 - black),
+                    (1.0 - green - black) / (1.0 - black),
+                    (1.0 - blue - black) / (1.0 - black),
+                    black,
+                ]))
+            }
+        }
+        "gray" => Some(format_numbers(&[0.3 * red + 0.59 * green + 0.11 * blue])),
+        _ => None,
+    }
+}
+
+fn format_numbers(values: &[f32]) -> String {
+    values
+        .iter()
+        .map(|value| {
+            let text = format!("{:.3}", value.max(0.0).min(1.0));
+            text.trim_end_matches('0').trim_end_matches('.').to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn to_byte(value: f32) -> u8 {
+    (value.max(0.0).min(1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use lsp_types::Range;
+
+    use crate::{features::testing::FeatureTester, RangeExt};
+
+    use super::*;
+
+    fn color(red: f32, green: f32, blue: f32) -> Color {
+        Color {
+            red,
+            green,
+            blue,
+            alpha: 1.0,
+        }
+    }
+
+    #[test]
+    fn test_empty_latex_document() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", "")])
+            .main("main.tex")
+            .build()
+            .document_color();
+
+        let actual_colors = find_document_colors(request, CancellationToken::none());
+
+        assert!(actual_colors.is_empty());
+    }
+
+    #[test]
+    fn test_definitions_and_references() {
+        let request = FeatureTester::builder()
+            .files(vec![(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \definecolor{foo}{HTML}{FF0000}
+                        \definecolor{bar}{rgb}{0,0.5,1}
+                        \color{blue}
+                        \color{foo}
+                    "#
+                ),
+            )])
+            .main("main.tex")
+            .build()
+            .document_color();
+
+        let actual_colors = find_document_colors(request, CancellationToken::none());
+
+        assert_eq!(
+            actual_colors,
+            vec![
+                ColorInformation {
+                    range: Range::new_simple(0, 24, 0, 30),
+                    color: color(1.0, 0.0, 0.0),
+                },
+                ColorInformation {
+                    range: Range::new_simple(1, 23, 1, 30),
+                    color: color(0.0, 0.5, 1.0),
+                },
+                ColorInformation {
+                    range: Range::new_simple(2, 7, 2, 11),
+                    color: color(0.0, 0.0, 1.0),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_presentation_in_original_model() {
+        let tester = FeatureTester::builder()
+            .files(vec![("main.tex", r#"\definecolor{foo}{HTML}{FF0000}"#)])
+            .main("main.tex")
+            .build();
+
+        let mut request = tester.color_presentation();
+        request.params.range = Range::new_simple(0, 24, 0, 30);
+        request.params.color = color(0.0, 0.5, 1.0);
+
+        let actual_presentations = find_color_presentations(request, CancellationToken::none());
+
+        assert_eq!(
+            actual_presentations,
+            vec![ColorPresentation {
+                label: "0080FF".into(),
+                text_edit: Some(TextEdit::new(
+                    Range::new_simple(0, 24, 0, 30),
+                    "0080FF".into()
+                )),
+                additional_text_edits: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_presentation_of_predefined_color() {
+        let tester = FeatureTester::builder()
+            .files(vec![("main.tex", r#"\color{blue}"#)])
+            .main("main.tex")
+            .build();
+
+        let mut request = tester.color_presentation();
+        request.params.range = Range::new_simple(0, 7, 0, 11);
+        request.params.color = color(0.95, 0.05, 0.0);
+
+        let actual_presentations = find_color_presentations(request, CancellationToken::none());
+
+        assert_eq!(actual_presentations.len(), 1);
+        assert_eq!(actual_presentations[0].label, "red");
+    }
+
+    #[test]
+    fn test_parse_models() {
+        assert_eq!(parse_color("RGB", "0, 255, 0"), Some(color(0.0, 1.0, 0.0)));
+        assert_eq!(parse_color("gray", "0.5"), Some(color(0.5, 0.5, 0.5)));
+        assert_eq!(parse_color("cmyk", "0,1,1,0"), Some(color(1.0, 0.0, 0.0)));
+        assert_eq!(parse_color("rgb", "2,0,0"), None);
+        assert_eq!(parse_color("HTML", "FF00"), None);
+        assert_eq!(parse_color("foo", "1"), None);
+    }
+
+    #[test]
+    fn test_format_models() {
+        let color = color(1.0, 0.5, 0.0);
+        assert_eq!(format_color("rgb", color).unwrap(), "1,0.5,0");
+        assert_eq!(format_color("RGB", color).unwrap(), "255,128,0");
+        assert_eq!(format_color("cmyk", color).unwrap(), "0,0.5,1,0");
+        assert_eq!(format_color("gray", color).unwrap(), "0.595");
+    }
+
+    #[test]
+    fn test_nearest_color_name() {
+        assert_eq!(find_nearest_color_name(color(0.95, 0.05, 0.0)), Some("red"));
+        assert_eq!(find_nearest_color_name(color(f32::NAN, 0.0, 0.0)), None);
+        assert_eq!(
+            find_nearest_color_name(color(f32::INFINITY, 0.0, 0.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_predefined_colors() {
+        for name in &LANGUAGE_DATA.colors {
+            assert!(find_predefined_color(name).is_some(), "{}", name);
+        }
+    }
+}
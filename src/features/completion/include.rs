@@ -100,19 +100,18 @@ fn current_dir(
     path_text: &str,
     graphics_path: Option<&str>,
 ) -> Option<PathBuf> {
+    let uri = &context.request.main_document().uri;
     let mut path = context
         .request
         .context
-        .options
-        .read()
-        .unwrap()
+        .options_for(uri)
         .root_directory
         .as_ref()
         .map(|root_directory| {
             context
                 .request
                 .context
-                .current_directory
+                .directory_for(uri)
                 .join(root_directory)
         })
         .unwrap_or_else(|| {
@@ -1,11 +1,16 @@
 use cstree::{TextRange, TextSize};
 use lsp_types::{
-    CompletionParams, DocumentHighlightParams, GotoDefinitionParams, HoverParams, Position,
-    ReferenceParams, RenameParams, TextDocumentPositionParams,
+    CompletionParams, DocumentHighlightParams, GotoDefinitionParams, HoverParams,
+    LinkedEditingRangeParams, Position, ReferenceParams, RenameParams, SignatureHelpParams,
+    TextDocumentPositionParams,
 };
 
 use crate::{
-    syntax::{bibtex, latex, CstNode},
+    syntax::{
+        bibtex,
+        latex::{self, HasCurly},
+        CstNode,
+    },
     DocumentData, LineIndexExt,
 };
 
@@ -236,6 +241,30 @@ impl<P: HasPosition> CursorContext<P> {
         Some((name, range))
     }
 
+    /// Finds the environment whose name is under the cursor and
+    /// returns the names and ranges of both its `\begin` and `\end` commands.
+    pub fn find_environment_names(&self) -> Option<[(String, TextRange); 2]> {
+        let (_, _, group) = self.find_curly_group_word()?;
+        let parent = group.syntax().parent()?;
+        if !matches!(parent.kind(), latex::BEGIN | latex::END) {
+            return None;
+        }
+
+        let environment = latex::Environment::cast(parent.parent()?)?;
+        let name = |group: latex::CurlyGroupWord| match group.key() {
+            Some(key) => Some((key.to_string(), key.small_range())),
+            None => Some((
+                String::new(),
+                TextRange::empty(group.left_curly()?.text_range().end()),
+            )),
+        };
+
+        Some([
+            name(environment.begin()?.name()?)?,
+            name(environment.end()?.name()?)?,
+        ])
+    }
+
     pub fn find_curly_group_word(&self) -> Option<(String, TextRange, latex::CurlyGroupWord)> {
         let token = self.cursor.as_latex()?;
         let key = latex::Key::cast(token.parent());
@@ -329,3 +358,15 @@ impl HasPosition for DocumentHighlightParams {
         self.text_document_position_params.position
     }
 }
+
+impl HasPosition for SignatureHelpParams {
+    fn position(&self) -> Position {
+        self.text_document_position_params.position
+    }
+}
+
+impl HasPosition for LinkedEditingRangeParams {
+    fn position(&self) -> Position {
+        self.text_document_position_params.position
+    }
+}
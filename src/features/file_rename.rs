@@ -0,0 +1,328 @@
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    path::{Component, Path, PathBuf},
+};
+
+use cancellation::CancellationToken;
+use lsp_types::{RenameFilesParams, TextEdit, Url, WorkspaceEdit};
+
+use crate::{
+    syntax::{latex, CstNode},
+    Document, LineIndexExt, ServerContext, Workspace,
+};
+
+/// Computes the edits that keep all includes pointing to the renamed files or directories.
+pub fn find_file_rename_edits(
+    context: &ServerContext,
+    workspace: &dyn Workspace,
+    params: &RenameFilesParams,
+    cancellation_token: &CancellationToken,
+) -> Option<WorkspaceEdit> {
+    let renames: Vec<_> = params
+        .files
+        .iter()
+        .filter_map(|file| {
+            let old_path = Url::parse(&file.old_uri).ok()?.to_file_path().ok()?;
+            let new_path = Url::parse(&file.new_uri).ok()?.to_file_path().ok()?;
+            Some((normalize(&old_path), normalize(&new_path)))
+        })
+        .collect();
+
+    if renames.is_empty() {
+        return None;
+    }
+
+    let mut changes = HashMap::new();
+    for document in workspace.documents() {
+        cancellation_token.result().ok()?;
+
+        let root_directory = context
+            .options_for(&document.uri)
+            .root_directory
+            .as_ref()
+            .map(|root_directory| context.directory_for(&document.uri).join(root_directory));
+
+        let base_directory = match root_directory.or_else(|| {
+            let mut path = document.uri.to_file_path().ok()?;
+            path.pop();
+            Some(path)
+        }) {
+            Some(base_directory) => base_directory,
+            None => continue,
+        };
+
+        let edits = find_document_edits(&document, &base_directory, &renames);
+        if !edits.is_empty() {
+            changes.insert(document.uri.as_ref().clone().into(), edits);
+        }
+    }
+
+    if changes.is_empty() {
+        None
+    } else {
+        Some(WorkspaceEdit::new(changes))
+    }
+}
+
+fn find_document_edits(
+    document: &Document,
+    base_directory: &Path,
+    renames: &[(PathBuf, PathBuf)],
+) -> Vec<TextEdit> {
+    let mut edits = Vec::new();
+    let data = match document.data.as_latex() {
+        Some(data) => data,
+        None => return edits,
+    };
+
+    let graphics_directories: Vec<_> = data
+        .extras
+        .graphics_paths
+        .iter()
+        .map(|path| base_directory.join(path))
+        .collect();
+
+    let mut push_edit = |key: latex::Key, directories: &[&Path], extensions: &[&str]| {
+        if let Some(new_stem) = rename_stem(&key.to_string(), directories, extensions, renames) {
+            edits.push(TextEdit::new(
+                document.line_index.line_col_lsp_range(key.small_range()),
+                new_stem,
+            ));
+        }
+    };
+
+    for node in data.root.descendants() {
+        if let Some(include) = latex::Include::cast(node) {
+            let extensions: &[&str] = match include.syntax().kind() {
+                latex::LATEX_INCLUDE => &["tex"],
+                latex::BIBLATEX_INCLUDE | latex::BIBTEX_INCLUDE => &["bib"],
+                latex::GRAPHICS_INCLUDE => &["pdf", "png", "jpg", "jpeg", "bmp"],
+                latex::SVG_INCLUDE => &["svg"],
+                latex::INKSCAPE_INCLUDE => &["pdf", "eps", "ps", "png"],
+                latex::VERBATIM_INCLUDE => &[],
+                _ => continue,
+            };
+
+            let mut directories = vec![base_directory];
+            if include.syntax().kind() == latex::GRAPHICS_INCLUDE {
+                directories.extend(graphics_directories.iter().map(PathBuf::as_path));
+            }
+
+            for key in include.path_list().into_iter().flat_map(|list| list.keys()) {
+                push_edit(key, &directories, extensions);
+            }
+        } else if let Some(import) = latex::Import::cast(node) {
+            let directory = import.directory().and_then(|directory| directory.key());
+            let import_directory = match &directory {
+                Some(directory) => base_directory.join(directory.to_string()),
+                None => base_directory.to_path_buf(),
+            };
+
+            if let Some(file) = import.file().and_then(|file| file.key()) {
+                push_edit(file, &[&import_directory], &["tex"]);
+            }
+
+            if let Some(directory) = directory {
+                push_edit(directory, &[base_directory], &[]);
+            }
+        }
+    }
+
+    edits
+}
+
+/// Returns the new stem of an include if it resolves to one of the renamed paths.
+fn rename_stem(
+    stem: &str,
+    directories: &[&Path],
+    extensions: &[&str],
+    renames: &[(PathBuf, PathBuf)],
+) -> Option<String> {
+    for directory in directories {
+        let directory = normalize(directory);
+        let path = normalize(&directory.join(stem));
+        let candidates =
+            std::iter::once((path.clone(), None)).chain(extensions.iter().map(|ext| {
+                let mut name = OsString::from(path.as_os_str());
+                name.push(".");
+                name.push(ext);
+                (PathBuf::from(name), Some(*ext))
+            }));
+
+        for (candidate, extension) in candidates {
+            if let Some(mut new_path) = rename_path(&candidate, renames) {
+                if let Some(extension) = extension {
+                    if new_path.extension().and_then(|ext| ext.to_str()) == Some(extension) {
+                        new_path.set_extension("");
+                    }
+                }
+
+                return relative_path(&directory, &new_path);
+            }
+        }
+    }
+
+    None
+}
+
+fn rename_path(path: &Path, renames: &[(PathBuf, PathBuf)]) -> Option<PathBuf> {
+    renames.iter().find_map(|(old_path, new_path)| {
+        let rest = path.strip_prefix(old_path).ok()?;
+        if rest.as_os_str().is_empty() {
+            Some(new_path.clone())
+        } else {
+            Some(new_path.join(rest))
+        }
+    })
+}
+
+/// Resolves `.` and `..` without accessing the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                result.pop();
+            }
+            component => result.push(component),
+        }
+    }
+    result
+}
+
+/// Computes the path of `target` relative to `base` using forward slashes.
+fn relative_path(base: &Path, target: &Path) -> Option<String> {
+    let base: Vec<_> = base.components().collect();
+    let target: Vec<_> = target.components().collect();
+    if base.first() != target.first() {
+        return None;
+    }
+
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+
+    let mut parts = vec!["..".to_string(); base.len() - common];
+    for component in &target[common..] {
+        parts.push(component.as_os_str().to_str()?.to_string());
+    }
+
+    Some(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use lsp_types::Range;
+
+    use crate::{features::testing::FeatureTester, RangeExt};
+
+    use super::*;
+
+    #[test]
+    fn test_rename_files() {
+        let tester = FeatureTester::builder()
+            .files(vec![
+                (
+                    "main.tex",
+                    indoc!(
+                        r#"
+                            \input{chapters/intro}
+                            \addbibresource{refs.bib}
+                            \includegraphics{figures/plot}
+                        "#
+                    ),
+                ),
+                ("chapters/intro.tex", ""),
+            ])
+            .main("main.tex")
+            .build();
+
+        let uri = tester.uri("main.tex");
+        let request = tester.rename_files(vec![
+            ("chapters/intro.tex", "parts/intro.tex"),
+            ("refs.bib", "bib/refs.bib"),
+            ("figures", "images"),
+        ]);
+
+        let actual_edit = find_file_rename_edits(
+            &request.context,
+            request.workspace.as_ref(),
+            &request.params,
+            CancellationToken::none(),
+        )
+        .unwrap();
+
+        let mut expected_changes = HashMap::new();
+        expected_changes.insert(
+            uri.as_ref().clone().into(),
+            vec![
+                TextEdit::new(Range::new_simple(0, 7, 0, 21), "parts/intro".into()),
+                TextEdit::new(Range::new_simple(1, 16, 1, 24), "bib/refs.bib".into()),
+                TextEdit::new(Range::new_simple(2, 17, 2, 29), "images/plot".into()),
+            ],
+        );
+
+        assert_eq!(actual_edit, WorkspaceEdit::new(expected_changes));
+    }
+
+    #[test]
+    fn test_rename_relative_to_included_document() {
+        let tester = FeatureTester::builder()
+            .files(vec![
+                ("main.tex", r#"\input{chapters/intro}"#),
+                ("chapters/intro.tex", r#"\input{../preamble}"#),
+            ])
+            .main("main.tex")
+            .build();
+
+        let uri = tester.uri("chapters/intro.tex");
+        let request = tester.rename_files(vec![("preamble.tex", "setup/preamble.tex")]);
+
+        let actual_edit = find_file_rename_edits(
+            &request.context,
+            request.workspace.as_ref(),
+            &request.params,
+            CancellationToken::none(),
+        )
+        .unwrap();
+
+        let mut expected_changes = HashMap::new();
+        expected_changes.insert(
+            uri.as_ref().clone().into(),
+            vec![TextEdit::new(
+                Range::new_simple(0, 7, 0, 18),
+                "../setup/preamble".into(),
+            )],
+        );
+
+        assert_eq!(actual_edit, WorkspaceEdit::new(expected_changes));
+    }
+
+    #[test]
+    fn test_unrelated_file() {
+        let tester = FeatureTester::builder()
+            .files(vec![("main.tex", r#"\input{foo}"#)])
+            .main("main.tex")
+            .build();
+
+        let request = tester.rename_files(vec![("bar.tex", "baz.tex")]);
+
+        let actual_edit = find_file_rename_edits(
+            &request.context,
+            request.workspace.as_ref(),
+            &request.params,
+            CancellationToken::none(),
+        );
+
+        assert_eq!(actual_edit, None);
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("/foo/bar"), Path::new("/foo/baz/qux")).as_deref(),
+            Some("../baz/qux")
+        );
+    }
+}
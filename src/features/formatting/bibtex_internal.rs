@@ -1,16 +1,63 @@
 use cancellation::CancellationToken;
-use cstree::NodeOrToken;
+use cstree::{NodeOrToken, TextRange};
 use lsp_types::{DocumentFormattingParams, TextEdit};
+use rustc_hash::FxHashMap;
 
 use crate::{
     features::FeatureRequest,
     syntax::{
-        bibtex::{self, HasType},
+        bibtex::{self, HasCurly, HasQuotes, HasType},
         CstNode,
     },
-    LineIndex, LineIndexExt,
+    BibtexFormattingOptions, BibtexSortOrder, BibtexValueDelimiters, LineIndex, LineIndexExt,
 };
 
+const FIELD_ORDER: &[&str] = &[
+    "author",
+    "editor",
+    "translator",
+    "title",
+    "subtitle",
+    "titleaddon",
+    "booktitle",
+    "journal",
+    "journaltitle",
+    "series",
+    "edition",
+    "volume",
+    "number",
+    "chapter",
+    "pages",
+    "publisher",
+    "organization",
+    "institution",
+    "school",
+    "address",
+    "location",
+    "howpublished",
+    "type",
+    "month",
+    "year",
+    "date",
+    "isbn",
+    "issn",
+    "doi",
+    "eprint",
+    "eprinttype",
+    "archiveprefix",
+    "eprintclass",
+    "primaryclass",
+    "url",
+    "urldate",
+    "language",
+    "keywords",
+    "abstract",
+    "file",
+    "note",
+    "annotation",
+    "annote",
+];
+
 pub fn format_bibtex_internal(
     request: &FeatureRequest<DocumentFormattingParams>,
     _cancellation_token: &CancellationToken,
@@ -24,12 +71,9 @@ pub fn format_bibtex_internal(
         indent.push('\t');
     }
 
-    let line_length = {
-        request
-            .context
-            .options
-            .read()
-            .unwrap()
+    let (line_length, options) = {
+        let options = request.context.options_for(&request.main_document().uri);
+        let line_length = options
             .formatter_line_length
             .map(|value| {
                 if value <= 0 {
@@ -38,16 +82,77 @@ pub fn format_bibtex_internal(
                     value as usize
                 }
             })
-            .unwrap_or(80)
+            .unwrap_or(80);
+
+        (line_length, options.bibtex_formatting.clone())
     };
 
     let document = request.main_document();
     let data = document.data.as_bibtex()?;
-    let mut edits = Vec::new();
+    let edits = format_nodes(
+        &data.root,
+        &document.line_index,
+        &indent,
+        request.params.options.tab_size,
+        line_length,
+        &options,
+    )
+    .into_iter()
+    .map(|(range, new_text)| TextEdit {
+        range: document.line_index.line_col_lsp_range(range),
+        new_text,
+    })
+    .collect();
+
+    Some(edits)
+}
 
-    for node in data.root.children() {
+/// Formats a BibTeX document and returns the resulting text.
+/// Content outside of entries, strings and preambles is left untouched.
+pub fn format_bibtex_text(
+    text: &str,
+    insert_spaces: bool,
+    tab_size: u32,
+    line_length: usize,
+    options: &BibtexFormattingOptions,
+) -> String {
+    let indent = if insert_spaces {
+        " ".repeat(tab_size as usize)
+    } else {
+        "\t".to_string()
+    };
+
+    let root = bibtex::parse(text).root;
+    let line_index = LineIndex::new(text);
+    let mut output = String::new();
+    let mut last_end = 0;
+    for (range, new_text) in
+        format_nodes(&root, &line_index, &indent, tab_size, line_length, options)
+    {
+        output.push_str(&text[last_end..usize::from(range.start())]);
+        output.push_str(&new_text);
+        last_end = range.end().into();
+    }
+
+    output.push_str(&text[last_end..]);
+    output
+}
+
+fn format_nodes(
+    root: &bibtex::SyntaxNode,
+    line_index: &LineIndex,
+    indent: &str,
+    tab_size: u32,
+    line_length: usize,
+    options: &BibtexFormattingOptions,
+) -> Vec<(TextRange, String)> {
+    let mut results = Vec::new();
+    let mut entries = Vec::new();
+    for node in root.children() {
         let range = if let Some(entry) = bibtex::Entry::cast(node) {
-            entry.small_range()
+            let range = entry.small_range();
+            entries.push((results.len(), entry));
+            range
         } else if let Some(string) = bibtex::String::cast(node) {
             string.small_range()
         } else if let Some(preamble) = bibtex::Preamble::cast(node) {
@@ -57,20 +162,109 @@ pub fn format_bibtex_internal(
         };
 
         let mut formatter = Formatter::new(
-            indent.clone(),
-            request.params.options.tab_size,
+            indent.to_string(),
+            tab_size,
             line_length,
-            &document.line_index,
+            line_index,
+            options,
         );
 
         formatter.visit_node(node);
-        edits.push(TextEdit {
-            range: document.line_index.line_col_lsp_range(range),
-            new_text: formatter.output,
-        });
+        results.push((range, formatter.output));
     }
 
-    Some(edits)
+    // Entries are reordered by moving their formatted text between the existing entry slots,
+    // so strings, preambles and comments keep their position.
+    if let Some(order) = options.sort_entries {
+        let mut sorted: Vec<_> = entries
+            .iter()
+            .map(|(index, entry)| (sort_key(entry, order), *index))
+            .collect();
+        sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        let texts: Vec<_> = sorted
+            .into_iter()
+            .map(|(_, index)| results[index].1.clone())
+            .collect();
+
+        for ((index, _), text) in entries.iter().zip(texts) {
+            results[*index].1 = text;
+        }
+    }
+
+    results
+}
+
+fn sort_key(entry: &bibtex::Entry, order: BibtexSortOrder) -> Vec<(bool, String)> {
+    let key = entry
+        .key()
+        .map(|key| key.to_string().to_lowercase())
+        .unwrap_or_default();
+
+    let year = field_text(entry, &["year"])
+        .or_else(|| field_text(entry, &["date"]).map(|date| date.chars().take(4).collect()));
+
+    let author = field_text(entry, &["author", "editor"]);
+
+    // Entries without the requested field are placed after all other entries.
+    let component = |text: Option<String>| (text.is_none(), text.unwrap_or_default());
+    match order {
+        BibtexSortOrder::Key => vec![(false, key)],
+        BibtexSortOrder::Year => vec![component(year), (false, key)],
+        BibtexSortOrder::Author => vec![component(author), component(year), (false, key)],
+    }
+}
+
+fn field_text(entry: &bibtex::Entry, names: &[&str]) -> Option<String> {
+    let value = entry
+        .fields()
+        .find(|field| {
+            field.name().map_or(false, |name| {
+                names.contains(&name.text().to_lowercase().as_str())
+            })
+        })?
+        .value()?;
+
+    let words: Vec<_> = value
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.kind() == bibtex::WORD)
+        .map(|token| token.text().to_lowercase())
+        .collect();
+
+    Some(words.join(" "))
+}
+
+fn field_order(field: &bibtex::Field) -> usize {
+    let name = field
+        .name()
+        .map(|name| name.text().to_lowercase())
+        .unwrap_or_default();
+
+    FIELD_ORDER
+        .iter()
+        .position(|field_name| *field_name == name)
+        .unwrap_or(FIELD_ORDER.len())
+}
+
+fn is_empty_field(field: &bibtex::Field) -> bool {
+    field.value().map_or(true, |value| {
+        value
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|element| element.into_token())
+            .all(|token| {
+                matches!(
+                    token.kind(),
+                    bibtex::L_CURLY
+                        | bibtex::R_CURLY
+                        | bibtex::QUOTE
+                        | bibtex::WHITESPACE
+                        | bibtex::MISSING
+                )
+            })
+    })
 }
 
 struct Formatter<'a> {
@@ -80,10 +274,17 @@ struct Formatter<'a> {
     output: String,
     align: Vec<usize>,
     line_index: &'a LineIndex,
+    options: &'a BibtexFormattingOptions,
 }
 
 impl<'a> Formatter<'a> {
-    fn new(indent: String, tab_size: u32, line_length: usize, line_index: &'a LineIndex) -> Self {
+    fn new(
+        indent: String,
+        tab_size: u32,
+        line_length: usize,
+        line_index: &'a LineIndex,
+        options: &'a BibtexFormattingOptions,
+    ) -> Self {
         Self {
             indent,
             tab_size,
@@ -91,11 +292,13 @@ impl<'a> Formatter<'a> {
             output: String::new(),
             align: Vec::new(),
             line_index,
+            options,
         }
     }
 
-    fn visit_token_lowercase(&mut self, token: &bibtex::SyntaxToken) {
-        self.output.push_str(&token.text().to_lowercase());
+    fn visit_type(&mut self, token: &bibtex::SyntaxToken) {
+        let text = self.options.entry_type_case.apply(token.text());
+        self.output.push_str(&text);
     }
 
     fn should_insert_space(
@@ -115,11 +318,48 @@ impl<'a> Formatter<'a> {
             .count()
     }
 
+    fn replace_delimiters(&self, value: &bibtex::SyntaxNode) -> FxHashMap<TextRange, &'static str> {
+        let mut replacements = FxHashMap::default();
+        for token in value.children() {
+            match self.options.value_delimiters {
+                BibtexValueDelimiters::Preserve => {}
+                BibtexValueDelimiters::Braces => {
+                    if let Some(group) = token.children().find_map(bibtex::QuoteGroup::cast) {
+                        if let (Some(left), Some(right)) = (group.left_quote(), group.right_quote())
+                        {
+                            replacements.insert(left.text_range(), "{");
+                            replacements.insert(right.text_range(), "}");
+                        }
+                    }
+                }
+                BibtexValueDelimiters::Quotes => {
+                    if let Some(group) = token.children().find_map(bibtex::BraceGroup::cast) {
+                        // A quote on the top level of the group would terminate the value.
+                        let has_quote = group
+                            .syntax()
+                            .children_with_tokens()
+                            .filter_map(|element| element.into_token())
+                            .any(|token| token.kind() == bibtex::QUOTE);
+
+                        if let (false, Some(left), Some(right)) =
+                            (has_quote, group.left_curly(), group.right_curly())
+                        {
+                            replacements.insert(left.text_range(), "\"");
+                            replacements.insert(right.text_range(), "\"");
+                        }
+                    }
+                }
+            };
+        }
+
+        replacements
+    }
+
     fn visit_node(&mut self, parent: &bibtex::SyntaxNode) {
         match parent.kind() {
             bibtex::PREAMBLE => {
                 let preamble = bibtex::Preamble::cast(parent).unwrap();
-                self.visit_token_lowercase(preamble.ty().unwrap());
+                self.visit_type(preamble.ty().unwrap());
                 self.output.push('{');
                 if preamble.syntax().arity() > 0 {
                     self.align.push(self.base_align());
@@ -131,7 +371,7 @@ impl<'a> Formatter<'a> {
             }
             bibtex::STRING => {
                 let string = bibtex::String::cast(parent).unwrap();
-                self.visit_token_lowercase(string.ty().unwrap());
+                self.visit_type(string.ty().unwrap());
                 self.output.push('{');
                 if let Some(name) = string.name() {
                     self.output.push_str(name.text());
@@ -145,32 +385,39 @@ impl<'a> Formatter<'a> {
             }
             bibtex::ENTRY => {
                 let entry = bibtex::Entry::cast(parent).unwrap();
-                self.visit_token_lowercase(entry.ty().unwrap());
+                self.visit_type(entry.ty().unwrap());
                 self.output.push('{');
                 if let Some(key) = entry.key() {
                     self.output.push_str(&key.to_string());
                     self.output.push(',');
                     self.output.push('\n');
-                    for field in entry.fields() {
-                        self.visit_node(field.syntax());
+
+                    let mut fields: Vec<_> = entry
+                        .fields()
+                        .filter(|field| !self.options.drop_empty_fields || !is_empty_field(field))
+                        .collect();
+
+                    if self.options.canonical_field_order {
+                        fields.sort_by_key(field_order);
+                    }
+
+                    let name_width = if self.options.align_equals {
+                        fields
+                            .iter()
+                            .filter_map(|field| field.name())
+                            .map(|name| name.text().chars().count())
+                            .max()
+                            .unwrap_or_default()
+                    } else {
+                        0
+                    };
+
+                    for (i, field) in fields.iter().enumerate() {
+                        self.visit_field(field, name_width, i + 1 == fields.len());
                     }
                     self.output.push('}');
                 }
             }
-            bibtex::FIELD => {
-                let field = bibtex::Field::cast(parent).unwrap();
-                self.output.push_str(&self.indent);
-                let name = field.name().unwrap();
-                self.output.push_str(name.text());
-                self.output.push_str(" = ");
-                if let Some(value) = field.value() {
-                    let count = name.text().chars().count();
-                    self.align.push(self.tab_size as usize + count + 3);
-                    self.visit_node(value.syntax());
-                    self.output.push(',');
-                    self.output.push('\n');
-                }
-            }
             bibtex::VALUE => {
                 let tokens: Vec<_> = parent
                     .descendants_with_tokens()
@@ -178,14 +425,25 @@ impl<'a> Formatter<'a> {
                     .filter(|token| token.kind() != bibtex::WHITESPACE)
                     .collect();
 
-                self.output.push_str(tokens[0].text());
+                let replacements: FxHashMap<TextRange, &str> = self.replace_delimiters(parent);
+                let texts: Vec<_> = tokens
+                    .iter()
+                    .map(|token| {
+                        replacements
+                            .get(&token.text_range())
+                            .copied()
+                            .unwrap_or_else(|| token.text())
+                    })
+                    .collect();
+
+                self.output.push_str(texts[0]);
 
                 let align = self.align.pop().unwrap_or_default();
-                let mut length = align + tokens[0].text().chars().count();
+                let mut length = align + texts[0].chars().count();
                 for i in 1..tokens.len() {
                     let previous = tokens[i - 1];
                     let current = tokens[i];
-                    let current_length = current.text().chars().count();
+                    let current_length = texts[i].chars().count();
 
                     let insert_space = self.should_insert_space(previous, current);
                     let space_length = if insert_space { 1 } else { 0 };
@@ -201,7 +459,7 @@ impl<'a> Formatter<'a> {
                         self.output.push(' ');
                         length += 1;
                     }
-                    self.output.push_str(current.text());
+                    self.output.push_str(texts[i]);
                     length += current_length;
                 }
             }
@@ -220,13 +478,38 @@ impl<'a> Formatter<'a> {
             _ => unreachable!(),
         }
     }
+
+    fn visit_field(&mut self, field: &bibtex::Field, name_width: usize, is_last: bool) {
+        self.output.push_str(&self.indent);
+        let name = self
+            .options
+            .field_name_case
+            .apply(field.name().unwrap().text());
+
+        let count = name.chars().count();
+        self.output.push_str(&name);
+        for _ in count..name_width {
+            self.output.push(' ');
+        }
+
+        self.output.push_str(" = ");
+        if let Some(value) = field.value() {
+            self.align
+                .push(self.tab_size as usize + count.max(name_width) + 3);
+            self.visit_node(value.syntax());
+            if !is_last || self.options.trailing_comma {
+                self.output.push(',');
+            }
+            self.output.push('\n');
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use insta::{assert_debug_snapshot, assert_display_snapshot};
 
-    use crate::features::testing::FeatureTester;
+    use crate::{features::testing::FeatureTester, BibtexCase};
 
     use super::*;
 
@@ -378,4 +661,89 @@ mod tests {
 
         assert_display_snapshot!(edit.new_text);
     }
+
+    #[test]
+    fn test_format_text() {
+        let text = "% Comment\n@article{foo, bar = baz}\n\n@string{ foo =\"bar\"}\n";
+        let actual_text =
+            format_bibtex_text(text, true, 2, 80, &BibtexFormattingOptions::default());
+        assert_eq!(
+            actual_text,
+            "% Comment\n@article{foo,\n  bar = baz,\n}\n\n@string{foo = \"bar\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_by_key() {
+        let text = "@article{b, year = 2000}\n@string{x = \"y\"}\n@article{a, year = 1999}\n";
+        let options = BibtexFormattingOptions {
+            sort_entries: Some(BibtexSortOrder::Key),
+            ..BibtexFormattingOptions::default()
+        };
+
+        let actual_text = format_bibtex_text(text, true, 2, 80, &options);
+        assert_eq!(
+            actual_text,
+            "@article{a,\n  year = 1999,\n}\n@string{x = \"y\"}\n@article{b,\n  year = 2000,\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_sort_by_year() {
+        let text = "@article{a, year = 2001}\n@article{b, date = {1999-01-01}}\n@article{c, title = {Foo}}";
+        let options = BibtexFormattingOptions {
+            sort_entries: Some(BibtexSortOrder::Year),
+            ..BibtexFormattingOptions::default()
+        };
+
+        let actual_text = format_bibtex_text(text, true, 2, 80, &options);
+        assert_eq!(
+            actual_text,
+            "@article{b,\n  date = {1999-01-01},\n}\n@article{a,\n  year = 2001,\n}\n@article{c,\n  title = {Foo},\n}"
+        );
+    }
+
+    #[test]
+    fn test_canonical_style() {
+        let text = "@ARTICLE{foo, year = {2020}, note = {}, title = {Bar}, author = \"Doe\"}";
+        let options = BibtexFormattingOptions {
+            align_equals: true,
+            canonical_field_order: true,
+            field_name_case: BibtexCase::Uppercase,
+            trailing_comma: false,
+            value_delimiters: BibtexValueDelimiters::Quotes,
+            drop_empty_fields: true,
+            ..BibtexFormattingOptions::default()
+        };
+
+        let actual_text = format_bibtex_text(text, true, 2, 80, &options);
+        assert_eq!(
+            actual_text,
+            "@article{foo,\n  AUTHOR = \"Doe\",\n  TITLE  = \"Bar\",\n  YEAR   = \"2020\"\n}"
+        );
+    }
+
+    #[test]
+    fn test_quotes_to_braces() {
+        let text = "@article{foo, title = \"Bar\" # baz}";
+        let options = BibtexFormattingOptions {
+            value_delimiters: BibtexValueDelimiters::Braces,
+            ..BibtexFormattingOptions::default()
+        };
+
+        let actual_text = format_bibtex_text(text, true, 2, 80, &options);
+        assert_eq!(actual_text, "@article{foo,\n  title = {Bar} # baz,\n}");
+    }
+
+    #[test]
+    fn test_braces_with_quote() {
+        let text = "@article{foo, title = {A \"B\"}}";
+        let options = BibtexFormattingOptions {
+            value_delimiters: BibtexValueDelimiters::Quotes,
+            ..BibtexFormattingOptions::default()
+        };
+
+        let actual_text = format_bibtex_text(text, true, 2, 80, &options);
+        assert_eq!(actual_text, "@article{foo,\n  title = {A \"B\"},\n}");
+    }
 }
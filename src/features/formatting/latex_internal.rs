@@ -0,0 +1,414 @@
+use cancellation::CancellationToken;
+use cstree::{TextLen, TextRange};
+use lsp_types::{DocumentFormattingParams, TextEdit};
+
+use crate::{
+    features::FeatureRequest,
+    syntax::{latex, CstNode},
+    LineIndexExt,
+};
+
+pub fn format_latex_internal(
+    request: &FeatureRequest<DocumentFormattingParams>,
+    cancellation_token: &CancellationToken,
+) -> Option<Vec<TextEdit>> {
+    let mut indent = String::new();
+    if request.params.options.insert_spaces {
+        for _ in 0..request.params.options.tab_size {
+            indent.push(' ');
+        }
+    } else {
+        indent.push('\t');
+    }
+
+    let line_length = {
+        request
+            .context
+            .options_for(&request.main_document().uri)
+            .formatter_line_length
+            .map(|value| {
+                if value <= 0 {
+                    usize::MAX
+                } else {
+                    value as usize
+                }
+            })
+            .unwrap_or(80)
+    };
+
+    let document = request.main_document();
+    let data = document.data.as_latex()?;
+
+    let line_ending = if document.text.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+
+    let mut formatter = Formatter::new(
+        indent,
+        request.params.options.tab_size,
+        line_length,
+        line_ending,
+    );
+
+    let lines = split_lines(&data.root, cancellation_token)?;
+    formatter.visit_lines(&lines.items);
+    if lines.trailing_newline {
+        formatter.output.push_str(line_ending);
+    }
+
+    if formatter.output == document.text {
+        return Some(Vec::new());
+    }
+
+    let range = TextRange::new(0.into(), document.text.text_len());
+    Some(vec![TextEdit {
+        range: document.line_index.line_col_lsp_range(range),
+        new_text: formatter.output,
+    }])
+}
+
+#[derive(Debug)]
+enum Piece<'a> {
+    Token(&'a latex::SyntaxToken),
+    Space { text: String, breakable: bool },
+}
+
+#[derive(Debug, Default)]
+struct Line<'a> {
+    level: usize,
+    blank_before: bool,
+    pieces: Vec<Piece<'a>>,
+}
+
+impl<'a> Line<'a> {
+    fn can_wrap(&self) -> bool {
+        self.pieces.iter().all(|piece| match piece {
+            Piece::Token(token) => {
+                token.kind() != latex::VERBATIM
+                    && !(token.kind() == latex::GENERIC_COMMAND_NAME
+                        && is_inline_verbatim(token.text()))
+            }
+            Piece::Space { .. } => true,
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Lines<'a> {
+    items: Vec<Line<'a>>,
+    trailing_newline: bool,
+}
+
+fn split_lines<'a>(
+    root: &'a latex::SyntaxNode,
+    cancellation_token: &CancellationToken,
+) -> Option<Lines<'a>> {
+    let mut lines = Lines::default();
+    let mut current = Line::default();
+    let mut newline_count = 0;
+    let mut space = String::new();
+    let mut breakable = true;
+
+    for token in root
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+    {
+        if cancellation_token.is_canceled() {
+            return None;
+        }
+
+        match token.kind() {
+            latex::LINE_BREAK | latex::WHITESPACE => {
+                let count = count_newlines(token.text());
+                if count > 0 {
+                    newline_count += count;
+                    space.clear();
+                    breakable = true;
+                } else if newline_count == 0 {
+                    space.push_str(token.text());
+                    breakable &= token.parent().kind() == latex::TEXT;
+                }
+            }
+            _ if token.text().is_empty() => {}
+            _ => {
+                if newline_count > 0 {
+                    if !current.pieces.is_empty() {
+                        lines.items.push(std::mem::take(&mut current));
+                    }
+
+                    current.blank_before = newline_count > 1 && !lines.items.is_empty();
+                    newline_count = 0;
+                } else if !space.is_empty() {
+                    if !current.pieces.is_empty() {
+                        current.pieces.push(Piece::Space {
+                            text: std::mem::take(&mut space),
+                            breakable: breakable && token.kind() != latex::COMMENT,
+                        });
+                    }
+
+                    space.clear();
+                    breakable = true;
+                }
+
+                if current.pieces.is_empty() {
+                    current.level = indent_level(token);
+                }
+
+                current.pieces.push(Piece::Token(token));
+            }
+        }
+    }
+
+    if !current.pieces.is_empty() {
+        lines.items.push(current);
+    }
+
+    lines.trailing_newline = newline_count > 0 && !lines.items.is_empty();
+    Some(lines)
+}
+
+fn count_newlines(text: &str) -> usize {
+    let count = text.matches('\n').count();
+    if count == 0 {
+        text.matches('\r').count()
+    } else {
+        count
+    }
+}
+
+fn indent_level(token: &latex::SyntaxToken) -> usize {
+    let mut level = 0;
+    let mut child_kind = None;
+    let mut node = Some(token.parent());
+    while let Some(parent) = node {
+        match parent.kind() {
+            latex::ENVIRONMENT
+                if !matches!(child_kind, Some(latex::BEGIN) | Some(latex::END))
+                    && !is_document_environment(parent) =>
+            {
+                level += 1;
+            }
+            latex::EQUATION if child_kind.is_some() => {
+                level += 1;
+            }
+            _ => {}
+        }
+
+        child_kind = Some(parent.kind());
+        node = parent.parent();
+    }
+
+    level
+}
+
+fn is_document_environment(node: &latex::SyntaxNode) -> bool {
+    latex::Environment::cast(node)
+        .and_then(|environment| environment.begin())
+        .and_then(|begin| begin.name())
+        .and_then(|name| name.key())
+        .map_or(false, |name| name.to_string() == "document")
+}
+
+fn is_inline_verbatim(name: &str) -> bool {
+    name.starts_with("\\verb") || name == "\\lstinline" || name == "\\mintinline"
+}
+
+struct Formatter {
+    indent: String,
+    tab_size: u32,
+    line_length: usize,
+    line_ending: &'static str,
+    output: String,
+}
+
+impl Formatter {
+    fn new(indent: String, tab_size: u32, line_length: usize, line_ending: &'static str) -> Self {
+        Self {
+            indent,
+            tab_size,
+            line_length,
+            line_ending,
+            output: String::new(),
+        }
+    }
+
+    fn visit_lines(&mut self, lines: &[Line]) {
+        for (i, line) in lines.iter().enumerate() {
+            if i > 0 {
+                self.output.push_str(self.line_ending);
+                if line.blank_before {
+                    self.output.push_str(self.line_ending);
+                }
+            }
+
+            self.visit_line(line);
+        }
+    }
+
+    fn visit_line(&mut self, line: &Line) {
+        let indent = self.indent.repeat(line.level);
+        let indent_length = self.measure(&indent);
+        self.output.push_str(&indent);
+
+        let mut chunks = vec![(String::new(), String::new())];
+        for piece in &line.pieces {
+            match piece {
+                Piece::Token(token) => chunks.last_mut().unwrap().1.push_str(token.text()),
+                Piece::Space {
+                    text,
+                    breakable: true,
+                } => chunks.push((text.clone(), String::new())),
+                Piece::Space {
+                    text,
+                    breakable: false,
+                } => chunks.last_mut().unwrap().1.push_str(text),
+            }
+        }
+
+        let total_length = chunks
+            .iter()
+            .map(|(space, text)| self.measure(space) + self.measure(text))
+            .sum::<usize>();
+
+        if !line.can_wrap() || indent_length + total_length <= self.line_length {
+            for (space, text) in chunks {
+                self.output.push_str(&space);
+                self.output.push_str(&text);
+            }
+            return;
+        }
+
+        let mut length = indent_length;
+        for (space, text) in chunks {
+            let space_length = self.measure(&space);
+            let text_length = self.measure(&text);
+            if length > indent_length && length + space_length + text_length > self.line_length {
+                self.output.push_str(self.line_ending);
+                self.output.push_str(&indent);
+                length = indent_length;
+            } else {
+                self.output.push_str(&space);
+                length += space_length;
+            }
+
+            self.output.push_str(&text);
+            length += text_length;
+        }
+    }
+
+    fn measure(&self, text: &str) -> usize {
+        text.chars()
+            .map(|c| if c == '\t' { self.tab_size as usize } else { 1 })
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::features::testing::FeatureTester;
+
+    use super::*;
+
+    fn format(text: &str) -> String {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", text)])
+            .main("main.tex")
+            .build()
+            .formatting();
+
+        format_latex_internal(&request, CancellationToken::none())
+            .unwrap()
+            .pop()
+            .map(|edit| edit.new_text)
+            .unwrap_or_else(|| text.trim().to_string())
+    }
+
+    #[test]
+    fn test_indent_environments() {
+        let actual = format(indoc!(
+            r#"
+                \begin{itemize}
+                \item foo
+                    \begin{enumerate}
+                  \item bar
+                \end{enumerate}
+                \end{itemize}
+            "#
+        ));
+
+        assert_eq!(
+            actual,
+            "\\begin{itemize}\n\t\\item foo\n\t\\begin{enumerate}\n\t\t\\item bar\n\t\\end{enumerate}\n\\end{itemize}"
+        );
+    }
+
+    #[test]
+    fn test_document_environment() {
+        let actual = format(indoc!(
+            r#"
+                \begin{document}
+                  foo
+
+
+
+                bar
+                \end{document}
+            "#
+        ));
+
+        assert_eq!(actual, "\\begin{document}\nfoo\n\nbar\n\\end{document}");
+    }
+
+    #[test]
+    fn test_verbatim() {
+        let actual = format(indoc!(
+            r#"
+                \begin{center}
+                \begin{verbatim}
+                  foo
+                    bar
+                \end{verbatim}
+                \end{center}
+            "#
+        ));
+
+        assert_eq!(
+            actual,
+            "\\begin{center}\n\t\\begin{verbatim}\n  foo\n    bar\n\\end{verbatim}\n\\end{center}"
+        );
+    }
+
+    #[test]
+    fn test_block_comment() {
+        let actual = format(indoc!(
+            r#"
+                \begin{center}
+                \iffalse
+                  foo
+                \fi
+                \end{center}
+            "#
+        ));
+
+        assert_eq!(
+            actual,
+            "\\begin{center}\n\t\\iffalse\n  foo\n\\fi\n\\end{center}"
+        );
+    }
+
+    #[test]
+    fn test_wrap_long_lines() {
+        let actual = format(
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Sed do eiusmod tempor incididunt ut labore et dolore magna aliqua.",
+        );
+
+        assert_eq!(
+            actual,
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit. Sed do eiusmod tempor\nincididunt ut labore et dolore magna aliqua."
+        );
+    }
+}
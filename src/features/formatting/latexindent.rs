@@ -17,11 +17,11 @@ pub fn format_with_latexindent(
     let directory = tempdir().ok()?;
     let document = request.main_document();
 
-    let options = request.context.options.read().unwrap();
+    let options = request.context.options_for(&document.uri);
     let current_dir = options
         .root_directory
         .as_ref()
-        .cloned()
+        .map(|root_dir| request.context.directory_for(&document.uri).join(root_dir))
         .or_else(|| {
             if document.uri.scheme() == "file" {
                 document
@@ -43,8 +43,6 @@ pub fn format_with_latexindent(
 
     let modify_line_breaks = options.latexindent.modify_line_breaks;
 
-    drop(options);
-
     let path = directory.path();
     let _ = fs::copy(
         current_dir.join("localSettings.yaml"),
@@ -1,4 +1,5 @@
 mod bibtex_internal;
+mod latex_internal;
 mod latexindent;
 
 use cancellation::CancellationToken;
@@ -6,21 +7,30 @@ use lsp_types::{DocumentFormattingParams, TextEdit};
 
 use crate::{BibtexFormatter, LatexFormatter};
 
-use self::{bibtex_internal::format_bibtex_internal, latexindent::format_with_latexindent};
+use self::{
+    bibtex_internal::format_bibtex_internal, latex_internal::format_latex_internal,
+    latexindent::format_with_latexindent,
+};
 
 use super::FeatureRequest;
 
+pub use self::bibtex_internal::format_bibtex_text;
+
 pub fn format_source_code(
     request: FeatureRequest<DocumentFormattingParams>,
     cancellation_token: &CancellationToken,
 ) -> Option<Vec<TextEdit>> {
     let mut edits = None;
-    if request.context.options.read().unwrap().bibtex_formatter == BibtexFormatter::Texlab {
+    let options = request
+        .context
+        .options_for(&request.main_document().uri);
+
+    if options.bibtex_formatter == BibtexFormatter::Texlab {
         edits = edits.or_else(|| format_bibtex_internal(&request, cancellation_token));
     }
 
-    if request.context.options.read().unwrap().latex_formatter == LatexFormatter::Texlab {
-        edits = edits.or_else(|| Some(vec![]));
+    if options.latex_formatter == LatexFormatter::Texlab {
+        edits = edits.or_else(|| format_latex_internal(&request, cancellation_token));
     }
 
     edits = edits.or_else(|| format_with_latexindent(&request, cancellation_token));
@@ -1,6 +1,6 @@
 use std::{
     io,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Stdio},
 };
 
@@ -10,6 +10,8 @@ use lsp_types::TextDocumentPositionParams;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
 
+use crate::WorkspaceSubset;
+
 use super::FeatureRequest;
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize_repr, Deserialize_repr)]
@@ -33,9 +35,7 @@ pub fn execute_forward_search(
     let options = {
         request
             .context
-            .options
-            .read()
-            .unwrap()
+            .options_for(&request.main_document().uri)
             .forward_search
             .clone()
             .unwrap_or_default()
@@ -47,8 +47,30 @@ pub fn execute_forward_search(
         });
     }
 
-    let root_document = request
-        .subset
+    let pdf_path = find_pdf_path(&request.subset)?;
+    let tex_path = request.main_document().uri.to_file_path().ok()?;
+
+    let args: Vec<String> = options
+        .args
+        .unwrap()
+        .into_iter()
+        .flat_map(|arg| {
+            replace_placeholder(&tex_path, &pdf_path, request.params.position.line, arg)
+        })
+        .collect();
+
+    let status = match run_process(options.executable.unwrap(), args) {
+        Ok(()) => ForwardSearchStatus::SUCCESS,
+        Err(why) => {
+            error!("Unable to execute forward search: {}", why);
+            ForwardSearchStatus::FAILURE
+        }
+    };
+    Some(ForwardSearchResult { status })
+}
+
+pub(super) fn find_pdf_path(subset: &WorkspaceSubset) -> Option<PathBuf> {
+    let root_document = subset
         .documents
         .iter()
         .find(|document| {
@@ -67,33 +89,12 @@ pub fn execute_forward_search(
         .filter(|document| document.uri.scheme() == "file")?;
 
     let data = root_document.data.as_latex()?;
-    let pdf_path = data
-        .extras
+    data.extras
         .implicit_links
         .pdf
         .iter()
         .filter_map(|uri| uri.to_file_path().ok())
-        .find(|path| path.exists())?;
-
-    let tex_path = request.main_document().uri.to_file_path().ok()?;
-
-    let args: Vec<String> = options
-        .args
-        .unwrap()
-        .into_iter()
-        .flat_map(|arg| {
-            replace_placeholder(&tex_path, &pdf_path, request.params.position.line, arg)
-        })
-        .collect();
-
-    let status = match run_process(options.executable.unwrap(), args) {
-        Ok(()) => ForwardSearchStatus::SUCCESS,
-        Err(why) => {
-            error!("Unable to execute forward search: {}", why);
-            ForwardSearchStatus::FAILURE
-        }
-    };
-    Some(ForwardSearchResult { status })
+        .find(|path| path.exists())
 }
 
 fn replace_placeholder(
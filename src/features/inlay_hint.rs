@@ -0,0 +1,311 @@
+use cancellation::CancellationToken;
+use cstree::TextSize;
+use lsp_types::{Position, Range, TextDocumentIdentifier, WorkDoneProgressParams};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    find_label_number, render_label,
+    syntax::{
+        bibtex,
+        latex::{self, HasCurly},
+        CstNode,
+    },
+    LineIndexExt, WorkspaceSubset,
+};
+
+use super::FeatureRequest;
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHintParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+
+    #[serde(flatten)]
+    pub work_done_progress_params: WorkDoneProgressParams,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHint {
+    pub position: Position,
+    pub label: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tooltip: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub padding_left: Option<bool>,
+}
+
+pub fn find_inlay_hints(
+    request: FeatureRequest<InlayHintParams>,
+    cancellation_token: &CancellationToken,
+) -> Vec<InlayHint> {
+    let mut hints = Vec::new();
+    let main_document = request.main_document();
+    let data = match main_document.data.as_latex() {
+        Some(data) => data,
+        None => return hints,
+    };
+
+    let range = request.params.range;
+    for node in data.root.descendants() {
+        if cancellation_token.is_canceled() {
+            break;
+        }
+
+        if let Some((offset, label, tooltip)) = find_label_hint(&request.subset, node)
+            .or_else(|| find_citation_hint(&request.subset, node))
+        {
+            let position = main_document.line_index.line_col_lsp(offset);
+            if range.start <= position && position <= range.end {
+                hints.push(InlayHint {
+                    position,
+                    label,
+                    tooltip,
+                    padding_left: Some(true),
+                });
+            }
+        }
+    }
+
+    hints
+}
+
+fn find_label_hint(
+    subset: &WorkspaceSubset,
+    node: &latex::SyntaxNode,
+) -> Option<(TextSize, String, Option<String>)> {
+    let reference = latex::LabelReference::cast(node)?;
+    let name_list = reference.name_list()?;
+    name_list.right_curly()?;
+
+    let mut numbers = Vec::new();
+    let mut tooltips = Vec::new();
+    for name in name_list.keys().map(|key| key.to_string()) {
+        if let Some(number) = find_label_number(subset, &name) {
+            numbers.push(number.to_string());
+            if let Some(label) = render_label(subset, &name, None) {
+                tooltips.push(label.reference());
+            }
+        }
+    }
+
+    if numbers.is_empty() {
+        return None;
+    }
+
+    let tooltip = if tooltips.is_empty() {
+        None
+    } else {
+        Some(tooltips.join("\n"))
+    };
+
+    Some((
+        name_list.small_range().end(),
+        format!("→ {}", numbers.join(", ")),
+        tooltip,
+    ))
+}
+
+fn find_citation_hint(
+    subset: &WorkspaceSubset,
+    node: &latex::SyntaxNode,
+) -> Option<(TextSize, String, Option<String>)> {
+    let citation = latex::Citation::cast(node)?;
+    let key_list = citation.key_list()?;
+    key_list.right_curly()?;
+
+    let tags: Vec<_> = key_list
+        .keys()
+        .filter_map(|key| find_author_year(subset, &key.to_string()))
+        .collect();
+
+    if tags.is_empty() {
+        return None;
+    }
+
+    Some((key_list.small_range().end(), tags.join("; "), None))
+}
+
+fn find_author_year(subset: &WorkspaceSubset, key: &str) -> Option<String> {
+    let entry = subset
+        .documents
+        .iter()
+        .filter_map(|document| document.data.as_bibtex())
+        .find_map(|data| {
+            data.root
+                .children()
+                .filter_map(bibtex::Entry::cast)
+                .find(|entry| entry.key().map(|key| key.to_string()).as_deref() == Some(key))
+        })?;
+
+    let field_text = |names: &[&str]| {
+        entry
+            .fields()
+            .find(|field| {
+                field.name().map_or(false, |name| {
+                    names.contains(&name.text().to_lowercase().as_str())
+                })
+            })
+            .and_then(|field| field.value())
+            .map(|value| value_text(&value))
+    };
+
+    let author = field_text(&["author", "editor"]).and_then(|authors| {
+        let authors: Vec<_> = authors.split(" and ").map(str::trim).collect();
+        let first = authors.first()?;
+        let last_name = match first.split_once(',') {
+            Some((last_name, _)) => last_name.trim(),
+            None => first.split_whitespace().last()?,
+        };
+
+        if authors.len() > 1 {
+            Some(format!("{} et al.", last_name))
+        } else {
+            Some(last_name.to_string())
+        }
+    });
+
+    let year = field_text(&["year"])
+        .or_else(|| field_text(&["date"]))
+        .map(|text| text.chars().take(4).collect::<String>())
+        .filter(|year| !year.is_empty());
+
+    match (author, year) {
+        (Some(author), Some(year)) => Some(format!("{} {}", author, year)),
+        (Some(author), None) => Some(author),
+        (None, Some(year)) => Some(year),
+        (None, None) => None,
+    }
+}
+
+fn value_text(value: &bibtex::Value) -> String {
+    let mut words = Vec::new();
+    for token in value
+        .syntax()
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+    {
+        match token.kind() {
+            bibtex::WORD | bibtex::COMMA => words.push(token.text()),
+            bibtex::WHITESPACE => words.push(" "),
+            _ => {}
+        }
+    }
+
+    words
+        .concat()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::features::testing::FeatureTester;
+
+    use super::*;
+
+    #[test]
+    fn test_empty_latex_document() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", "")])
+            .main("main.tex")
+            .build()
+            .inlay_hint();
+
+        let actual_hints = find_inlay_hints(request, CancellationToken::none());
+
+        assert!(actual_hints.is_empty());
+    }
+
+    #[test]
+    fn test_empty_bibtex_document() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.bib", "")])
+            .main("main.bib")
+            .build()
+            .inlay_hint();
+
+        let actual_hints = find_inlay_hints(request, CancellationToken::none());
+
+        assert!(actual_hints.is_empty());
+    }
+
+    #[test]
+    fn test_label_reference() {
+        let request = FeatureTester::builder()
+            .files(vec![
+                (
+                    "foo.tex",
+                    indoc!(
+                        r#"
+                            \section{Foo}
+                            \label{sec:foo}
+                            \ref{sec:foo}
+                        "#
+                    ),
+                ),
+                ("foo.aux", r#"\newlabel{sec:foo}{{1}{1}}"#),
+            ])
+            .main("foo.tex")
+            .build()
+            .inlay_hint();
+
+        let actual_hints = find_inlay_hints(request, CancellationToken::none());
+
+        assert_eq!(
+            actual_hints,
+            vec![InlayHint {
+                position: Position::new(2, 13),
+                label: "→ 1".into(),
+                tooltip: Some("Section 1 (Foo)".into()),
+                padding_left: Some(true),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_citation() {
+        let request = FeatureTester::builder()
+            .files(vec![
+                (
+                    "main.tex",
+                    indoc!(
+                        r#"
+                            \addbibresource{main.bib}
+                            \cite{foo, bar}
+                        "#
+                    ),
+                ),
+                (
+                    "main.bib",
+                    indoc!(
+                        r#"
+                            @article{foo, author = {Doe, John and Smith, Jane}, year = {2020}}
+                            @book{bar, author = {Max Mustermann}, date = {2019-01-01}}
+                        "#
+                    ),
+                ),
+            ])
+            .main("main.tex")
+            .build()
+            .inlay_hint();
+
+        let actual_hints = find_inlay_hints(request, CancellationToken::none());
+
+        assert_eq!(
+            actual_hints,
+            vec![InlayHint {
+                position: Position::new(1, 15),
+                label: "Doe et al. 2020; Mustermann 2019".into(),
+                tooltip: None,
+                padding_left: Some(true),
+            }]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use cancellation::CancellationToken;
+use lsp_types::{LinkedEditingRangeParams, LinkedEditingRanges};
+
+use crate::LineIndexExt;
+
+use super::{cursor::CursorContext, FeatureRequest};
+
+pub fn find_linked_editing_ranges(
+    request: FeatureRequest<LinkedEditingRangeParams>,
+    cancellation_token: &CancellationToken,
+) -> Option<LinkedEditingRanges> {
+    let context = CursorContext::new(request);
+    cancellation_token.result().ok()?;
+
+    let [(begin_name, begin_range), (end_name, end_range)] = context.find_environment_names()?;
+    if begin_name != end_name {
+        return None;
+    }
+
+    let line_index = &context.request.main_document().line_index;
+    Some(LinkedEditingRanges {
+        ranges: vec![
+            line_index.line_col_lsp_range(begin_range),
+            line_index.line_col_lsp_range(end_range),
+        ],
+        word_pattern: Some(r"[^\s{}\\]*".to_string()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use lsp_types::Range;
+
+    use crate::{features::testing::FeatureTester, RangeExt};
+
+    use super::*;
+
+    #[test]
+    fn test_empty_latex_document() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", "")])
+            .main("main.tex")
+            .line(0)
+            .character(0)
+            .build()
+            .linked_editing_range();
+
+        let actual_ranges = find_linked_editing_ranges(request, CancellationToken::none());
+
+        assert_eq!(actual_ranges, None);
+    }
+
+    #[test]
+    fn test_begin() {
+        let request = FeatureTester::builder()
+            .files(vec![(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \begin{align*}
+                        \end{align*}
+                    "#
+                ),
+            )])
+            .main("main.tex")
+            .line(0)
+            .character(9)
+            .build()
+            .linked_editing_range();
+
+        let actual_ranges = find_linked_editing_ranges(request, CancellationToken::none())
+            .unwrap()
+            .ranges;
+
+        assert_eq!(
+            actual_ranges,
+            vec![
+                Range::new_simple(0, 7, 0, 13),
+                Range::new_simple(1, 5, 1, 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mismatched_names() {
+        let request = FeatureTester::builder()
+            .files(vec![(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \begin{foo}
+                        \end{bar}
+                    "#
+                ),
+            )])
+            .main("main.tex")
+            .line(1)
+            .character(6)
+            .build()
+            .linked_editing_range();
+
+        let actual_ranges = find_linked_editing_ranges(request, CancellationToken::none());
+
+        assert_eq!(actual_ranges, None);
+    }
+}
@@ -1,20 +1,29 @@
 mod build;
+mod code_action;
+mod code_lens;
+mod color;
 #[cfg(feature = "completion")]
 mod completion;
 mod cursor;
 mod definition;
+mod file_rename;
 mod folding;
 mod formatting;
 mod forward_search;
 mod highlight;
 mod hover;
+mod inlay_hint;
 mod link;
+mod linked_editing;
 mod lsp_kinds;
 mod reference;
 mod rename;
+mod selection;
 #[cfg(feature = "semantic")]
 mod semantic;
+mod signature_help;
 mod symbol;
+mod synctex;
 
 use std::sync::Arc;
 
@@ -23,19 +32,34 @@ use crate::{Document, ServerContext, Workspace, WorkspaceSubset};
 #[cfg(feature = "completion")]
 pub use self::completion::{complete, CompletionItemData, COMPLETION_LIMIT};
 #[cfg(feature = "semantic")]
-pub use self::semantic::{find_semantic_tokens_range, legend};
+pub use self::semantic::{
+    find_semantic_tokens_full, find_semantic_tokens_full_delta, find_semantic_tokens_range, legend,
+    SemanticTokensCache,
+};
 pub use self::{
     build::{BuildEngine, BuildParams, BuildResult, BuildStatus},
+    code_action::find_code_actions,
+    code_lens::find_code_lenses,
+    color::{find_color_presentations, find_document_colors},
     definition::goto_definition,
+    file_rename::find_file_rename_edits,
     folding::find_foldings,
-    formatting::format_source_code,
+    formatting::{format_bibtex_text, format_source_code},
     forward_search::{execute_forward_search, ForwardSearchResult, ForwardSearchStatus},
     highlight::find_document_highlights,
     hover::find_hover,
+    inlay_hint::{find_inlay_hints, InlayHint, InlayHintParams},
     link::find_document_links,
+    linked_editing::find_linked_editing_ranges,
     reference::find_all_references,
     rename::{prepare_rename_all, rename_all},
+    selection::find_selection_ranges,
+    signature_help::find_signature_help,
     symbol::{find_document_symbols, find_workspace_symbols},
+    synctex::{
+        find_forward_search_location, find_inverse_search_location, ForwardSearchLocation,
+        InverseSearchParams,
+    },
 };
 
 #[derive(Clone)]
@@ -57,17 +81,19 @@ mod testing {
     use std::{path::PathBuf, sync::Arc};
 
     use lsp_types::{
-        ClientCapabilities, ClientInfo, CompletionParams, DocumentFormattingParams,
-        DocumentHighlightParams, DocumentLinkParams, FoldingRangeParams, FormattingOptions,
-        GotoDefinitionParams, HoverParams, PartialResultParams, Position, ReferenceContext,
-        ReferenceParams, RenameParams, TextDocumentIdentifier, TextDocumentPositionParams,
-        WorkDoneProgressParams,
+        ClientCapabilities, ClientInfo, CodeActionContext, CodeActionParams, CodeLensParams, Color,
+        ColorPresentationParams, CompletionParams, DocumentColorParams, DocumentFormattingParams,
+        DocumentHighlightParams, DocumentLinkParams, FileRename, FoldingRangeParams,
+        FormattingOptions, GotoDefinitionParams, HoverParams, LinkedEditingRangeParams,
+        PartialResultParams, Position, Range, ReferenceContext, ReferenceParams, RenameFilesParams,
+        RenameParams, SelectionRangeParams, SignatureHelpParams, TextDocumentIdentifier,
+        TextDocumentPositionParams, WorkDoneProgressParams,
     };
     use typed_builder::TypedBuilder;
 
     use crate::{
-        create_workspace_fast, distro::Resolver, DocumentLanguage, Options, ServerContext, Uri,
-        Workspace, WorkspaceSource,
+        create_workspace_fast, distro::Resolver, BuildOptions, DocumentLanguage, Options,
+        ServerContext, Uri, Workspace, WorkspaceSource,
     };
 
     use super::*;
@@ -107,6 +133,9 @@ mod testing {
 
         #[builder(default, setter(strip_option))]
         aux_directory: Option<PathBuf>,
+
+        #[builder(default)]
+        build_options: BuildOptions,
     }
 
     impl<'a> FeatureTester<'a> {
@@ -119,6 +148,7 @@ mod testing {
             let mut options = Options::default();
             options.aux_directory = self.aux_directory.clone();
             options.root_directory = self.root_directory.clone();
+            options.build = self.build_options.clone();
             options
         }
 
@@ -131,7 +161,7 @@ mod testing {
             let cx = ServerContext::new(self.current_directory.clone());
             *cx.client_capabilities.lock().unwrap() = self.client_capabilities.clone();
             *cx.client_info.lock().unwrap() = self.client_info.clone();
-            *cx.options.write().unwrap() = self.options();
+            *cx.options.write().unwrap() = Arc::new(self.options());
             *cx.resolver.lock().unwrap() = self.resolver.clone();
             Arc::new(cx)
         }
@@ -164,6 +194,13 @@ mod testing {
             }
         }
 
+        pub fn build_document(self) -> FeatureRequest<BuildParams> {
+            let params = BuildParams {
+                text_document: self.identifier(),
+            };
+            self.request(params)
+        }
+
         pub fn link(self) -> FeatureRequest<DocumentLinkParams> {
             let text_document = self.identifier();
             let params = DocumentLinkParams {
@@ -199,6 +236,15 @@ mod testing {
             self.request(params)
         }
 
+        pub fn code_lens(self) -> FeatureRequest<CodeLensParams> {
+            let params = CodeLensParams {
+                text_document: self.identifier(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            };
+            self.request(params)
+        }
+
         pub fn hover(self) -> FeatureRequest<HoverParams> {
             let params = HoverParams {
                 text_document_position_params: TextDocumentPositionParams::new(
@@ -257,6 +303,96 @@ mod testing {
             self.request(params)
         }
 
+        pub fn document_color(self) -> FeatureRequest<DocumentColorParams> {
+            let params = DocumentColorParams {
+                text_document: self.identifier(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            };
+            self.request(params)
+        }
+
+        pub fn color_presentation(self) -> FeatureRequest<ColorPresentationParams> {
+            let params = ColorPresentationParams {
+                text_document: self.identifier(),
+                color: Color {
+                    red: 0.0,
+                    green: 0.0,
+                    blue: 0.0,
+                    alpha: 1.0,
+                },
+                range: Range::default(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            };
+            self.request(params)
+        }
+
+        pub fn linked_editing_range(self) -> FeatureRequest<LinkedEditingRangeParams> {
+            let params = LinkedEditingRangeParams {
+                text_document_position_params: TextDocumentPositionParams::new(
+                    self.identifier(),
+                    Position::new(self.line, self.character),
+                ),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            };
+            self.request(params)
+        }
+
+        pub fn rename_files(self, files: Vec<(&str, &str)>) -> FeatureRequest<RenameFilesParams> {
+            let files = files
+                .into_iter()
+                .map(|(old_name, new_name)| FileRename {
+                    old_uri: self.uri(old_name).to_string(),
+                    new_uri: self.uri(new_name).to_string(),
+                })
+                .collect();
+            self.request(RenameFilesParams { files })
+        }
+
+        pub fn selection_range(self) -> FeatureRequest<SelectionRangeParams> {
+            let params = SelectionRangeParams {
+                text_document: self.identifier(),
+                positions: vec![Position::new(self.line, self.character)],
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            };
+            self.request(params)
+        }
+
+        pub fn code_action(self) -> FeatureRequest<CodeActionParams> {
+            let position = Position::new(self.line, self.character);
+            let params = CodeActionParams {
+                text_document: self.identifier(),
+                range: Range::new(position, position),
+                context: CodeActionContext::default(),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+                partial_result_params: PartialResultParams::default(),
+            };
+            self.request(params)
+        }
+
+        pub fn signature_help(self) -> FeatureRequest<SignatureHelpParams> {
+            let params = SignatureHelpParams {
+                context: None,
+                text_document_position_params: TextDocumentPositionParams::new(
+                    self.identifier(),
+                    Position::new(self.line, self.character),
+                ),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            };
+            self.request(params)
+        }
+
+        pub fn inlay_hint(self) -> FeatureRequest<InlayHintParams> {
+            let params = InlayHintParams {
+                text_document: self.identifier(),
+                range: Range::new(Position::new(0, 0), Position::new(u32::MAX, u32::MAX)),
+                work_done_progress_params: WorkDoneProgressParams::default(),
+            };
+            self.request(params)
+        }
+
         pub fn highlight(self) -> FeatureRequest<DocumentHighlightParams> {
             let params = DocumentHighlightParams {
                 text_document_position_params: TextDocumentPositionParams::new(
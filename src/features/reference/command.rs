@@ -0,0 +1,123 @@
+use cancellation::CancellationToken;
+use lsp_types::{Location, ReferenceParams};
+
+use crate::{
+    features::cursor::CursorContext,
+    syntax::{latex, CstNode},
+    LineIndexExt,
+};
+
+pub fn find_command_references(
+    context: &CursorContext<ReferenceParams>,
+    cancellation_token: &CancellationToken,
+    references: &mut Vec<Location>,
+) -> Option<()> {
+    cancellation_token.result().ok()?;
+
+    let name_text = context
+        .cursor
+        .as_latex()
+        .filter(|token| token.kind() == latex::GENERIC_COMMAND_NAME)?
+        .text();
+
+    let mut is_user_defined = false;
+    let mut locations = Vec::new();
+    for document in &context.request.subset.documents {
+        if let Some(data) = document.data.as_latex() {
+            cancellation_token.result().ok()?;
+            for token in data
+                .root
+                .descendants_with_tokens()
+                .filter_map(|element| element.into_token())
+                .filter(|token| token.kind() == latex::GENERIC_COMMAND_NAME)
+                .filter(|token| token.text() == name_text)
+            {
+                let is_definition = is_definition(token);
+                is_user_defined |= is_definition;
+                if !is_definition || context.request.params.context.include_declaration {
+                    locations.push(Location::new(
+                        document.uri.as_ref().clone().into(),
+                        document.line_index.line_col_lsp_range(token.text_range()),
+                    ));
+                }
+            }
+        }
+    }
+
+    // Built-in commands like `\ref` are handled by the other providers
+    if is_user_defined {
+        references.append(&mut locations);
+    }
+
+    Some(())
+}
+
+fn is_definition(token: &latex::SyntaxToken) -> bool {
+    token.parent().kind() == latex::CURLY_GROUP_COMMAND
+        && token
+            .parent()
+            .parent()
+            .and_then(latex::CommandDefinition::cast)
+            .is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Range;
+
+    use crate::{features::testing::FeatureTester, RangeExt};
+
+    use super::*;
+
+    #[test]
+    fn test_definition() {
+        let tester = FeatureTester::builder()
+            .files(vec![
+                ("foo.tex", r#"\newcommand{\foo}{bar}\foo"#),
+                ("bar.tex", r#"\foo\input{foo.tex}"#),
+            ])
+            .main("foo.tex")
+            .line(0)
+            .character(13)
+            .build();
+        let uri1 = tester.uri("foo.tex");
+        let uri2 = tester.uri("bar.tex");
+        let mut actual_references = Vec::new();
+
+        let request = tester.reference();
+        let context = CursorContext::new(request);
+        find_command_references(&context, CancellationToken::none(), &mut actual_references);
+
+        let expected_references = vec![
+            Location::new(
+                uri1.as_ref().clone().into(),
+                Range::new_simple(0, 22, 0, 26),
+            ),
+            Location::new(uri2.as_ref().clone().into(), Range::new_simple(0, 0, 0, 4)),
+        ];
+        assert_eq!(actual_references, expected_references);
+    }
+
+    #[test]
+    fn test_definition_include_declaration() {
+        let tester = FeatureTester::builder()
+            .files(vec![("foo.tex", r#"\newcommand{\foo}{bar}\foo"#)])
+            .main("foo.tex")
+            .line(0)
+            .character(24)
+            .include_declaration(true)
+            .build();
+        let uri = tester.uri("foo.tex");
+        let mut actual_references = Vec::new();
+
+        let request = tester.reference();
+        let context = CursorContext::new(request);
+        find_command_references(&context, CancellationToken::none(), &mut actual_references);
+
+        let expected_references = vec![
+            Location::new(uri.as_ref().clone().into(), Range::new_simple(0, 12, 0, 16)),
+            Location::new(uri.as_ref().clone().into(), Range::new_simple(0, 22, 0, 26)),
+        ];
+        assert_eq!(actual_references, expected_references);
+    }
+}
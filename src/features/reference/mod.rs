@@ -1,3 +1,4 @@
+mod command;
 mod entry;
 mod label;
 mod string;
@@ -6,7 +7,8 @@ use cancellation::CancellationToken;
 use lsp_types::{Location, ReferenceParams};
 
 use self::{
-    entry::find_entry_references, label::find_label_references, string::find_string_references,
+    command::find_command_references, entry::find_entry_references, label::find_label_references,
+    string::find_string_references,
 };
 
 use super::{cursor::CursorContext, FeatureRequest};
@@ -21,5 +23,6 @@ pub fn find_all_references(
     find_label_references(&context, cancellation_token, &mut references);
     find_entry_references(&context, cancellation_token, &mut references);
     find_string_references(&context, cancellation_token, &mut references);
+    find_command_references(&context, cancellation_token, &mut references);
     Some(references)
 }
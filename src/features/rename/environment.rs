@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+
+use cancellation::CancellationToken;
+use lsp_types::{Range, RenameParams, TextEdit, WorkspaceEdit};
+
+use crate::{
+    features::cursor::{CursorContext, HasPosition},
+    LineIndexExt,
+};
+
+pub fn prepare_environment_rename<P: HasPosition>(
+    context: &CursorContext<P>,
+    _cancellation_token: &CancellationToken,
+) -> Option<Range> {
+    context.find_environment_names()?;
+    let (_, range) = context.find_environment_name()?;
+
+    Some(
+        context
+            .request
+            .main_document()
+            .line_index
+            .line_col_lsp_range(range),
+    )
+}
+
+pub fn rename_environment(
+    context: &CursorContext<RenameParams>,
+    cancellation_token: &CancellationToken,
+) -> Option<WorkspaceEdit> {
+    prepare_environment_rename(context, cancellation_token)?;
+    let main_document = context.request.main_document();
+    let edits = context
+        .find_environment_names()?
+        .iter()
+        .map(|(_, range)| {
+            TextEdit::new(
+                main_document.line_index.line_col_lsp_range(*range),
+                context.request.params.new_name.clone(),
+            )
+        })
+        .collect();
+
+    let mut changes = HashMap::new();
+    changes.insert(main_document.uri.as_ref().clone().into(), edits);
+    Some(WorkspaceEdit::new(changes))
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use crate::{features::testing::FeatureTester, RangeExt};
+
+    use super::*;
+
+    #[test]
+    fn test_environment() {
+        let tester = FeatureTester::builder()
+            .files(vec![(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \begin{foo}
+                        \begin{bar}
+                        \end{bar}
+                        \end{foo}
+                    "#
+                ),
+            )])
+            .main("main.tex")
+            .line(3)
+            .character(6)
+            .new_name("baz")
+            .build();
+
+        let uri = tester.uri("main.tex");
+        let request = tester.rename();
+
+        let context = CursorContext::new(request);
+        let actual_edit = rename_environment(&context, CancellationToken::none()).unwrap();
+
+        let mut expected_changes = HashMap::new();
+        expected_changes.insert(
+            uri.as_ref().clone().into(),
+            vec![
+                TextEdit::new(Range::new_simple(0, 7, 0, 10), "baz".into()),
+                TextEdit::new(Range::new_simple(3, 5, 3, 8), "baz".into()),
+            ],
+        );
+        let expected_edit = WorkspaceEdit::new(expected_changes);
+
+        assert_eq!(actual_edit, expected_edit);
+    }
+
+    #[test]
+    fn test_no_environment() {
+        let tester = FeatureTester::builder()
+            .files(vec![("main.tex", r#"\foo{bar}"#)])
+            .main("main.tex")
+            .line(0)
+            .character(6)
+            .new_name("baz")
+            .build();
+
+        let request = tester.rename();
+
+        let context = CursorContext::new(request);
+        let actual_edit = rename_environment(&context, CancellationToken::none());
+
+        assert_eq!(actual_edit, None);
+    }
+}
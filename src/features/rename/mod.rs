@@ -1,5 +1,6 @@
 mod command;
 mod entry;
+mod environment;
 mod label;
 
 use cancellation::CancellationToken;
@@ -8,6 +9,7 @@ use lsp_types::{Range, RenameParams, TextDocumentPositionParams, WorkspaceEdit};
 use self::{
     command::{prepare_command_rename, rename_command},
     entry::{prepare_entry_rename, rename_entry},
+    environment::{prepare_environment_rename, rename_environment},
     label::{prepare_label_rename, rename_label},
 };
 
@@ -20,6 +22,7 @@ pub fn prepare_rename_all(
     let context = CursorContext::new(request);
     prepare_entry_rename(&context, cancellation_token)
         .or_else(|| prepare_label_rename(&context, cancellation_token))
+        .or_else(|| prepare_environment_rename(&context, cancellation_token))
         .or_else(|| prepare_command_rename(&context, cancellation_token))
 }
 
@@ -30,5 +33,6 @@ pub fn rename_all(
     let context = CursorContext::new(request);
     rename_entry(&context, cancellation_token)
         .or_else(|| rename_label(&context, cancellation_token))
+        .or_else(|| rename_environment(&context, cancellation_token))
         .or_else(|| rename_command(&context, cancellation_token))
 }
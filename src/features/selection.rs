@@ -0,0 +1,273 @@
+use cancellation::CancellationToken;
+use cstree::{TextRange, TextSize};
+use lsp_types::{Range, SelectionRange, SelectionRangeParams};
+
+use crate::{
+    syntax::{bibtex, latex, CstNode},
+    DocumentData, LineIndexExt,
+};
+
+use super::FeatureRequest;
+
+pub fn find_selection_ranges(
+    request: FeatureRequest<SelectionRangeParams>,
+    cancellation_token: &CancellationToken,
+) -> Vec<SelectionRange> {
+    let main_document = request.main_document();
+    let mut selection_ranges = Vec::new();
+    for position in &request.params.positions {
+        let offset = main_document.line_index.offset_lsp(*position);
+        let ranges = if cancellation_token.is_canceled() {
+            Vec::new()
+        } else {
+            match &main_document.data {
+                DocumentData::Latex(data) => find_latex_ranges(&data.root, offset),
+                DocumentData::Bibtex(data) => find_bibtex_ranges(&data.root, offset),
+                DocumentData::BuildLog(_) => Vec::new(),
+            }
+        };
+
+        let mut selection_range = None;
+        for range in ranges.into_iter().rev() {
+            selection_range = Some(SelectionRange {
+                range: main_document.line_index.line_col_lsp_range(range),
+                parent: selection_range.map(Box::new),
+            });
+        }
+
+        selection_ranges.push(selection_range.unwrap_or(SelectionRange {
+            range: Range::new(*position, *position),
+            parent: None,
+        }));
+    }
+
+    selection_ranges
+}
+
+fn find_latex_ranges(root: &latex::SyntaxNode, offset: TextSize) -> Vec<TextRange> {
+    let mut ranges = Vec::new();
+    let token = match pick_token(
+        root.token_at_offset(offset).left_biased(),
+        root.token_at_offset(offset).right_biased(),
+        |token| matches!(token.kind(), latex::WHITESPACE | latex::LINE_BREAK),
+    ) {
+        Some(token) => token,
+        None => return ranges,
+    };
+
+    if !matches!(
+        token.kind(),
+        latex::WHITESPACE | latex::LINE_BREAK | latex::MISSING
+    ) {
+        push_range(&mut ranges, token.text_range());
+    }
+
+    for node in token.parent().ancestors() {
+        if let Some(environment) = latex::Environment::cast(node) {
+            if let (Some(begin), Some(end)) = (environment.begin(), environment.end()) {
+                let body = TextRange::new(
+                    begin.syntax().text_range().end(),
+                    end.syntax().text_range().start(),
+                );
+
+                if body.contains_inclusive(offset) {
+                    push_range(&mut ranges, body);
+                }
+            }
+        }
+
+        push_range(
+            &mut ranges,
+            trim_trailing_trivia(node.text_range(), node.last_token(), |token| {
+                matches!(
+                    token.kind(),
+                    latex::WHITESPACE | latex::LINE_BREAK | latex::COMMENT
+                )
+            }),
+        );
+    }
+
+    ranges
+}
+
+fn find_bibtex_ranges(root: &bibtex::SyntaxNode, offset: TextSize) -> Vec<TextRange> {
+    let mut ranges = Vec::new();
+    let token = match pick_token(
+        root.token_at_offset(offset).left_biased(),
+        root.token_at_offset(offset).right_biased(),
+        |token| token.kind() == bibtex::WHITESPACE,
+    ) {
+        Some(token) => token,
+        None => return ranges,
+    };
+
+    if !matches!(token.kind(), bibtex::WHITESPACE | bibtex::MISSING) {
+        push_range(&mut ranges, token.text_range());
+    }
+
+    for node in token.parent().ancestors() {
+        push_range(
+            &mut ranges,
+            trim_trailing_trivia(node.text_range(), node.last_token(), |token| {
+                token.kind() == bibtex::WHITESPACE
+            }),
+        );
+    }
+
+    ranges
+}
+
+/// Prefers the token to the right of the cursor unless it is whitespace.
+fn pick_token<T>(
+    left: Option<T>,
+    right: Option<T>,
+    is_whitespace: impl Fn(&T) -> bool,
+) -> Option<T> {
+    match (left, right) {
+        (Some(left), Some(right)) if is_whitespace(&right) && !is_whitespace(&left) => Some(left),
+        (left, right) => right.or(left),
+    }
+}
+
+fn trim_trailing_trivia<L: cstree::Language>(
+    full_range: TextRange,
+    last_token: Option<&cstree::ResolvedToken<L>>,
+    is_trivia: impl Fn(&cstree::ResolvedToken<L>) -> bool,
+) -> TextRange {
+    let start = full_range.start();
+    let mut token = last_token;
+    while let Some(current) = token.filter(|current| current.text_range().start() >= start) {
+        if !is_trivia(current) {
+            return TextRange::new(start, current.text_range().end());
+        }
+        token = current.prev_token();
+    }
+
+    TextRange::new(start, start)
+}
+
+/// Adds a range if it strictly extends the previous one.
+fn push_range(ranges: &mut Vec<TextRange>, range: TextRange) {
+    match ranges.last() {
+        Some(last) if range == *last || !range.contains_range(*last) => {}
+        _ => ranges.push(range),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+    use lsp_types::Position;
+
+    use crate::{features::testing::FeatureTester, RangeExt};
+
+    use super::*;
+
+    fn flatten(selection_range: &SelectionRange) -> Vec<Range> {
+        let mut ranges = vec![selection_range.range];
+        let mut parent = &selection_range.parent;
+        while let Some(current) = parent {
+            ranges.push(current.range);
+            parent = &current.parent;
+        }
+        ranges
+    }
+
+    #[test]
+    fn test_empty_latex_document() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", "")])
+            .main("main.tex")
+            .line(0)
+            .character(0)
+            .build()
+            .selection_range();
+
+        let actual_ranges = find_selection_ranges(request, CancellationToken::none());
+
+        assert_eq!(
+            actual_ranges,
+            vec![SelectionRange {
+                range: Range::new_simple(0, 0, 0, 0),
+                parent: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_latex() {
+        let request = FeatureTester::builder()
+            .files(vec![(
+                "main.tex",
+                indoc!(
+                    r#"
+                        \section{Foo}
+                        \begin{itemize}
+                        \textbf{bar baz}
+                        \end{itemize}
+                    "#
+                ),
+            )])
+            .main("main.tex")
+            .line(2)
+            .character(9)
+            .build()
+            .selection_range();
+
+        let actual_ranges = find_selection_ranges(request, CancellationToken::none());
+
+        assert_eq!(actual_ranges.len(), 1);
+        assert_eq!(
+            flatten(&actual_ranges[0]),
+            vec![
+                Range::new_simple(2, 8, 2, 11),
+                Range::new_simple(2, 8, 2, 15),
+                Range::new_simple(2, 7, 2, 16),
+                Range::new_simple(2, 0, 2, 16),
+                Range::new_simple(2, 0, 3, 0),
+                Range::new_simple(1, 0, 3, 13),
+                Range::new_simple(0, 0, 3, 13),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bibtex() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.bib", "@article{foo, title = {Bar}}")])
+            .main("main.bib")
+            .line(0)
+            .character(24)
+            .build()
+            .selection_range();
+
+        let actual_ranges = find_selection_ranges(request, CancellationToken::none());
+
+        assert_eq!(actual_ranges.len(), 1);
+        assert_eq!(
+            flatten(&actual_ranges[0]),
+            vec![
+                Range::new_simple(0, 23, 0, 26),
+                Range::new_simple(0, 22, 0, 27),
+                Range::new_simple(0, 14, 0, 27),
+                Range::new_simple(0, 0, 0, 28),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_multiple_positions() {
+        let mut request = FeatureTester::builder()
+            .files(vec![("main.bib", "@article{foo, title = {Bar}}")])
+            .main("main.bib")
+            .build()
+            .selection_range();
+
+        request.params.positions = vec![Position::new(0, 10), Position::new(0, 24)];
+        let actual_ranges = find_selection_ranges(request, CancellationToken::none());
+
+        assert_eq!(actual_ranges.len(), 2);
+        assert_eq!(actual_ranges[0].range, Range::new_simple(0, 9, 0, 12));
+        assert_eq!(actual_ranges[1].range, Range::new_simple(0, 23, 0, 26));
+    }
+}
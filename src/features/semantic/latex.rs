@@ -0,0 +1,246 @@
+use cstree::TextRange;
+use lsp_types::{SemanticTokenModifier, SemanticTokenType};
+
+use crate::{
+    syntax::{latex, CstNode},
+    Document, LANGUAGE_DATA,
+};
+
+use super::{legend::*, Context};
+
+pub fn find_latex_semantic_tokens(
+    context: &mut Context,
+    document: &Document,
+    range: TextRange,
+) -> Option<()> {
+    let data = document.data.as_latex()?;
+    for token in data
+        .root
+        .descendants_with_tokens()
+        .filter_map(|element| element.into_token())
+        .filter(|token| token.text_range().intersect(range).is_some())
+    {
+        context.cancellation_token.result().ok()?;
+        visit_token(context, token);
+    }
+
+    Some(())
+}
+
+fn visit_token(context: &mut Context, token: &latex::SyntaxToken) -> Option<()> {
+    let mut modifiers = ModifierSet::default();
+    let is_math = is_inside_math(token);
+    if is_math {
+        modifiers |= MATH_MODE;
+    }
+
+    let token_type = match token.kind() {
+        latex::COMMENT | latex::BEGIN_BLOCK_COMMENT_NAME | latex::END_BLOCK_COMMENT_NAME => {
+            SemanticTokenType::COMMENT
+        }
+        latex::VERBATIM => {
+            let token_type = if token.parent().kind() == latex::BLOCK_COMMENT {
+                SemanticTokenType::COMMENT
+            } else {
+                VERBATIM
+            };
+
+            context.insert_lines(
+                token.text_range().start(),
+                token.text(),
+                token_type,
+                modifiers,
+            );
+            return Some(());
+        }
+        latex::DOLLAR | latex::BEGIN_EQUATION_NAME | latex::END_EQUATION_NAME => MATH,
+        kind if kind.is_command_name() => {
+            let parent = token.parent();
+            if parent.kind() == latex::CURLY_GROUP_COMMAND
+                && parent.parent()?.kind() == latex::COMMAND_DEFINITION
+            {
+                modifiers |= SemanticTokenModifier::DEFINITION;
+            }
+
+            COMMAND
+        }
+        latex::WORD => match find_key_owner(token) {
+            Some(latex::BEGIN) | Some(latex::END) => ENVIRONMENT_NAME,
+            Some(latex::ENVIRONMENT_DEFINITION) => {
+                modifiers |= SemanticTokenModifier::DEFINITION;
+                ENVIRONMENT_NAME
+            }
+            Some(latex::LABEL_DEFINITION) => {
+                modifiers |= SemanticTokenModifier::DEFINITION;
+                LABEL
+            }
+            Some(latex::LABEL_REFERENCE) | Some(latex::LABEL_REFERENCE_RANGE) => LABEL,
+            Some(latex::CITATION) => ENTRY_KEY,
+            _ if is_math => MATH,
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    context.insert(token.text_range(), token_type, modifiers);
+    Some(())
+}
+
+fn find_key_owner(token: &latex::SyntaxToken) -> Option<latex::SyntaxKind> {
+    let key = token.parent();
+    if key.kind() != latex::KEY {
+        return None;
+    }
+
+    let group = key.parent()?;
+    if !matches!(
+        group.kind(),
+        latex::CURLY_GROUP_WORD | latex::CURLY_GROUP_WORD_LIST
+    ) {
+        return None;
+    }
+
+    Some(group.parent()?.kind())
+}
+
+fn is_inside_math(token: &latex::SyntaxToken) -> bool {
+    let mut child_kind = None;
+    let mut node = Some(token.parent());
+    while let Some(parent) = node {
+        match parent.kind() {
+            latex::FORMULA | latex::EQUATION => return true,
+            latex::ENVIRONMENT
+                if !matches!(child_kind, Some(latex::BEGIN) | Some(latex::END))
+                    && is_math_environment(parent) =>
+            {
+                return true;
+            }
+            _ => {}
+        }
+
+        child_kind = Some(parent.kind());
+        node = parent.parent();
+    }
+
+    false
+}
+
+fn is_math_environment(node: &latex::SyntaxNode) -> bool {
+    latex::Environment::cast(node)
+        .and_then(|environment| environment.begin())
+        .and_then(|begin| begin.name())
+        .and_then(|name| name.key())
+        .map_or(false, |name| {
+            let name = name.to_string();
+            LANGUAGE_DATA
+                .math_environments
+                .iter()
+                .any(|environment| environment == &name)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, sync::Arc};
+
+    use cancellation::CancellationToken;
+    use cstree::TextLen;
+
+    use crate::{features::semantic::find_semantic_tokens, DocumentLanguage, ServerContext, Uri};
+
+    use super::*;
+
+    fn find_tokens(text: &str) -> Vec<(u32, u32, u32, SemanticTokenType, u32)> {
+        let context = Arc::new(ServerContext::new(env::temp_dir()));
+        let uri = Arc::new(Uri::from_file_path(env::temp_dir().join("main.tex")).unwrap());
+        let document = Document::parse(context, uri, text.into(), DocumentLanguage::Latex);
+        let range = TextRange::new(0.into(), document.text.text_len());
+
+        let (mut line, mut character) = (0, 0);
+        find_semantic_tokens(&document, range, CancellationToken::none())
+            .into_iter()
+            .map(|token| {
+                if token.delta_line > 0 {
+                    line += token.delta_line;
+                    character = token.delta_start;
+                } else {
+                    character += token.delta_start;
+                }
+
+                (
+                    line,
+                    character,
+                    token.length,
+                    SUPPORTED_TYPES[token.token_type as usize].clone(),
+                    token.token_modifiers_bitset,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_command() {
+        let definition = (ModifierSet::default() | SemanticTokenModifier::DEFINITION).0;
+        assert_eq!(
+            find_tokens("\\newcommand{\\foo}{bar}\n\\foo"),
+            vec![
+                (0, 0, 11, COMMAND, 0),
+                (0, 12, 4, COMMAND, definition),
+                (1, 0, 4, COMMAND, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inline_math() {
+        let math_mode = (ModifierSet::default() | MATH_MODE).0;
+        assert_eq!(
+            find_tokens("foo $x \\alpha$"),
+            vec![
+                (0, 4, 1, MATH, math_mode),
+                (0, 5, 1, MATH, math_mode),
+                (0, 7, 6, COMMAND, math_mode),
+                (0, 13, 1, MATH, math_mode),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_math_environment() {
+        let math_mode = (ModifierSet::default() | MATH_MODE).0;
+        assert_eq!(
+            find_tokens("\\begin{equation}x\\end{equation}"),
+            vec![
+                (0, 0, 6, COMMAND, 0),
+                (0, 7, 8, ENVIRONMENT_NAME, 0),
+                (0, 16, 1, MATH, math_mode),
+                (0, 17, 4, COMMAND, 0),
+                (0, 22, 8, ENVIRONMENT_NAME, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_keys() {
+        let definition = (ModifierSet::default() | SemanticTokenModifier::DEFINITION).0;
+        assert_eq!(
+            find_tokens("\\label{foo}\\ref{foo}\\cite{bar}"),
+            vec![
+                (0, 0, 6, COMMAND, 0),
+                (0, 7, 3, LABEL, definition),
+                (0, 11, 4, COMMAND, 0),
+                (0, 16, 3, LABEL, 0),
+                (0, 20, 5, COMMAND, 0),
+                (0, 26, 3, ENTRY_KEY, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comment() {
+        assert_eq!(
+            find_tokens("foo % bar"),
+            vec![(0, 4, 5, SemanticTokenType::COMMENT, 0)]
+        );
+    }
+}
@@ -38,6 +38,11 @@ define_semantic_token_types![
     (HASH, "hash"),
     (QUOTE, "quote"),
     (GENERIC_COMMAND, "genericCommand"),
+    (COMMAND, "command"),
+    (ENVIRONMENT_NAME, "environment"),
+    (LABEL, "label"),
+    (MATH, "math"),
+    (VERBATIM, "verbatim"),
 ];
 
 macro_rules! define_semantic_token_modifiers {
@@ -54,13 +59,13 @@ macro_rules! define_semantic_token_modifiers {
     };
 }
 
-define_semantic_token_modifiers![(ITALIC, "italic"),];
+define_semantic_token_modifiers![(ITALIC, "italic"), (MATH_MODE, "mathMode"),];
 
 pub fn type_index(ty: SemanticTokenType) -> u32 {
     SUPPORTED_TYPES.iter().position(|t| *t == ty).unwrap() as u32
 }
 
-#[derive(Default)]
+#[derive(Debug, Default, Clone, Copy)]
 pub struct ModifierSet(pub u32);
 
 impl ops::BitOrAssign<SemanticTokenModifier> for ModifierSet {
@@ -1,21 +1,333 @@
 mod bibtex;
+mod latex;
 pub mod legend;
 
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
 use cancellation::CancellationToken;
-use lsp_types::{SemanticTokens, SemanticTokensRangeParams};
+use cstree::{TextLen, TextRange, TextSize};
+use dashmap::DashMap;
+use lsp_types::{
+    SemanticToken, SemanticTokenType, SemanticTokens, SemanticTokensDelta,
+    SemanticTokensDeltaParams, SemanticTokensEdit, SemanticTokensFullDeltaResult,
+    SemanticTokensParams, SemanticTokensRangeParams,
+};
+
+use crate::{Document, LineIndex, LineIndexExt, Uri};
 
-use self::bibtex::find_bibtex_semantic_tokens_range;
+use self::{bibtex::find_bibtex_semantic_tokens, latex::find_latex_semantic_tokens, legend::*};
 
 use super::FeatureRequest;
 
+pub struct Context<'a> {
+    line_index: &'a LineIndex,
+    previous_line: u32,
+    previous_character: u32,
+    tokens: &'a mut Vec<SemanticToken>,
+    cancellation_token: &'a CancellationToken,
+}
+
+impl<'a> Context<'a> {
+    pub fn insert(
+        &mut self,
+        range: TextRange,
+        token_type: SemanticTokenType,
+        token_modifiers_bitset: ModifierSet,
+    ) {
+        let range = self.line_index.line_col_lsp_range(range);
+
+        let mut delta_line = range.start.line;
+        let mut delta_start = range.start.character;
+        if !self.tokens.is_empty() {
+            delta_line -= self.previous_line;
+            if delta_line == 0 {
+                delta_start -= self.previous_character;
+            }
+        }
+        self.tokens.push(SemanticToken {
+            delta_line,
+            delta_start,
+            length: range.end.character - range.start.character,
+            token_type: type_index(token_type),
+            token_modifiers_bitset: token_modifiers_bitset.0,
+        });
+
+        self.previous_line = range.start.line;
+        self.previous_character = range.start.character;
+    }
+
+    pub fn insert_lines(
+        &mut self,
+        start: TextSize,
+        text: &str,
+        token_type: SemanticTokenType,
+        token_modifiers_bitset: ModifierSet,
+    ) {
+        let mut offset = start;
+        for line in text.split_inclusive('\n') {
+            let content = line.trim_end_matches(|c| c == '\r' || c == '\n');
+            if !content.trim().is_empty() {
+                let range = TextRange::at(offset, content.text_len());
+                self.insert(range, token_type.clone(), token_modifiers_bitset);
+            }
+
+            offset += line.text_len();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SemanticTokensCache {
+    next_result_id: AtomicU64,
+    tokens_by_uri: DashMap<Arc<Uri>, (String, Vec<SemanticToken>)>,
+}
+
+impl SemanticTokensCache {
+    fn insert(&self, uri: Arc<Uri>, tokens: Vec<SemanticToken>) -> String {
+        let result_id = self
+            .next_result_id
+            .fetch_add(1, Ordering::SeqCst)
+            .to_string();
+
+        self.tokens_by_uri.insert(uri, (result_id.clone(), tokens));
+        result_id
+    }
+
+    pub fn remove(&self, uri: &Uri) {
+        self.tokens_by_uri.remove(uri);
+    }
+}
+
 pub fn find_semantic_tokens_range(
     request: FeatureRequest<SemanticTokensRangeParams>,
     cancellation_token: &CancellationToken,
 ) -> Option<SemanticTokens> {
-    let mut data = Vec::new();
-    find_bibtex_semantic_tokens_range(&request, &mut data, cancellation_token);
+    let document = request.main_document();
+    let range = document.line_index.offset_lsp_range(request.params.range);
+    let data = find_semantic_tokens(document, range, cancellation_token);
     Some(SemanticTokens {
         result_id: None,
         data,
     })
 }
+
+pub fn find_semantic_tokens_full(
+    request: FeatureRequest<SemanticTokensParams>,
+    cache: &SemanticTokensCache,
+    cancellation_token: &CancellationToken,
+) -> Option<SemanticTokens> {
+    let document = request.main_document();
+    let range = TextRange::new(0.into(), document.text.text_len());
+    let data = find_semantic_tokens(document, range, cancellation_token);
+    cancellation_token.result().ok()?;
+
+    let result_id = cache.insert(Arc::clone(&document.uri), data.clone());
+    Some(SemanticTokens {
+        result_id: Some(result_id),
+        data,
+    })
+}
+
+pub fn find_semantic_tokens_full_delta(
+    request: FeatureRequest<SemanticTokensDeltaParams>,
+    cache: &SemanticTokensCache,
+    cancellation_token: &CancellationToken,
+) -> Option<SemanticTokensFullDeltaResult> {
+    let document = request.main_document();
+    let range = TextRange::new(0.into(), document.text.text_len());
+    let data = find_semantic_tokens(document, range, cancellation_token);
+    cancellation_token.result().ok()?;
+
+    let previous = cache
+        .tokens_by_uri
+        .get(&document.uri)
+        .filter(|entry| entry.0 == request.params.previous_result_id)
+        .map(|entry| entry.1.clone());
+
+    let result_id = cache.insert(Arc::clone(&document.uri), data.clone());
+    match previous {
+        Some(previous) => Some(SemanticTokensFullDeltaResult::TokensDelta(
+            SemanticTokensDelta {
+                result_id: Some(result_id),
+                edits: diff_semantic_tokens(&previous, &data),
+            },
+        )),
+        None => Some(SemanticTokensFullDeltaResult::Tokens(SemanticTokens {
+            result_id: Some(result_id),
+            data,
+        })),
+    }
+}
+
+fn find_semantic_tokens(
+    document: &Document,
+    range: TextRange,
+    cancellation_token: &CancellationToken,
+) -> Vec<SemanticToken> {
+    let mut data = Vec::new();
+    let mut context = Context {
+        line_index: &document.line_index,
+        previous_line: 0,
+        previous_character: 0,
+        tokens: &mut data,
+        cancellation_token,
+    };
+
+    find_bibtex_semantic_tokens(&mut context, document, range)
+        .or_else(|| find_latex_semantic_tokens(&mut context, document, range));
+    data
+}
+
+fn diff_semantic_tokens(old: &[SemanticToken], new: &[SemanticToken]) -> Vec<SemanticTokensEdit> {
+    let prefix = old
+        .iter()
+        .zip(new)
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(old, new)| old == new)
+        .count();
+
+    let deleted = &old[prefix..old.len() - suffix];
+    let inserted = &new[prefix..new.len() - suffix];
+    if deleted.is_empty() && inserted.is_empty() {
+        return Vec::new();
+    }
+
+    vec![SemanticTokensEdit {
+        start: (prefix * 5) as u32,
+        delete_count: (deleted.len() * 5) as u32,
+        data: Some(inserted.to_vec()),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::{DocumentLanguage, ServerContext};
+
+    use super::*;
+
+    fn token(delta_line: u32, delta_start: u32, length: u32) -> SemanticToken {
+        SemanticToken {
+            delta_line,
+            delta_start,
+            length,
+            token_type: 0,
+            token_modifiers_bitset: 0,
+        }
+    }
+
+    fn apply_edits(old: &[SemanticToken], edits: Vec<SemanticTokensEdit>) -> Vec<SemanticToken> {
+        let mut tokens = old.to_vec();
+        for edit in edits.into_iter().rev() {
+            let start = edit.start as usize / 5;
+            let end = start + edit.delete_count as usize / 5;
+            tokens.splice(start..end, edit.data.unwrap_or_default());
+        }
+        tokens
+    }
+
+    fn find_tokens(text: &str) -> Vec<SemanticToken> {
+        let context = Arc::new(ServerContext::new(env::temp_dir()));
+        let uri = Arc::new(Uri::from_file_path(env::temp_dir().join("main.tex")).unwrap());
+        let document = Document::parse(context, uri, text.into(), DocumentLanguage::Latex);
+        let range = TextRange::new(0.into(), document.text.text_len());
+        find_semantic_tokens(&document, range, CancellationToken::none())
+    }
+
+    #[test]
+    fn test_diff_unchanged() {
+        let tokens = vec![token(0, 0, 1), token(1, 0, 2)];
+        assert_eq!(diff_semantic_tokens(&tokens, &tokens), Vec::new());
+    }
+
+    #[test]
+    fn test_diff_insertion() {
+        let old = vec![token(0, 0, 1), token(1, 0, 2), token(1, 0, 3)];
+        let new = vec![
+            token(0, 0, 1),
+            token(1, 0, 2),
+            token(1, 0, 4),
+            token(1, 0, 3),
+        ];
+        let edits = diff_semantic_tokens(&old, &new);
+
+        assert_eq!(
+            edits,
+            vec![SemanticTokensEdit {
+                start: 10,
+                delete_count: 0,
+                data: Some(vec![token(1, 0, 4)]),
+            }]
+        );
+        assert_eq!(apply_edits(&old, edits), new);
+    }
+
+    #[test]
+    fn test_diff_deletion() {
+        let old = vec![
+            token(0, 0, 1),
+            token(1, 0, 2),
+            token(1, 0, 4),
+            token(1, 0, 3),
+        ];
+        let new = vec![token(0, 0, 1), token(1, 0, 2), token(1, 0, 3)];
+        let edits = diff_semantic_tokens(&old, &new);
+
+        assert_eq!(
+            edits,
+            vec![SemanticTokensEdit {
+                start: 10,
+                delete_count: 5,
+                data: Some(Vec::new()),
+            }]
+        );
+        assert_eq!(apply_edits(&old, edits), new);
+    }
+
+    #[test]
+    fn test_diff_replacement() {
+        let old = vec![token(0, 0, 1), token(1, 0, 2), token(1, 0, 3)];
+        let new = vec![
+            token(0, 0, 1),
+            token(0, 2, 5),
+            token(1, 0, 6),
+            token(1, 0, 3),
+        ];
+        let edits = diff_semantic_tokens(&old, &new);
+        assert_eq!(apply_edits(&old, edits), new);
+    }
+
+    #[test]
+    fn test_diff_all_changed() {
+        let old = vec![token(0, 0, 1), token(1, 0, 2)];
+        let new = vec![token(0, 1, 1)];
+        let edits = diff_semantic_tokens(&old, &new);
+        assert_eq!(apply_edits(&old, edits), new);
+    }
+
+    #[test]
+    fn test_diff_document_insertion() {
+        let old = find_tokens("\\foo\n\\label{bar}\n\\ref{bar}");
+        let new = find_tokens("\\foo\n\\label{bar} \\baz{$x$}\n\\ref{bar}");
+        let edits = diff_semantic_tokens(&old, &new);
+        assert_eq!(apply_edits(&old, edits), new);
+    }
+
+    #[test]
+    fn test_diff_document_deletion() {
+        let old = find_tokens("\\foo\n\\label{bar} \\baz{$x$}\n\\ref{bar}");
+        let new = find_tokens("\\foo\n\\label{bar}\n\\ref{bar}");
+        let edits = diff_semantic_tokens(&old, &new);
+        assert_eq!(apply_edits(&old, edits), new);
+    }
+}
@@ -0,0 +1,226 @@
+use cancellation::CancellationToken;
+use lsp_types::{
+    ParameterInformation, ParameterLabel, SignatureHelp, SignatureHelpParams, SignatureInformation,
+};
+
+use crate::{
+    component_db::COMPONENT_DATABASE,
+    syntax::{
+        latex::{self, HasBrack, HasCurly},
+        CstNode,
+    },
+};
+
+use super::{cursor::CursorContext, FeatureRequest};
+
+pub fn find_signature_help(
+    request: FeatureRequest<SignatureHelpParams>,
+    cancellation_token: &CancellationToken,
+) -> Option<SignatureHelp> {
+    let context = CursorContext::new(request);
+    let main_document = context.request.main_document();
+    let data = main_document.data.as_latex()?;
+
+    let token = data.root.token_at_offset(context.offset).left_biased()?;
+    let group = token
+        .ancestors()
+        .filter_map(latex::CurlyGroup::cast)
+        .find(|group| {
+            group.syntax().text_range().start() < context.offset
+                && (group.small_range().contains(context.offset) || group.right_curly().is_none())
+        })?;
+
+    let command = latex::GenericCommand::cast(group.syntax().parent()?)?;
+    let active_parameter = command
+        .syntax()
+        .children()
+        .filter_map(latex::CurlyGroup::cast)
+        .position(|g| g.syntax().text_range() == group.syntax().text_range())?;
+
+    let command_name = command.name()?.text();
+    let mut signatures = Vec::new();
+    for component in COMPONENT_DATABASE.linked_components(&context.request.subset) {
+        cancellation_token.result().ok()?;
+        for component_command in component
+            .commands
+            .iter()
+            .filter(|command| command.name == command_name[1..])
+            .filter(|command| !command.parameters.is_empty())
+        {
+            let parameters = component_command.parameters.iter().map(|param| {
+                if param.0.is_empty() {
+                    "...".to_string()
+                } else {
+                    param
+                        .0
+                        .iter()
+                        .map(|arg| arg.name.as_str())
+                        .collect::<Vec<_>>()
+                        .join("|")
+                }
+            });
+
+            signatures.push(create_signature(command_name, parameters));
+        }
+    }
+
+    for document in &context.request.subset.documents {
+        cancellation_token.result().ok()?;
+        if let Some(data) = document.data.as_latex() {
+            for definition in data
+                .root
+                .descendants()
+                .filter_map(latex::CommandDefinition::cast)
+                .filter(|definition| {
+                    definition
+                        .name()
+                        .and_then(|name| name.command())
+                        .map_or(false, |name| name.text() == command_name)
+                })
+            {
+                let count = definition
+                    .syntax()
+                    .children()
+                    .find_map(latex::BrackGroupWord::cast)
+                    .and_then(|group| group.content_text())
+                    .and_then(|text| text.trim().parse::<usize>().ok())
+                    .unwrap_or(0);
+
+                if count > 0 {
+                    let parameters = (1..=count).map(|i| format!("#{}", i));
+                    signatures.push(create_signature(command_name, parameters));
+                }
+            }
+        }
+    }
+
+    if signatures.is_empty() {
+        return None;
+    }
+
+    Some(SignatureHelp {
+        signatures,
+        active_signature: Some(0),
+        active_parameter: Some(active_parameter as u32),
+    })
+}
+
+fn create_signature(
+    command_name: &str,
+    parameters: impl Iterator<Item = String>,
+) -> SignatureInformation {
+    let mut label = command_name.to_string();
+    let mut infos = Vec::new();
+    for parameter in parameters {
+        label.push('{');
+        let start = label.encode_utf16().count() as u32;
+        label.push_str(&parameter);
+        let end = label.encode_utf16().count() as u32;
+        label.push('}');
+        infos.push(ParameterInformation {
+            label: ParameterLabel::LabelOffsets([start, end]),
+            documentation: None,
+        });
+    }
+
+    SignatureInformation {
+        label,
+        documentation: None,
+        parameters: Some(infos),
+        active_parameter: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::features::testing::FeatureTester;
+
+    use super::*;
+
+    #[test]
+    fn test_empty_latex_document() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.tex", "")])
+            .main("main.tex")
+            .line(0)
+            .character(0)
+            .build()
+            .signature_help();
+
+        let actual_help = find_signature_help(request, CancellationToken::none());
+
+        assert_eq!(actual_help, None);
+    }
+
+    #[test]
+    fn test_empty_bibtex_document() {
+        let request = FeatureTester::builder()
+            .files(vec![("main.bib", "")])
+            .main("main.bib")
+            .line(0)
+            .character(0)
+            .build()
+            .signature_help();
+
+        let actual_help = find_signature_help(request, CancellationToken::none());
+
+        assert_eq!(actual_help, None);
+    }
+
+    #[test]
+    fn test_user_command() {
+        let request = FeatureTester::builder()
+            .files(vec![(
+                "main.tex",
+                "\\newcommand{\\foo}[2]{#1 #2}\n\\foo{a}{b}",
+            )])
+            .main("main.tex")
+            .line(1)
+            .character(8)
+            .build()
+            .signature_help();
+
+        let actual_help = find_signature_help(request, CancellationToken::none()).unwrap();
+
+        assert_eq!(
+            actual_help,
+            SignatureHelp {
+                signatures: vec![SignatureInformation {
+                    label: "\\foo{#1}{#2}".to_string(),
+                    documentation: None,
+                    parameters: Some(vec![
+                        ParameterInformation {
+                            label: ParameterLabel::LabelOffsets([5, 7]),
+                            documentation: None,
+                        },
+                        ParameterInformation {
+                            label: ParameterLabel::LabelOffsets([9, 11]),
+                            documentation: None,
+                        },
+                    ]),
+                    active_parameter: None,
+                }],
+                active_signature: Some(0),
+                active_parameter: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn test_outside_of_group() {
+        let request = FeatureTester::builder()
+            .files(vec![(
+                "main.tex",
+                "\\newcommand{\\foo}[2]{#1 #2}\n\\foo{a}{b}",
+            )])
+            .main("main.tex")
+            .line(1)
+            .character(2)
+            .build()
+            .signature_help();
+
+        let actual_help = find_signature_help(request, CancellationToken::none());
+
+        assert_eq!(actual_help, None);
+    }
+}
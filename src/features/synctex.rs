@@ -0,0 +1,380 @@
+use std::{
+    cmp::Ordering,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use cancellation::CancellationToken;
+use flate2::read::GzDecoder;
+use lsp_types::{
+    Location, Position, Range, TextDocumentIdentifier, TextDocumentPositionParams, Url,
+};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+use super::{forward_search::find_pdf_path, FeatureRequest};
+
+const SCALED_POINTS_PER_BIG_POINT: f64 = 65781.76;
+
+const ORIGIN_OFFSET: f64 = 72.0;
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ForwardSearchLocation {
+    pub uri: Url,
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InverseSearchParams {
+    pub text_document: TextDocumentIdentifier,
+    pub page: u32,
+    pub x: f64,
+    pub y: f64,
+}
+
+pub fn find_forward_search_location(
+    request: FeatureRequest<TextDocumentPositionParams>,
+    cancellation_token: &CancellationToken,
+) -> Option<ForwardSearchLocation> {
+    let pdf_path = find_pdf_path(&request.subset)?;
+    let synctex = SyncTex::load(&pdf_path)?;
+    cancellation_token.result().ok()?;
+
+    let tex_path = request.main_document().uri.to_file_path().ok()?;
+    let record = synctex.forward(&tex_path, request.params.position.line + 1)?;
+    Some(ForwardSearchLocation {
+        uri: Url::from_file_path(&pdf_path).ok()?,
+        page: record.page,
+        x: record.x,
+        y: record.y - record.height,
+        width: record.width,
+        height: record.height + record.depth,
+    })
+}
+
+pub fn find_inverse_search_location(
+    request: FeatureRequest<InverseSearchParams>,
+    cancellation_token: &CancellationToken,
+) -> Option<Location> {
+    let pdf_path = find_pdf_path(&request.subset)?;
+    let synctex = SyncTex::load(&pdf_path)?;
+    cancellation_token.result().ok()?;
+
+    let params = &request.params;
+    let (path, line) = synctex.inverse(params.page, params.x, params.y)?;
+    let position = Position::new(line.saturating_sub(1), 0);
+    Some(Location::new(
+        Url::from_file_path(path).ok()?,
+        Range::new(position, position),
+    ))
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct SyncTexRecord {
+    input: u32,
+    line: u32,
+    page: u32,
+    is_box: bool,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    depth: f64,
+}
+
+impl SyncTexRecord {
+    fn contains(&self, x: f64, y: f64) -> bool {
+        let width = self.width.abs();
+        self.is_box
+            && self.x <= x
+            && x <= self.x + width
+            && self.y - self.height <= y
+            && y <= self.y + self.depth
+    }
+
+    fn area(&self) -> f64 {
+        self.width.abs() * (self.height + self.depth)
+    }
+
+    fn distance(&self, x: f64, y: f64) -> f64 {
+        let dx = x - self.x;
+        let dy = y - self.y;
+        dx * dx + dy * dy
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+struct SyncTex {
+    inputs: FxHashMap<u32, PathBuf>,
+    records: Vec<SyncTexRecord>,
+}
+
+impl SyncTex {
+    fn load(pdf_path: &Path) -> Option<Self> {
+        let directory = pdf_path.parent()?;
+        let gz_path = pdf_path.with_extension("synctex.gz");
+        let text = if gz_path.exists() {
+            let mut text = String::new();
+            GzDecoder::new(fs::File::open(gz_path).ok()?)
+                .read_to_string(&mut text)
+                .ok()?;
+            text
+        } else {
+            fs::read_to_string(pdf_path.with_extension("synctex")).ok()?
+        };
+
+        Some(Self::parse(&text, directory))
+    }
+
+    fn parse(text: &str, directory: &Path) -> Self {
+        let mut synctex = Self::default();
+        let mut unit = 1.0;
+        let mut magnification = 1.0;
+        let mut x_offset = 0.0;
+        let mut y_offset = 0.0;
+        let mut page = 0;
+        for line in text.lines() {
+            if let Some(rest) = line.strip_prefix("Input:") {
+                if let Some((tag, path)) = rest.split_once(':') {
+                    if let Ok(tag) = tag.parse() {
+                        synctex.inputs.insert(tag, directory.join(path));
+                    }
+                }
+            } else if let Some(rest) = line.strip_prefix("Unit:") {
+                unit = rest.trim().parse().unwrap_or(1.0);
+            } else if let Some(rest) = line.strip_prefix("Magnification:") {
+                magnification = rest.trim().parse::<f64>().unwrap_or(1000.0) / 1000.0;
+            } else if let Some(rest) = line.strip_prefix("X Offset:") {
+                x_offset = rest.trim().parse().unwrap_or(0.0);
+            } else if let Some(rest) = line.strip_prefix("Y Offset:") {
+                y_offset = rest.trim().parse().unwrap_or(0.0);
+            } else if line.starts_with("Postamble:") {
+                break;
+            } else if let Some(rest) = line.strip_prefix('{') {
+                page = rest.trim().parse().unwrap_or(page);
+            } else if let Some(kind) = line.chars().next() {
+                let is_box = match kind {
+                    '[' | '(' | 'h' | 'v' => true,
+                    'x' | 'k' | 'g' | '$' => false,
+                    _ => continue,
+                };
+
+                let convert = |value: f64, offset: f64| {
+                    (value * unit + offset) * magnification / SCALED_POINTS_PER_BIG_POINT
+                        + ORIGIN_OFFSET
+                };
+
+                let scale = |value: f64| value * unit * magnification / SCALED_POINTS_PER_BIG_POINT;
+
+                if let Some(record) = parse_record(&line[1..]) {
+                    synctex.records.push(SyncTexRecord {
+                        input: record.input,
+                        line: record.line,
+                        page,
+                        is_box,
+                        x: convert(record.x, x_offset),
+                        y: convert(record.y, y_offset),
+                        width: scale(record.width),
+                        height: scale(record.height),
+                        depth: scale(record.depth),
+                    });
+                }
+            }
+        }
+
+        synctex
+    }
+
+    fn forward(&self, path: &Path, line: u32) -> Option<SyncTexRecord> {
+        let input = self
+            .inputs
+            .iter()
+            .find(|(_, input_path)| same_file(input_path, path))
+            .map(|(tag, _)| *tag)?;
+
+        let records = || {
+            self.records
+                .iter()
+                .filter(move |record| record.input == input && record.line > 0)
+        };
+
+        let best_line = records()
+            .map(|record| record.line)
+            .min_by_key(|record_line| {
+                let distance = (*record_line as i64 - line as i64).abs();
+                (distance, *record_line < line)
+            })?;
+
+        records()
+            .filter(|record| record.line == best_line)
+            .min_by_key(|record| (!record.is_box, record.page))
+            .copied()
+    }
+
+    fn inverse(&self, page: u32, x: f64, y: f64) -> Option<(PathBuf, u32)> {
+        let records = || {
+            self.records
+                .iter()
+                .filter(move |record| record.page == page && record.line > 0)
+        };
+
+        let record = records()
+            .filter(|record| record.contains(x, y))
+            .min_by(|a, b| compare(a.area(), b.area()))
+            .or_else(|| records().min_by(|a, b| compare(a.distance(x, y), b.distance(x, y))))?;
+
+        let path = self.inputs.get(&record.input)?;
+        Some((path.clone(), record.line))
+    }
+}
+
+struct RawRecord {
+    input: u32,
+    line: u32,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    depth: f64,
+}
+
+fn parse_record(text: &str) -> Option<RawRecord> {
+    let mut parts = text.split(':');
+    let mut link = parts.next()?.split(',');
+    let input = link.next()?.parse().ok()?;
+    let line = link.next()?.parse().ok()?;
+
+    let mut point = parts.next()?.split(',');
+    let x = point.next()?.parse().ok()?;
+    let y = point.next()?.parse().ok()?;
+
+    let mut size = parts
+        .next()
+        .unwrap_or_default()
+        .split(',')
+        .map(|value| value.parse().unwrap_or(0.0));
+
+    Some(RawRecord {
+        input,
+        line,
+        x,
+        y,
+        width: size.next().unwrap_or(0.0),
+        height: size.next().unwrap_or(0.0),
+        depth: size.next().unwrap_or(0.0),
+    })
+}
+
+fn compare(lhs: f64, rhs: f64) -> Ordering {
+    lhs.partial_cmp(&rhs).unwrap_or(Ordering::Equal)
+}
+
+fn same_file(lhs: &Path, rhs: &Path) -> bool {
+    if lhs == rhs {
+        return true;
+    }
+
+    match (fs::canonicalize(lhs), fs::canonicalize(rhs)) {
+        (Ok(lhs), Ok(rhs)) => lhs == rhs,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    const SYNCTEX: &str = indoc!(
+        r#"
+            SyncTeX Version:1
+            Input:1:./main.tex
+            Input:2:./chapter.tex
+            Output:pdf
+            Magnification:1000
+            Unit:1
+            X Offset:0
+            Y Offset:0
+            Content:
+            !100
+            {1
+            [1,3:0,0:26214400,45875200,0
+            (1,3:0,655360:13107200,655360,0
+            g1,3:655360,655360
+            )
+            (2,5:0,6553600:26214400,655360,131072
+            )
+            ]
+            }1
+            {2
+            [1,8:0,0:26214400,45875200,0
+            (1,8:0,1310720:13107200,655360,0
+            )
+            ]
+            }2
+            Postamble:
+            Count:10
+        "#
+    );
+
+    fn synctex() -> SyncTex {
+        SyncTex::parse(SYNCTEX, Path::new("/build"))
+    }
+
+    #[test]
+    fn test_parse_inputs() {
+        let synctex = synctex();
+        assert_eq!(synctex.inputs.len(), 2);
+        assert_eq!(synctex.inputs[&1], Path::new("/build/main.tex"));
+        assert_eq!(synctex.inputs[&2], Path::new("/build/chapter.tex"));
+        assert_eq!(synctex.records.len(), 6);
+    }
+
+    #[test]
+    fn test_forward_exact_line() {
+        let record = synctex()
+            .forward(Path::new("/build/chapter.tex"), 5)
+            .unwrap();
+
+        assert_eq!(record.page, 1);
+        assert!(record.is_box);
+        assert!((record.x - 72.0).abs() < 1e-6);
+        assert!((record.y - (72.0 + 6553600.0 / SCALED_POINTS_PER_BIG_POINT)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_forward_nearest_line() {
+        let record = synctex().forward(Path::new("/build/main.tex"), 7).unwrap();
+        assert_eq!(record.page, 2);
+        assert_eq!(record.line, 8);
+    }
+
+    #[test]
+    fn test_forward_unknown_file() {
+        assert_eq!(synctex().forward(Path::new("/build/foo.tex"), 1), None);
+    }
+
+    #[test]
+    fn test_inverse() {
+        let x = 72.0 + 1.0;
+        let y = 72.0 + 6553600.0 / SCALED_POINTS_PER_BIG_POINT;
+        let (path, line) = synctex().inverse(1, x, y).unwrap();
+        assert_eq!(path, Path::new("/build/chapter.tex"));
+        assert_eq!(line, 5);
+    }
+
+    #[test]
+    fn test_inverse_outside_of_boxes() {
+        let (path, line) = synctex().inverse(2, 1000.0, 1000.0).unwrap();
+        assert_eq!(path, Path::new("/build/main.tex"));
+        assert_eq!(line, 8);
+    }
+}
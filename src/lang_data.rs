@@ -19,6 +19,26 @@ pub struct BibtexEntryTypeDoc {
     pub name: String,
     pub category: BibtexEntryTypeCategory,
     pub documentation: Option<String>,
+
+    #[serde(default)]
+    pub required_fields: Vec<Vec<String>>,
+
+    #[serde(default)]
+    pub optional_fields: Vec<String>,
+}
+
+impl BibtexEntryTypeDoc {
+    pub fn has_field_data(&self) -> bool {
+        !self.required_fields.is_empty()
+    }
+
+    pub fn allows_field(&self, name: &str) -> bool {
+        self.required_fields
+            .iter()
+            .flatten()
+            .chain(&self.optional_fields)
+            .any(|field| field == name)
+    }
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
@@ -1,6 +1,7 @@
 mod capabilities;
 #[cfg(feature = "citation")]
 pub mod citation;
+pub mod cli;
 mod client;
 pub mod component_db;
 mod config;
@@ -24,7 +25,7 @@ mod workspace;
 
 pub use self::{
     capabilities::ClientCapabilitiesExt,
-    context::ServerContext,
+    context::{ServerContext, WorkspaceFolderConfig},
     label::*,
     lang_data::*,
     language::DocumentLanguage,
@@ -1,10 +1,16 @@
-use std::{env, fs::OpenOptions, io, path::PathBuf};
+use std::{
+    env,
+    fs::{self, OpenOptions},
+    io,
+    path::PathBuf,
+    process,
+};
 
 use anyhow::Result;
 use log::LevelFilter;
 use lsp_server::Connection;
 use structopt::StructOpt;
-use texlab::Server;
+use texlab::{cli::OutputFormat, BibtexFormattingOptions, Options, Server};
 
 /// An implementation of the Language Server Protocol for LaTeX
 #[derive(Debug, StructOpt)]
@@ -20,12 +26,107 @@ struct Opts {
     /// Write the logging output to FILE
     #[structopt(long, name = "FILE", parse(from_os_str))]
     log_file: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Debug, StructOpt)]
+enum Command {
+    /// Report the diagnostics of all documents in a directory and exit with a non-zero code on errors
+    Check {
+        /// The root directory of the project
+        #[structopt(parse(from_os_str), default_value = ".")]
+        root: PathBuf,
+
+        /// The output format (text, json or sarif)
+        #[structopt(long, default_value = "text")]
+        format: OutputFormat,
+
+        /// Lint LaTeX documents using ChkTeX
+        #[structopt(long)]
+        chktex: bool,
+
+        /// Read the server settings from a JSON file (same format as the `texlab` settings)
+        #[structopt(long, parse(from_os_str))]
+        config: Option<PathBuf>,
+    },
+
+    /// Format BibTeX files in place using the internal formatter
+    Format {
+        /// Report a diff and exit with a non-zero code instead of rewriting the files
+        #[structopt(long)]
+        check: bool,
+
+        /// The maximum line length
+        #[structopt(long, default_value = "80")]
+        line_length: usize,
+
+        /// The number of spaces used for indentation
+        #[structopt(long, default_value = "2")]
+        tab_size: u32,
+
+        /// Read the formatting style from a JSON file (same format as `texlab.bibtexFormatting`)
+        #[structopt(long, parse(from_os_str))]
+        config: Option<PathBuf>,
+
+        /// The files to format
+        #[structopt(parse(from_os_str), required = true)]
+        files: Vec<PathBuf>,
+    },
 }
 
 fn main() -> Result<()> {
-    let opts = Opts::from_args();
+    let mut opts = Opts::from_args();
+    let command = opts.command.take();
     setup_logger(opts);
 
+    match command {
+        Some(Command::Check {
+            root,
+            format,
+            chktex,
+            config,
+        }) => {
+            let options = match config {
+                Some(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+                None => Options::default(),
+            };
+
+            let has_errors = texlab::cli::check(&root, format, chktex, options, &mut io::stdout())?;
+            process::exit(i32::from(has_errors));
+        }
+        Some(Command::Format {
+            check,
+            line_length,
+            tab_size,
+            config,
+            files,
+        }) => {
+            let options = match config {
+                Some(path) => serde_json::from_str(&fs::read_to_string(path)?)?,
+                None => BibtexFormattingOptions::default(),
+            };
+
+            let line_length = if line_length == 0 {
+                usize::MAX
+            } else {
+                line_length
+            };
+
+            let has_changes = texlab::cli::format(
+                &files,
+                check,
+                line_length,
+                tab_size,
+                &options,
+                &mut io::stdout(),
+            )?;
+            process::exit(i32::from(check && has_changes));
+        }
+        None => {}
+    };
+
     let (connection, threads) = Connection::stdio();
     Server::with_connection(connection, env::current_dir()?, true)?.run()?;
     threads.join()?;
@@ -1,5 +1,6 @@
 use std::path::PathBuf;
 
+use rustc_hash::FxHashSet;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
@@ -17,6 +18,9 @@ pub struct Options {
 
     pub formatter_line_length: Option<i32>,
 
+    #[serde(default)]
+    pub bibtex_formatting: BibtexFormattingOptions,
+
     pub diagnostics_delay: Option<u64>,
 
     #[serde(default)]
@@ -25,10 +29,16 @@ pub struct Options {
     #[serde(default)]
     pub chktex: ChktexOptions,
 
+    #[serde(default)]
+    pub diagnostics: DiagnosticsOptions,
+
     #[serde(default)]
     pub latexindent: LatexindentOptions,
 
     pub forward_search: Option<ForwardSearchOptions>,
+
+    #[serde(default)]
+    pub experimental: ExperimentalOptions,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
@@ -44,6 +54,75 @@ impl Default for BibtexFormatter {
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BibtexFormattingOptions {
+    pub sort_entries: Option<BibtexSortOrder>,
+
+    pub align_equals: bool,
+
+    pub canonical_field_order: bool,
+
+    pub entry_type_case: BibtexCase,
+
+    pub field_name_case: BibtexCase,
+
+    pub trailing_comma: bool,
+
+    pub value_delimiters: BibtexValueDelimiters,
+
+    pub drop_empty_fields: bool,
+}
+
+impl Default for BibtexFormattingOptions {
+    fn default() -> Self {
+        Self {
+            sort_entries: None,
+            align_equals: false,
+            canonical_field_order: false,
+            entry_type_case: BibtexCase::Lowercase,
+            field_name_case: BibtexCase::Preserve,
+            trailing_comma: true,
+            value_delimiters: BibtexValueDelimiters::Preserve,
+            drop_empty_fields: false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BibtexSortOrder {
+    Key,
+    Year,
+    Author,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BibtexCase {
+    Preserve,
+    Lowercase,
+    Uppercase,
+}
+
+impl BibtexCase {
+    pub fn apply(self, text: &str) -> String {
+        match self {
+            Self::Preserve => text.to_string(),
+            Self::Lowercase => text.to_lowercase(),
+            Self::Uppercase => text.to_uppercase(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BibtexValueDelimiters {
+    Preserve,
+    Braces,
+    Quotes,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum LatexFormatter {
@@ -124,8 +203,29 @@ pub struct ChktexOptions {
     pub on_edit: bool,
 }
 
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsOptions {
+    #[serde(default)]
+    pub uncited_entries: bool,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
 pub struct ForwardSearchOptions {
     pub executable: Option<String>,
     pub args: Option<Vec<String>>,
 }
+
+#[derive(Debug, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ExperimentalOptions {
+    pub citation_commands: FxHashSet<String>,
+
+    pub label_definition_commands: FxHashSet<String>,
+
+    pub label_reference_commands: FxHashSet<String>,
+
+    pub include_commands: FxHashSet<String>,
+
+    pub verbatim_environments: FxHashSet<String>,
+}
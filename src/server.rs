@@ -1,6 +1,6 @@
 use std::{
     path::PathBuf,
-    sync::{Arc, Mutex},
+    sync::{Arc, Mutex, MutexGuard},
 };
 
 use anyhow::Result;
@@ -10,41 +10,53 @@ use log::{error, info, warn};
 use lsp_server::{Connection, ErrorCode, Message, RequestId};
 use lsp_types::{
     notification::{
-        Cancel, DidChangeConfiguration, DidChangeTextDocument, DidOpenTextDocument,
-        DidSaveTextDocument, PublishDiagnostics,
+        Cancel, DidChangeConfiguration, DidChangeTextDocument, DidChangeWatchedFiles,
+        DidChangeWorkspaceFolders, DidOpenTextDocument, DidSaveTextDocument, PublishDiagnostics,
+        WorkDoneProgressCancel,
     },
     request::{
-        DocumentLinkRequest, FoldingRangeRequest, Formatting, GotoDefinition, PrepareRenameRequest,
-        References, Rename, SemanticTokensRangeRequest,
+        CodeActionRequest, CodeLensRequest, ColorPresentationRequest, DocumentColor,
+        DocumentLinkRequest, FoldingRangeRequest, Formatting, GotoDefinition, LinkedEditingRange,
+        PrepareRenameRequest, References, Rename, SelectionRangeRequest,
+        SemanticTokensFullDeltaRequest, SemanticTokensFullRequest, SemanticTokensRangeRequest,
+        SignatureHelpRequest,
     },
     *,
 };
-use notification::DidCloseTextDocument;
+use notification::{DidCloseTextDocument, Notification};
 use notify::RecursiveMode;
 use request::{
     Completion, DocumentHighlightRequest, DocumentSymbolRequest, HoverRequest,
-    ResolveCompletionItem, WorkspaceSymbol,
+    ResolveCompletionItem, WillRenameFiles, WorkspaceSymbol,
 };
 use serde::Serialize;
 use threadpool::ThreadPool;
 
 use crate::{
-    client::send_notification,
+    client::{send_notification, send_request},
     component_db::COMPONENT_DATABASE,
     config::{pull_config, push_config, register_config_capability},
     create_workspace_full,
-    diagnostics::{DiagnosticsDebouncer, DiagnosticsManager, DiagnosticsMessage},
+    diagnostics::{
+        DiagnosticPullSupport, DiagnosticsDebouncer, DiagnosticsManager, DiagnosticsMessage,
+        DocumentDiagnosticParams, DocumentDiagnosticReport, WorkspaceDiagnosticParams,
+        WorkspaceDiagnosticReport,
+    },
     dispatch::{NotificationDispatcher, RequestDispatcher},
     distro::Distribution,
     features::{
-        find_all_references, find_document_highlights, find_document_links, find_document_symbols,
-        find_foldings, find_hover, find_workspace_symbols, format_source_code, goto_definition,
-        prepare_rename_all, rename_all, BuildEngine, BuildParams, BuildResult, BuildStatus,
-        FeatureRequest, ForwardSearchResult, ForwardSearchStatus,
+        find_all_references, find_code_actions, find_code_lenses, find_color_presentations,
+        find_document_colors, find_document_highlights, find_document_links, find_document_symbols,
+        find_file_rename_edits, find_foldings, find_forward_search_location, find_hover,
+        find_inlay_hints, find_inverse_search_location, find_linked_editing_ranges,
+        find_selection_ranges, find_signature_help, find_workspace_symbols, format_source_code,
+        goto_definition, prepare_rename_all, rename_all, BuildEngine, BuildParams, BuildResult,
+        BuildStatus, FeatureRequest, ForwardSearchLocation, ForwardSearchResult,
+        ForwardSearchStatus, InlayHint, InlayHintParams, InverseSearchParams,
     },
     req_queue::{self, IncomingData, ReqQueue},
-    ClientCapabilitiesExt, Document, DocumentLanguage, LineIndexExt, ServerContext, Uri, Workspace,
-    WorkspaceSource,
+    ClientCapabilitiesExt, Document, DocumentLanguage, LineIndex, LineIndexExt, ServerContext, Uri,
+    Workspace, WorkspaceFolderConfig, WorkspaceSource,
 };
 
 pub struct Server {
@@ -52,11 +64,14 @@ pub struct Server {
     context: Arc<ServerContext>,
     req_queue: Arc<Mutex<ReqQueue>>,
     workspace: Arc<dyn Workspace>,
+    diag_manager: Arc<Mutex<DiagnosticsManager>>,
     static_debouncer: DiagnosticsDebouncer,
     chktex_debouncer: DiagnosticsDebouncer,
     pool: ThreadPool,
     load_resolver: bool,
     build_engine: Arc<BuildEngine>,
+    #[cfg(feature = "semantic")]
+    semantic_tokens_cache: Arc<crate::features::SemanticTokensCache>,
 }
 
 impl Server {
@@ -66,26 +81,37 @@ impl Server {
         load_resolver: bool,
     ) -> Result<Self> {
         let context = Arc::new(ServerContext::new(current_dir));
-        let req_queue = Arc::default();
+        let req_queue: Arc<Mutex<ReqQueue>> = Arc::default();
         let workspace = Arc::new(create_workspace_full(Arc::clone(&context))?);
         let diag_manager = Arc::new(Mutex::new(DiagnosticsManager::default()));
 
-        let static_debouncer =
-            create_static_debouncer(Arc::clone(&diag_manager), &connection, Arc::clone(&context));
+        let static_debouncer = create_static_debouncer(
+            Arc::clone(&diag_manager),
+            Arc::clone(&req_queue),
+            &connection,
+            Arc::clone(&context),
+        );
 
-        let chktex_debouncer =
-            create_chktex_debouncer(diag_manager, &connection, Arc::clone(&context));
+        let chktex_debouncer = create_chktex_debouncer(
+            Arc::clone(&diag_manager),
+            Arc::clone(&req_queue),
+            &connection,
+            Arc::clone(&context),
+        );
 
         Ok(Self {
             connection,
             context,
             req_queue,
             workspace,
+            diag_manager,
             static_debouncer,
             chktex_debouncer,
             pool: threadpool::Builder::new().build(),
             load_resolver,
             build_engine: Arc::default(),
+            #[cfg(feature = "semantic")]
+            semantic_tokens_cache: Arc::default(),
         })
     }
 
@@ -107,6 +133,9 @@ impl Server {
                 work_done_progress_options: WorkDoneProgressOptions::default(),
             }),
             folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+            selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+            color_provider: Some(ColorProviderCapability::Simple(true)),
+            linked_editing_range_provider: Some(LinkedEditingRangeServerCapabilities::Simple(true)),
             definition_provider: Some(OneOf::Left(true)),
             references_provider: Some(OneOf::Left(true)),
             hover_provider: Some(HoverProviderCapability::Simple(true)),
@@ -125,16 +154,48 @@ impl Server {
             }),
             document_symbol_provider: Some(OneOf::Left(true)),
             workspace_symbol_provider: Some(OneOf::Left(true)),
+            workspace: Some(WorkspaceServerCapabilities {
+                workspace_folders: Some(WorkspaceFoldersServerCapabilities {
+                    supported: Some(true),
+                    change_notifications: Some(OneOf::Left(true)),
+                }),
+                file_operations: Some(WorkspaceFileOperationsServerCapabilities {
+                    will_rename: Some(FileOperationRegistrationOptions {
+                        filters: vec![FileOperationFilter {
+                            scheme: Some("file".into()),
+                            pattern: FileOperationPattern {
+                                glob: "**/*".into(),
+                                matches: None,
+                                options: None,
+                            },
+                        }],
+                    }),
+                    ..WorkspaceFileOperationsServerCapabilities::default()
+                }),
+            }),
             rename_provider: Some(OneOf::Right(RenameOptions {
                 prepare_provider: Some(true),
                 work_done_progress_options: WorkDoneProgressOptions::default(),
             })),
             document_highlight_provider: Some(OneOf::Left(true)),
             document_formatting_provider: Some(OneOf::Left(true)),
+            signature_help_provider: Some(SignatureHelpOptions {
+                trigger_characters: Some(vec!["{".into()]),
+                retrigger_characters: None,
+                work_done_progress_options: WorkDoneProgressOptions::default(),
+            }),
+            code_action_provider: Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+                work_done_progress_options: WorkDoneProgressOptions::default(),
+                resolve_provider: None,
+            })),
+            code_lens_provider: Some(CodeLensOptions {
+                resolve_provider: Some(false),
+            }),
             #[cfg(feature = "semantic")]
             semantic_tokens_provider: Some(
                 SemanticTokensServerCapabilities::SemanticTokensOptions(SemanticTokensOptions {
-                    full: None,
+                    full: Some(SemanticTokensFullOptions::Delta { delta: Some(true) }),
                     range: Some(true),
                     legend: SemanticTokensLegend {
                         token_types: crate::features::legend::SUPPORTED_TYPES.to_vec(),
@@ -149,10 +210,19 @@ impl Server {
 
     fn initialize(&mut self) -> Result<()> {
         let (id, params) = self.connection.initialize_start()?;
+        *self.context.diagnostic_pull_support.lock().unwrap() =
+            DiagnosticPullSupport::from_capabilities(&params["capabilities"]);
+
         let params: InitializeParams = serde_json::from_value(params)?;
 
         *self.context.client_capabilities.lock().unwrap() = params.capabilities;
         *self.context.client_info.lock().unwrap() = params.client_info;
+        *self.context.workspace_folders.write().unwrap() = params
+            .workspace_folders
+            .into_iter()
+            .flatten()
+            .filter_map(|folder| WorkspaceFolderConfig::new(Arc::new(folder.uri.into())))
+            .collect();
 
         let result = InitializeResult {
             capabilities: self.capabilities(),
@@ -161,13 +231,26 @@ impl Server {
                 version: Some(env!("CARGO_PKG_VERSION").to_owned()),
             }),
         };
-        self.connection
-            .initialize_finish(id, serde_json::to_value(result)?)?;
+
+        // lsp-types does not know about the inlay hint and pull diagnostic capabilities yet
+        let mut result = serde_json::to_value(result)?;
+        result["capabilities"]["inlayHintProvider"] = true.into();
+        result["capabilities"]["diagnosticProvider"] = serde_json::json!({
+            "identifier": "texlab",
+            "interFileDependencies": true,
+            "workspaceDiagnostics": true,
+        });
+        self.connection.initialize_finish(id, result)?;
 
         let cx = Arc::clone(&self.context);
         if self.load_resolver {
             self.pool.execute(move || {
-                let distro = Distribution::detect();
+                let cached = Distribution::cached_resolver();
+                if let Some(cached) = &cached {
+                    *cx.resolver.lock().unwrap() = cached.resolver.clone();
+                }
+
+                let distro = Distribution::detect(cached);
                 info!("Detected distribution: {}", distro.kind);
                 *cx.resolver.lock().unwrap() = distro.resolver;
             });
@@ -181,6 +264,7 @@ impl Server {
         let workspace = Arc::clone(&self.workspace);
         self.pool.execute(move || {
             register_config_capability(&req_queue, &sender, &context.client_capabilities);
+            register_file_watcher(&req_queue, &sender, &context.client_capabilities);
             pull_and_reparse_all(req_queue, sender, context, workspace);
         });
 
@@ -223,6 +307,11 @@ impl Server {
         Ok(())
     }
 
+    fn cancel_progress(&self, params: WorkDoneProgressCancelParams) -> Result<()> {
+        self.build_engine.cancel_progress(&params.token);
+        Ok(())
+    }
+
     fn did_change_configuration(&self, params: DidChangeConfigurationParams) -> Result<()> {
         let client_capabilities = { self.context.client_capabilities.lock().unwrap().clone() };
         if client_capabilities.has_pull_configuration_support() {
@@ -230,24 +319,99 @@ impl Server {
             let sender = self.connection.sender.clone();
             let context = Arc::clone(&self.context);
             let workspace = Arc::clone(&self.workspace);
+            let build_engine = Arc::clone(&self.build_engine);
             self.pool.execute(move || {
-                pull_and_reparse_all(req_queue, sender, context, workspace);
+                pull_and_reparse_all(
+                    Arc::clone(&req_queue),
+                    sender.clone(),
+                    Arc::clone(&context),
+                    Arc::clone(&workspace),
+                );
+                restart_continuous_builds(&build_engine, context, workspace, &req_queue, &sender);
             });
         } else {
             push_config(&self.context.options, params.settings);
-            if let Some(path) = { self.context.options.read().unwrap().aux_directory.clone() } {
-                let _ = self.workspace.watch(path, RecursiveMode::NonRecursive);
-            }
+            watch_aux_directories(&self.context, self.workspace.as_ref());
 
+            let req_queue = Arc::clone(&self.req_queue);
+            let sender = self.connection.sender.clone();
+            let context = Arc::clone(&self.context);
             let workspace = Arc::clone(&self.workspace);
+            let build_engine = Arc::clone(&self.build_engine);
             self.pool.execute(move || {
                 reparse_all(workspace.as_ref());
+                restart_continuous_builds(&build_engine, context, workspace, &req_queue, &sender);
             });
         }
 
         Ok(())
     }
 
+    fn did_change_workspace_folders(&self, params: DidChangeWorkspaceFoldersParams) -> Result<()> {
+        {
+            let mut folders = self.context.workspace_folders.write().unwrap();
+            for removed in params.event.removed {
+                let uri = Uri::from(removed.uri);
+                folders.retain(|folder| *folder.uri != uri);
+            }
+
+            folders.extend(
+                params
+                    .event
+                    .added
+                    .into_iter()
+                    .filter_map(|folder| WorkspaceFolderConfig::new(Arc::new(folder.uri.into()))),
+            );
+        }
+
+        let req_queue = Arc::clone(&self.req_queue);
+        let sender = self.connection.sender.clone();
+        let context = Arc::clone(&self.context);
+        let workspace = Arc::clone(&self.workspace);
+        let build_engine = Arc::clone(&self.build_engine);
+        self.pool.execute(move || {
+            pull_and_reparse_all(
+                Arc::clone(&req_queue),
+                sender.clone(),
+                Arc::clone(&context),
+                Arc::clone(&workspace),
+            );
+            restart_continuous_builds(&build_engine, context, workspace, &req_queue, &sender);
+        });
+
+        Ok(())
+    }
+
+    fn did_change_watched_files(&self, params: DidChangeWatchedFilesParams) -> Result<()> {
+        for change in params.changes {
+            match change.typ {
+                FileChangeType::CREATED | FileChangeType::CHANGED => {
+                    if let Ok(path) = change.uri.to_file_path() {
+                        if let Err(why) = self.workspace.reload(path) {
+                            warn!("Failed to reload document \"{}\": {}", change.uri, why);
+                        }
+                    }
+                }
+                FileChangeType::DELETED => {
+                    self.workspace.remove(&change.uri.into());
+                }
+                _ => {}
+            }
+        }
+
+        let req_queue = Arc::clone(&self.req_queue);
+        let sender = self.connection.sender.clone();
+        let context = Arc::clone(&self.context);
+        let workspace = Arc::clone(&self.workspace);
+        let diag_manager = Arc::clone(&self.diag_manager);
+        self.pool.execute(move || {
+            let manager = diag_manager.lock().unwrap();
+            report_diagnostics(&req_queue, &sender, &context, workspace.as_ref(), manager);
+        });
+
+        Ok(())
+    }
+
     fn did_open(&self, params: DidOpenTextDocumentParams) -> Result<()> {
         let language_id = &params.text_document.language_id;
         let language = DocumentLanguage::by_language_id(language_id);
@@ -258,7 +422,11 @@ impl Server {
             WorkspaceSource::Client,
         );
 
-        let should_lint = { self.context.options.read().unwrap().chktex.on_open_and_save };
+        let should_lint = self
+            .context
+            .options_for(&document.uri)
+            .chktex
+            .on_open_and_save;
         if let Some(document) = self
             .workspace
             .get(document.uri.as_ref())
@@ -287,12 +455,15 @@ impl Server {
             .unwrap_or(DocumentLanguage::Latex);
 
         let new_document = match &old_document {
-            Some(old_document) => params
-                .content_changes
-                .into_iter()
-                .fold(Arc::clone(&old_document), |old_document, change| {
-                    self.merge_text_changes(&old_document, language, change)
-                }),
+            Some(old_document) => {
+                let new_text = apply_text_changes(old_document, params.content_changes);
+                self.workspace.open(
+                    Arc::clone(&uri),
+                    new_text,
+                    language,
+                    WorkspaceSource::Client,
+                )
+            }
             None => self.workspace.open(
                 Arc::clone(&uri),
                 params.content_changes.pop().unwrap().text,
@@ -313,7 +484,7 @@ impl Server {
             .positions_by_uri
             .insert(Arc::clone(&uri), Position::new(line, 0));
 
-        let should_lint = { self.context.options.read().unwrap().chktex.on_edit };
+        let should_lint = self.context.options_for(&uri).chktex.on_edit;
         if should_lint {
             self.chktex_debouncer
                 .sender
@@ -326,36 +497,10 @@ impl Server {
         Ok(())
     }
 
-    fn merge_text_changes(
-        &self,
-        old_document: &Document,
-        new_language: DocumentLanguage,
-        change: TextDocumentContentChangeEvent,
-    ) -> Arc<Document> {
-        let new_text = match change.range {
-            Some(range) => {
-                let range = old_document.line_index.offset_lsp_range(range);
-                let mut new_text = String::new();
-                new_text.push_str(&old_document.text[..range.start().into()]);
-                new_text.push_str(&change.text);
-                new_text.push_str(&old_document.text[range.end().into()..]);
-                new_text
-            }
-            None => change.text,
-        };
-
-        self.workspace.open(
-            Arc::clone(&old_document.uri),
-            new_text,
-            new_language,
-            WorkspaceSource::Client,
-        )
-    }
-
     fn did_save(&self, params: DidSaveTextDocumentParams) -> Result<()> {
         let uri = params.text_document.uri.into();
 
-        let should_build = { self.context.options.read().unwrap().build.on_save };
+        let should_build = self.context.options_for(&uri).build.on_save;
         if let Some(request) =
             self.workspace
                 .get(&uri)
@@ -384,7 +529,7 @@ impl Server {
             });
         }
 
-        let should_lint = { self.context.options.read().unwrap().chktex.on_open_and_save };
+        let should_lint = self.context.options_for(&uri).chktex.on_open_and_save;
         if let Some(document) = self.workspace.get(&uri).filter(|_| should_lint) {
             self.chktex_debouncer
                 .sender
@@ -398,6 +543,8 @@ impl Server {
 
     fn did_close(&self, params: DidCloseTextDocumentParams) -> Result<()> {
         let uri = params.text_document.uri.into();
+        #[cfg(feature = "semantic")]
+        self.semantic_tokens_cache.remove(&uri);
         self.workspace.close(&uri);
         Ok(())
     }
@@ -500,6 +647,74 @@ impl Server {
         Ok(())
     }
 
+    fn will_rename_files(
+        &self,
+        id: RequestId,
+        params: RenameFilesParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let sender = self.connection.sender.clone();
+        let context = Arc::clone(&self.context);
+        let workspace = Arc::clone(&self.workspace);
+        let token = Arc::clone(token);
+        self.pool.execute(move || {
+            let result = find_file_rename_edits(&context, workspace.as_ref(), &params, &token);
+            if token.is_canceled() {
+                sender.send(cancel_response(id).into()).unwrap();
+            } else {
+                sender
+                    .send(lsp_server::Response::new_ok(id, result).into())
+                    .unwrap();
+            }
+        });
+        Ok(())
+    }
+
+    fn document_diagnostic(&self, id: RequestId, params: DocumentDiagnosticParams) -> Result<()> {
+        let sender = self.connection.sender.clone();
+        let diag_manager = Arc::clone(&self.diag_manager);
+        self.pool.execute(move || {
+            let uri: Arc<Uri> = Arc::new(params.text_document.uri.into());
+            let mut manager = diag_manager.lock().unwrap();
+            let is_pulled = manager.is_pulled(&uri);
+            let report = manager.pull(Arc::clone(&uri), params.previous_result_id.as_deref());
+            drop(manager);
+
+            if !is_pulled {
+                clear_published_diagnostics(&sender, &[uri]).unwrap();
+            }
+
+            sender
+                .send(lsp_server::Response::new_ok(id, report).into())
+                .unwrap();
+        });
+        Ok(())
+    }
+
+    fn workspace_diagnostic(&self, id: RequestId, params: WorkspaceDiagnosticParams) -> Result<()> {
+        let sender = self.connection.sender.clone();
+        let workspace = Arc::clone(&self.workspace);
+        let diag_manager = Arc::clone(&self.diag_manager);
+        self.pool.execute(move || {
+            let mut manager = diag_manager.lock().unwrap();
+            let new_uris: Vec<_> = workspace
+                .documents()
+                .into_iter()
+                .map(|document| Arc::clone(&document.uri))
+                .filter(|uri| !manager.is_pulled(uri))
+                .collect();
+
+            let report = manager.pull_workspace(workspace.as_ref(), &params.previous_result_ids);
+            drop(manager);
+
+            clear_published_diagnostics(&sender, &new_uris).unwrap();
+            sender
+                .send(lsp_server::Response::new_ok(id, report).into())
+                .unwrap();
+        });
+        Ok(())
+    }
+
     #[cfg(feature = "completion")]
     fn completion(
         &self,
@@ -577,6 +792,57 @@ impl Server {
         Ok(())
     }
 
+    fn selection_range(
+        &self,
+        id: RequestId,
+        params: SelectionRangeParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        self.handle_feature_request(id, params, uri, token, find_selection_ranges)?;
+        Ok(())
+    }
+
+    fn document_color(
+        &self,
+        id: RequestId,
+        params: DocumentColorParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        self.handle_feature_request(id, params, uri, token, find_document_colors)?;
+        Ok(())
+    }
+
+    fn color_presentation(
+        &self,
+        id: RequestId,
+        params: ColorPresentationParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        self.handle_feature_request(id, params, uri, token, find_color_presentations)?;
+        Ok(())
+    }
+
+    fn linked_editing_range(
+        &self,
+        id: RequestId,
+        params: LinkedEditingRangeParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(
+            params
+                .text_document_position_params
+                .text_document
+                .uri
+                .clone()
+                .into(),
+        );
+        self.handle_feature_request(id, params, uri, token, find_linked_editing_ranges)?;
+        Ok(())
+    }
+
     fn references(
         &self,
         id: RequestId,
@@ -694,6 +960,57 @@ impl Server {
         Ok(())
     }
 
+    fn signature_help(
+        &self,
+        id: RequestId,
+        params: SignatureHelpParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(
+            params
+                .text_document_position_params
+                .text_document
+                .uri
+                .clone()
+                .into(),
+        );
+        self.handle_feature_request(id, params, uri, token, find_signature_help)?;
+        Ok(())
+    }
+
+    fn inlay_hint(
+        &self,
+        id: RequestId,
+        params: InlayHintParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        self.handle_feature_request(id, params, uri, token, find_inlay_hints)?;
+        Ok(())
+    }
+
+    fn code_action(
+        &self,
+        id: RequestId,
+        params: CodeActionParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        self.handle_feature_request(id, params, uri, token, find_code_actions)?;
+        Ok(())
+    }
+
+    fn code_lens(
+        &self,
+        id: RequestId,
+        params: CodeLensParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        self.handle_feature_request(id, params, uri, token, find_code_lenses)?;
+        Ok(())
+    }
+
     #[cfg(feature = "semantic")]
     fn semantic_tokens_range(
         &self,
@@ -722,6 +1039,56 @@ impl Server {
         Ok(())
     }
 
+    #[cfg(feature = "semantic")]
+    fn semantic_tokens_full(
+        &self,
+        id: RequestId,
+        params: SemanticTokensParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        let cache = Arc::clone(&self.semantic_tokens_cache);
+        self.handle_feature_request(id, params, uri, token, move |request, token| {
+            crate::features::find_semantic_tokens_full(request, &cache, token)
+        })?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "semantic"))]
+    fn semantic_tokens_full(
+        &self,
+        _id: RequestId,
+        _params: SemanticTokensParams,
+        _token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(feature = "semantic")]
+    fn semantic_tokens_full_delta(
+        &self,
+        id: RequestId,
+        params: SemanticTokensDeltaParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        let cache = Arc::clone(&self.semantic_tokens_cache);
+        self.handle_feature_request(id, params, uri, token, move |request, token| {
+            crate::features::find_semantic_tokens_full_delta(request, &cache, token)
+        })?;
+        Ok(())
+    }
+
+    #[cfg(not(feature = "semantic"))]
+    fn semantic_tokens_full_delta(
+        &self,
+        _id: RequestId,
+        _params: SemanticTokensDeltaParams,
+        _token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        Ok(())
+    }
+
     fn build(
         &self,
         id: RequestId,
@@ -732,16 +1099,28 @@ impl Server {
         let lsp_sender = self.connection.sender.clone();
         let req_queue = Arc::clone(&self.req_queue);
         let build_engine = Arc::clone(&self.build_engine);
-        self.handle_feature_request(id, params, uri, token, move |request, token| {
-            build_engine
-                .build(request, token, &req_queue, &lsp_sender)
-                .unwrap_or_else(|why| {
-                    error!("Build failed: {}", why);
-                    BuildResult {
-                        status: BuildStatus::FAILURE,
-                    }
-                })
-        })?;
+        match self.feature_request(uri, params) {
+            Some(request) => {
+                let token = Arc::clone(token);
+                self.pool.execute(move || {
+                    let result = build_engine
+                        .build(request, &token, &req_queue, &lsp_sender)
+                        .unwrap_or_else(|why| {
+                            error!("Build failed: {}", why);
+                            BuildResult {
+                                status: BuildStatus::FAILURE,
+                            }
+                        });
+
+                    lsp_sender
+                        .send(lsp_server::Response::new_ok(id, result).into())
+                        .unwrap();
+                });
+            }
+            None => {
+                self.send_feature_error(id)?;
+            }
+        };
         Ok(())
     }
 
@@ -762,6 +1141,28 @@ impl Server {
         Ok(())
     }
 
+    fn forward_search_location(
+        &self,
+        id: RequestId,
+        params: TextDocumentPositionParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        self.handle_feature_request(id, params, uri, token, find_forward_search_location)?;
+        Ok(())
+    }
+
+    fn inverse_search(
+        &self,
+        id: RequestId,
+        params: InverseSearchParams,
+        token: &Arc<CancellationToken>,
+    ) -> Result<()> {
+        let uri = Arc::new(params.text_document.uri.clone().into());
+        self.handle_feature_request(id, params, uri, token, find_inverse_search_location)?;
+        Ok(())
+    }
+
     fn process_messages(&self) -> Result<()> {
         for msg in &self.connection.receiver {
             match msg {
@@ -778,6 +1179,9 @@ impl Server {
                         .on::<FoldingRangeRequest, _>(|id, params| {
                             self.folding_range(id, params, &token)
                         })?
+                        .on::<SelectionRangeRequest, _>(|id, params| {
+                            self.selection_range(id, params, &token)
+                        })?
                         .on::<References, _>(|id, params| self.references(id, params, &token))?
                         .on::<HoverRequest, _>(|id, params| self.hover(id, params, &token))?
                         .on::<DocumentSymbolRequest, _>(|id, params| {
@@ -786,6 +1190,9 @@ impl Server {
                         .on::<WorkspaceSymbol, _>(|id, params| {
                             self.workspace_symbols(id, params, &token)
                         })?
+                        .on::<WillRenameFiles, _>(|id, params| {
+                            self.will_rename_files(id, params, &token)
+                        })?
                         .on::<Completion, _>(|id, params| {
                             #[cfg(feature = "completion")]
                             self.completion(id, params, &token)?;
@@ -803,17 +1210,54 @@ impl Server {
                             self.prepare_rename(id, params, &token)
                         })?
                         .on::<Rename, _>(|id, params| self.rename(id, params, &token))?
+                        .on::<DocumentColor, _>(|id, params| {
+                            self.document_color(id, params, &token)
+                        })?
+                        .on::<ColorPresentationRequest, _>(|id, params| {
+                            self.color_presentation(id, params, &token)
+                        })?
+                        .on::<LinkedEditingRange, _>(|id, params| {
+                            self.linked_editing_range(id, params, &token)
+                        })?
                         .on::<DocumentHighlightRequest, _>(|id, params| {
                             self.document_highlight(id, params, &token)
                         })?
                         .on::<Formatting, _>(|id, params| self.formatting(id, params, &token))?
+                        .on::<SignatureHelpRequest, _>(|id, params| {
+                            self.signature_help(id, params, &token)
+                        })?
+                        .on::<InlayHintRequest, _>(|id, params| {
+                            self.inlay_hint(id, params, &token)
+                        })?
+                        .on::<DocumentDiagnosticRequest, _>(|id, params| {
+                            self.document_diagnostic(id, params)
+                        })?
+                        .on::<WorkspaceDiagnosticRequest, _>(|id, params| {
+                            self.workspace_diagnostic(id, params)
+                        })?
+                        .on::<CodeActionRequest, _>(|id, params| {
+                            self.code_action(id, params, &token)
+                        })?
+                        .on::<CodeLensRequest, _>(|id, params| self.code_lens(id, params, &token))?
                         .on::<BuildRequest, _>(|id, params| self.build(id, params, &token))?
                         .on::<ForwardSearchRequest, _>(|id, params| {
                             self.forward_search(id, params, &token)
                         })?
+                        .on::<ForwardSearchLocationRequest, _>(|id, params| {
+                            self.forward_search_location(id, params, &token)
+                        })?
+                        .on::<InverseSearchRequest, _>(|id, params| {
+                            self.inverse_search(id, params, &token)
+                        })?
                         .on::<SemanticTokensRangeRequest, _>(|id, params| {
                             self.semantic_tokens_range(id, params, &token)
                         })?
+                        .on::<SemanticTokensFullRequest, _>(|id, params| {
+                            self.semantic_tokens_full(id, params, &token)
+                        })?
+                        .on::<SemanticTokensFullDeltaRequest, _>(|id, params| {
+                            self.semantic_tokens_full_delta(id, params, &token)
+                        })?
                         .default()
                     {
                         self.connection.sender.send(response.into())?;
@@ -822,9 +1266,16 @@ impl Server {
                 Message::Notification(notification) => {
                     NotificationDispatcher::new(notification)
                         .on::<Cancel, _>(|params| self.cancel(params))?
+                        .on::<WorkDoneProgressCancel, _>(|params| self.cancel_progress(params))?
                         .on::<DidChangeConfiguration, _>(|params| {
                             self.did_change_configuration(params)
                         })?
+                        .on::<DidChangeWorkspaceFolders, _>(|params| {
+                            self.did_change_workspace_folders(params)
+                        })?
+                        .on::<DidChangeWatchedFiles, _>(|params| {
+                            self.did_change_watched_files(params)
+                        })?
                         .on::<DidOpenTextDocument, _>(|params| self.did_open(params))?
                         .on::<DidChangeTextDocument, _>(|params| self.did_change(params))?
                         .on::<DidSaveTextDocument, _>(|params| self.did_save(params))?
@@ -848,6 +1299,7 @@ impl Server {
     pub fn run(mut self) -> Result<()> {
         self.initialize()?;
         self.process_messages()?;
+        self.build_engine.stop_continuous_builds();
         drop(self.static_debouncer);
         drop(self.chktex_debouncer);
         self.pool.join();
@@ -862,12 +1314,99 @@ fn pull_and_reparse_all(
     workspace: Arc<dyn Workspace>,
 ) {
     let client_capabilities = { context.client_capabilities.lock().unwrap().clone() };
-    pull_config(&req_queue, &sender, &context.options, &client_capabilities);
+    pull_config(&req_queue, &sender, &context, &client_capabilities);
+    watch_aux_directories(&context, workspace.as_ref());
+    reparse_all(workspace.as_ref());
+}
+
+/// Asks the client to report changes to the files that the server might have loaded.
+fn register_file_watcher(
+    req_queue: &Mutex<ReqQueue>,
+    sender: &Sender<Message>,
+    client_capabilities: &Mutex<ClientCapabilities>,
+) {
+    if !client_capabilities
+        .lock()
+        .unwrap()
+        .has_file_watching_support()
+    {
+        return;
+    }
+
+    let options = DidChangeWatchedFilesRegistrationOptions {
+        watchers: vec![FileSystemWatcher {
+            glob_pattern: "**/*.{tex,sty,cls,def,lco,aux,rnw,bib,bibtex,log}".to_string(),
+            kind: None,
+        }],
+    };
+
+    let reg = Registration {
+        id: "watch-files".to_string(),
+        method: DidChangeWatchedFiles::METHOD.to_string(),
+        register_options: serde_json::to_value(options).ok(),
+    };
+
+    let params = RegistrationParams {
+        registrations: vec![reg],
+    };
+
+    if let Err(why) = send_request::<request::RegisterCapability>(req_queue, sender, params) {
+        error!(
+            "Failed to register \"{}\" notification: {}",
+            DidChangeWatchedFiles::METHOD,
+            why
+        );
+    }
+}
+
+fn watch_aux_directories(context: &ServerContext, workspace: &dyn Workspace) {
     if let Some(path) = { context.options.read().unwrap().aux_directory.clone() } {
         let _ = workspace.watch(path, RecursiveMode::NonRecursive);
     }
 
-    reparse_all(workspace.as_ref());
+    let folders = { context.workspace_folders.read().unwrap().clone() };
+    for folder in folders {
+        let aux_directory = folder
+            .options
+            .and_then(|options| options.aux_directory)
+            .or_else(|| context.options.read().unwrap().aux_directory.clone());
+
+        if let Some(path) = aux_directory {
+            let _ = workspace.watch(folder.directory.join(path), RecursiveMode::NonRecursive);
+        }
+    }
+}
+
+fn restart_continuous_builds(
+    build_engine: &BuildEngine,
+    context: Arc<ServerContext>,
+    workspace: Arc<dyn Workspace>,
+    req_queue: &Mutex<ReqQueue>,
+    sender: &Sender<Message>,
+) {
+    for uri in build_engine.continuous_roots() {
+        if !context.options_for(&uri).build.is_continuous {
+            build_engine.stop_continuous_build(&uri);
+            continue;
+        }
+
+        if let Some(subset) = workspace.subset(Arc::clone(&uri)) {
+            let request = FeatureRequest {
+                context: Arc::clone(&context),
+                params: BuildParams {
+                    text_document: TextDocumentIdentifier::new(uri.as_ref().clone().into()),
+                },
+                workspace: Arc::clone(&workspace),
+                subset,
+            };
+
+            if let Err(why) =
+                build_engine.build(request, CancellationToken::none(), req_queue, sender)
+            {
+                error!("Build failed: {}", why);
+            }
+        }
+    }
 }
 
 fn reparse_all(workspace: &dyn Workspace) {
@@ -881,43 +1420,110 @@ fn reparse_all(workspace: &dyn Workspace) {
     }
 }
 
+fn apply_text_changes(
+    old_document: &Document,
+    changes: Vec<TextDocumentContentChangeEvent>,
+) -> String {
+    let mut text = old_document.text.clone();
+    for (i, change) in changes.into_iter().enumerate() {
+        match change.range {
+            Some(range) => {
+                let range = if i == 0 {
+                    old_document.line_index.offset_lsp_range(range)
+                } else {
+                    LineIndex::new(&text).offset_lsp_range(range)
+                };
+                text.replace_range(std::ops::Range::<usize>::from(range), &change.text);
+            }
+            None => text = change.text,
+        }
+    }
+    text
+}
+
 fn create_static_debouncer(
     manager: Arc<Mutex<DiagnosticsManager>>,
+    req_queue: Arc<Mutex<ReqQueue>>,
     conn: &Connection,
     context: Arc<ServerContext>,
 ) -> DiagnosticsDebouncer {
     let sender = conn.sender.clone();
-    DiagnosticsDebouncer::launch(context, move |workspace, document| {
+    DiagnosticsDebouncer::launch(Arc::clone(&context), move |workspace, document| {
+        let options = context.options_for(&document.uri);
         let mut manager = manager.lock().unwrap();
-        manager.update_static(workspace.as_ref(), Arc::clone(&document.uri));
-        if let Err(why) = publish_diagnostics(&sender, workspace.as_ref(), &manager) {
-            warn!("Failed to publish diagnostics: {}", why);
-        }
+        manager.update_static(workspace.as_ref(), Arc::clone(&document.uri), &options);
+        report_diagnostics(&req_queue, &sender, &context, workspace.as_ref(), manager);
     })
 }
 
 fn create_chktex_debouncer(
     manager: Arc<Mutex<DiagnosticsManager>>,
+    req_queue: Arc<Mutex<ReqQueue>>,
     conn: &Connection,
     context: Arc<ServerContext>,
 ) -> DiagnosticsDebouncer {
     let sender = conn.sender.clone();
     DiagnosticsDebouncer::launch(Arc::clone(&context), move |workspace, document| {
-        let options = { context.options.read().unwrap().clone() };
+        let options = context.options_for(&document.uri);
         let mut manager = manager.lock().unwrap();
         manager.update_chktex(workspace.as_ref(), Arc::clone(&document.uri), &options);
-        if let Err(why) = publish_diagnostics(&sender, workspace.as_ref(), &manager) {
-            warn!("Failed to publish diagnostics: {}", why);
-        }
+        report_diagnostics(&req_queue, &sender, &context, workspace.as_ref(), manager);
     })
 }
 
+/// Publishes the diagnostics of all documents that the client has not pulled yet.
+/// If the client supports it, it is asked to pull the other diagnostics again.
+fn report_diagnostics(
+    req_queue: &Mutex<ReqQueue>,
+    sender: &Sender<lsp_server::Message>,
+    context: &ServerContext,
+    workspace: &dyn Workspace,
+    mut manager: MutexGuard<DiagnosticsManager>,
+) {
+    let removed_uris = manager.prune(workspace);
+    if let Err(why) = publish_diagnostics(sender, workspace, &manager, removed_uris) {
+        warn!("Failed to publish diagnostics: {}", why);
+    }
+
+    let pull_support = *context.diagnostic_pull_support.lock().unwrap();
+    if pull_support.enabled && pull_support.refresh {
+        drop(manager);
+        if let Err(why) = send_request::<WorkspaceDiagnosticRefresh>(req_queue, sender, ()) {
+            warn!("Failed to refresh diagnostics: {}", why);
+        }
+    }
+}
+
+/// Clears the published diagnostics of documents that were removed or that the client pulls now.
+fn clear_published_diagnostics(
+    sender: &Sender<lsp_server::Message>,
+    uris: &[Arc<Uri>],
+) -> Result<()> {
+    for uri in uris {
+        send_notification::<PublishDiagnostics>(
+            sender,
+            PublishDiagnosticsParams {
+                uri: uri.as_ref().clone().into(),
+                version: None,
+                diagnostics: Vec::new(),
+            },
+        )?;
+    }
+    Ok(())
+}
+
 fn publish_diagnostics(
     sender: &Sender<lsp_server::Message>,
     workspace: &dyn Workspace,
     diag_manager: &DiagnosticsManager,
+    removed_uris: Vec<Arc<Uri>>,
 ) -> Result<()> {
+    clear_published_diagnostics(sender, &removed_uris)?;
     for document in workspace.documents() {
+        if diag_manager.is_pulled(&document.uri) {
+            continue;
+        }
+
         let diagnostics = diag_manager.publish(Arc::clone(&document.uri));
         send_notification::<PublishDiagnostics>(
             sender,
@@ -958,3 +1564,155 @@ impl lsp_types::request::Request for ForwardSearchRequest {
 
     const METHOD: &'static str = "textDocument/forwardSearch";
 }
+
+struct ForwardSearchLocationRequest;
+
+impl lsp_types::request::Request for ForwardSearchLocationRequest {
+    type Params = TextDocumentPositionParams;
+
+    type Result = Option<ForwardSearchLocation>;
+
+    const METHOD: &'static str = "textDocument/forwardSearchLocation";
+}
+
+struct InverseSearchRequest;
+
+impl lsp_types::request::Request for InverseSearchRequest {
+    type Params = InverseSearchParams;
+
+    type Result = Option<Location>;
+
+    const METHOD: &'static str = "texlab/inverseSearch";
+}
+
+struct InlayHintRequest;
+
+impl lsp_types::request::Request for InlayHintRequest {
+    type Params = InlayHintParams;
+
+    type Result = Vec<InlayHint>;
+
+    const METHOD: &'static str = "textDocument/inlayHint";
+}
+
+struct DocumentDiagnosticRequest;
+
+impl lsp_types::request::Request for DocumentDiagnosticRequest {
+    type Params = DocumentDiagnosticParams;
+
+    type Result = DocumentDiagnosticReport;
+
+    const METHOD: &'static str = "textDocument/diagnostic";
+}
+
+struct WorkspaceDiagnosticRequest;
+
+impl lsp_types::request::Request for WorkspaceDiagnosticRequest {
+    type Params = WorkspaceDiagnosticParams;
+
+    type Result = WorkspaceDiagnosticReport;
+
+    const METHOD: &'static str = "workspace/diagnostic";
+}
+
+struct WorkspaceDiagnosticRefresh;
+
+impl lsp_types::request::Request for WorkspaceDiagnosticRefresh {
+    type Params = ();
+
+    type Result = ();
+
+    const METHOD: &'static str = "workspace/diagnostic/refresh";
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::{create_workspace_fast, Options};
+
+    use super::*;
+
+    #[test]
+    fn test_publish_diagnostics_of_removed_document() {
+        let context = Arc::new(ServerContext::new(env::temp_dir()));
+        let workspace = create_workspace_fast(context).unwrap();
+        let uri = Arc::new(Uri::from_file_path(env::temp_dir().join("main.tex")).unwrap());
+        workspace.open(
+            Arc::clone(&uri),
+            "\\begin{document}\\ref{foo}\\end{document}".into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Server,
+        );
+
+        let mut manager = DiagnosticsManager::default();
+        manager.update_static(&workspace, Arc::clone(&uri), &Options::default());
+        workspace.remove(&uri);
+        let removed_uris = manager.prune(&workspace);
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        publish_diagnostics(&sender, &workspace, &manager, removed_uris).unwrap();
+
+        let all_params: Vec<PublishDiagnosticsParams> = receiver
+            .try_iter()
+            .map(|message| match message {
+                Message::Notification(notification) => {
+                    assert_eq!(notification.method, PublishDiagnostics::METHOD);
+                    serde_json::from_value(notification.params).unwrap()
+                }
+                message => panic!("unexpected message: {:?}", message),
+            })
+            .collect();
+
+        assert_eq!(
+            all_params,
+            vec![PublishDiagnosticsParams {
+                uri: uri.as_ref().clone().into(),
+                version: None,
+                diagnostics: Vec::new(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_publish_diagnostics_skips_pulled_documents() {
+        let context = Arc::new(ServerContext::new(env::temp_dir()));
+        let workspace = create_workspace_fast(context).unwrap();
+        let main_uri = Arc::new(Uri::from_file_path(env::temp_dir().join("main.tex")).unwrap());
+        let chapter_uri =
+            Arc::new(Uri::from_file_path(env::temp_dir().join("chapter.tex")).unwrap());
+        workspace.open(
+            Arc::clone(&chapter_uri),
+            "\\ref{foo}".into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Server,
+        );
+        workspace.open(
+            Arc::clone(&main_uri),
+            "\\begin{document}\\input{chapter}\\end{document}".into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Client,
+        );
+
+        let mut manager = DiagnosticsManager::default();
+        manager.update_static(&workspace, Arc::clone(&main_uri), &Options::default());
+        manager.pull(Arc::clone(&main_uri), None);
+
+        let (sender, receiver) = crossbeam_channel::unbounded();
+        publish_diagnostics(&sender, &workspace, &manager, Vec::new()).unwrap();
+
+        let all_params: Vec<PublishDiagnosticsParams> = receiver
+            .try_iter()
+            .map(|message| match message {
+                Message::Notification(notification) => {
+                    serde_json::from_value(notification.params).unwrap()
+                }
+                message => panic!("unexpected message: {:?}", message),
+            })
+            .collect();
+
+        assert_eq!(all_params.len(), 1);
+        assert_eq!(Uri::from(all_params[0].uri.clone()), *chapter_uri);
+        assert_eq!(all_params[0].diagnostics.len(), 1);
+    }
+}
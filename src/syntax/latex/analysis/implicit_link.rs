@@ -18,22 +18,15 @@ fn find_by_extension(context: &LatexAnalyzerContext, extension: &str) -> Option<
         let file_stem = file_path.file_stem()?;
         let aux_name = format!("{}.{}", file_stem.to_str()?, extension);
 
-        let options = context.inner.options.read().unwrap();
+        let options = context.inner.options_for(&context.document_uri);
+        let directory = context.inner.directory_for(&context.document_uri);
         if let Some(root_dir) = options.root_directory.as_ref() {
-            let path = context
-                .inner
-                .current_directory
-                .join(root_dir)
-                .join(&aux_name);
+            let path = directory.join(root_dir).join(&aux_name);
             targets.push(Arc::new(Uri::from_file_path(path).ok()?));
         }
 
         if let Some(build_dir) = options.aux_directory.as_ref() {
-            let path = context
-                .inner
-                .current_directory
-                .join(build_dir)
-                .join(&aux_name);
+            let path = directory.join(build_dir).join(&aux_name);
             targets.push(Arc::new(Uri::from_file_path(path).ok()?));
         }
     }
@@ -9,6 +9,10 @@ mod label_number;
 mod theorem;
 mod types;
 
+use std::sync::Arc;
+
+use cstree::{NodeOrToken, TextRange};
+
 use crate::syntax::latex;
 
 pub use self::types::*;
@@ -26,15 +30,202 @@ use self::{
 pub fn analyze(context: &mut LatexAnalyzerContext, root: &latex::SyntaxNode) {
     analyze_implicit_links(context);
     for node in root.descendants() {
-        analyze_command(context, node)
-            .or_else(|| analyze_command_definition(context, node))
-            .or_else(|| analyze_begin(context, node))
-            .or_else(|| analyze_include(context, node))
-            .or_else(|| analyze_import(context, node))
-            .or_else(|| analyze_label_name(context, node))
-            .or_else(|| analyze_label_number(context, node))
-            .or_else(|| analyze_theorem_definition(context, node))
-            .or_else(|| analyze_graphics_path(context, node));
+        analyze_node(context, node);
     }
     context.extras.has_document_environment = context.extras.environment_names.contains("document");
 }
+
+/// Updates the analysis results of the old tree after the nodes in `old_range`
+/// have been replaced by the nodes in `new_range` of the new tree.
+/// Returns `None` without touching the results if the whole document needs to be analyzed again,
+/// which is the case if the change removes names that might be declared elsewhere.
+pub fn analyze_incrementally(
+    context: &mut LatexAnalyzerContext,
+    old_root: &latex::SyntaxNode,
+    old_range: TextRange,
+    new_root: &latex::SyntaxNode,
+    new_range: TextRange,
+) -> Option<()> {
+    let old_extras = analyze_range(context, old_root, old_range);
+    let new_extras = analyze_range(context, new_root, new_range);
+    if !old_extras
+        .command_names
+        .is_subset(&new_extras.command_names)
+        || !old_extras
+            .environment_names
+            .is_subset(&new_extras.environment_names)
+        || !old_extras
+            .graphics_paths
+            .is_subset(&new_extras.graphics_paths)
+        || old_extras.label_numbers_by_name != new_extras.label_numbers_by_name
+        || old_extras.theorem_environments != new_extras.theorem_environments
+    {
+        return None;
+    }
+
+    let extras = &mut context.extras;
+    extras.command_names.extend(new_extras.command_names);
+    extras
+        .environment_names
+        .extend(new_extras.environment_names);
+    extras.graphics_paths.extend(new_extras.graphics_paths);
+    extras.has_document_environment = extras.environment_names.contains("document");
+
+    let shift = |range: TextRange| {
+        if range.start() >= old_range.end() {
+            range - old_range.end() + new_range.end()
+        } else {
+            range
+        }
+    };
+
+    extras
+        .label_names
+        .retain(|name| !old_range.contains_range(name.range));
+    for name in &mut extras.label_names {
+        name.range = shift(name.range);
+    }
+    let index = extras
+        .label_names
+        .partition_point(|name| name.range.start() < new_range.start());
+    extras
+        .label_names
+        .splice(index..index, new_extras.label_names);
+
+    extras
+        .explicit_links
+        .retain(|link| !old_range.contains_range(link.stem_range));
+    for link in &mut extras.explicit_links {
+        link.stem_range = shift(link.stem_range);
+    }
+    let index = extras
+        .explicit_links
+        .partition_point(|link| link.stem_range.start() < new_range.start());
+    extras
+        .explicit_links
+        .splice(index..index, new_extras.explicit_links);
+
+    Some(())
+}
+
+fn analyze_range(
+    context: &LatexAnalyzerContext,
+    root: &latex::SyntaxNode,
+    range: TextRange,
+) -> Extras {
+    let mut context = LatexAnalyzerContext {
+        inner: Arc::clone(&context.inner),
+        document_uri: Arc::clone(&context.document_uri),
+        base_uri: Arc::clone(&context.base_uri),
+        extras: Extras::default(),
+    };
+
+    let parent = match root.covering_element(range) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => token.parent(),
+    };
+
+    for node in parent
+        .children()
+        .filter(|node| range.contains_range(node.text_range()))
+        .flat_map(|node| node.descendants())
+    {
+        analyze_node(&mut context, node);
+    }
+
+    context.extras
+}
+
+fn analyze_node(context: &mut LatexAnalyzerContext, node: &latex::SyntaxNode) {
+    analyze_command(context, node)
+        .or_else(|| analyze_command_definition(context, node))
+        .or_else(|| analyze_begin(context, node))
+        .or_else(|| analyze_include(context, node))
+        .or_else(|| analyze_import(context, node))
+        .or_else(|| analyze_label_name(context, node))
+        .or_else(|| analyze_label_number(context, node))
+        .or_else(|| analyze_theorem_definition(context, node))
+        .or_else(|| analyze_graphics_path(context, node));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crate::{syntax::latex, ExperimentalOptions, ServerContext, Uri};
+
+    use super::*;
+
+    fn create_context() -> LatexAnalyzerContext {
+        let uri = Arc::new(Uri::from_file_path(env::temp_dir().join("main.tex")).unwrap());
+        LatexAnalyzerContext {
+            inner: Arc::new(ServerContext::new(env::temp_dir())),
+            document_uri: Arc::clone(&uri),
+            base_uri: uri,
+            extras: Extras::default(),
+        }
+    }
+
+    fn analyze_text(text: &str) -> (latex::SyntaxNode, Extras) {
+        let root = latex::parse(text, &ExperimentalOptions::default()).root;
+        let mut context = create_context();
+        analyze(&mut context, &root);
+        (root, context.extras)
+    }
+
+    fn edit(old_text: &str, old: &str, new: &str) -> (LatexAnalyzerContext, Option<()>, String) {
+        let options = ExperimentalOptions::default();
+        let old_parse = latex::parse(old_text, &options);
+        let new_text = old_text.replacen(old, new, 1);
+        let change = latex::TextChange::between(old_text, &new_text).unwrap();
+        let reparse = latex::reparse(
+            &old_parse.root,
+            &old_parse.interner,
+            change,
+            &new_text,
+            &options,
+        )
+        .unwrap();
+
+        let mut context = create_context();
+        context.extras = analyze_text(old_text).1;
+        let result = analyze_incrementally(
+            &mut context,
+            &old_parse.root,
+            reparse.old_range,
+            &reparse.root,
+            reparse.new_range,
+        );
+        (context, result, new_text)
+    }
+
+    #[test]
+    fn test_insert_label() {
+        let (context, result, new_text) = edit(
+            "\\begin{document}\n\\label{foo} bar \\input{baz}\nqux \\label{qux}\n\\end{document}",
+            "bar",
+            "\\ref{foo} \\label{bar}",
+        );
+        assert_eq!(result, Some(()));
+        assert_eq!(context.extras, analyze_text(&new_text).1);
+    }
+
+    #[test]
+    fn test_remove_include() {
+        let (context, result, new_text) = edit(
+            "\\begin{document}\n\\label{foo} bar \\input{baz} \\include{qux}\n\\label{qux}\n\\end{document}",
+            "\\input{baz}",
+            "",
+        );
+        assert_eq!(result, Some(()));
+        assert_eq!(context.extras, analyze_text(&new_text).1);
+    }
+
+    #[test]
+    fn test_remove_command() {
+        let old_text = "\\begin{document}\nfoo \\bar baz\n\\end{document}";
+        let (context, result, _) = edit(old_text, "\\bar", "");
+        assert_eq!(result, None);
+        assert_eq!(context.extras, analyze_text(old_text).1);
+    }
+}
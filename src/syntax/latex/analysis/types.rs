@@ -14,7 +14,7 @@ pub struct LatexAnalyzerContext {
     pub extras: Extras,
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct Extras {
     pub implicit_links: ImplicitLinks,
     pub explicit_links: Vec<ExplicitLink>,
@@ -42,7 +42,7 @@ pub enum ExplicitLinkKind {
     Bibtex,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct ExplicitLink {
     pub stem: SmolStr,
     pub stem_range: TextRange,
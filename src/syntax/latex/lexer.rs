@@ -1,4 +1,7 @@
 use logos::Logos;
+use rustc_hash::FxHashSet;
+
+use crate::ExperimentalOptions;
 
 use super::kind::SyntaxKind;
 
@@ -420,9 +423,9 @@ pub struct Lexer<'a> {
 }
 
 impl<'a> Lexer<'a> {
-    pub fn new(input: &'a str) -> Self {
+    pub fn new(input: &'a str, options: &ExperimentalOptions) -> Self {
         let mut tokens = Vec::new();
-        tokenize(input, &mut tokens);
+        tokenize(input, options, &mut tokens);
         tokens.reverse();
         Self { tokens }
     }
@@ -436,7 +439,11 @@ impl<'a> Lexer<'a> {
     }
 }
 
-fn tokenize<'a>(input: &'a str, tokens: &mut Vec<(SyntaxKind, &'a str)>) {
+fn tokenize<'a>(
+    input: &'a str,
+    options: &ExperimentalOptions,
+    tokens: &mut Vec<(SyntaxKind, &'a str)>,
+) {
     let mut lexer = RootToken::lexer(input);
     while let Some(kind) = lexer.next() {
         let text = lexer.slice();
@@ -481,8 +488,18 @@ fn tokenize<'a>(input: &'a str, tokens: &mut Vec<(SyntaxKind, &'a str)>) {
                 tokens.push((SyntaxKind::DOLLAR, text));
             }
             RootToken::CommandName => {
-                let kind = tokenize_command_name(text);
+                let kind = tokenize_command_name(text, options);
                 tokens.push((kind, text));
+                if kind == SyntaxKind::BEGIN_ENVIRONMENT_NAME {
+                    let end = lexer.span().end;
+                    if let Some(rest) = tokenize_custom_verbatim_environment(
+                        &lexer.source()[end..],
+                        &options.verbatim_environments,
+                        tokens,
+                    ) {
+                        lexer = RootToken::lexer(rest);
+                    }
+                }
             }
             RootToken::BeginBlockComment => {
                 tokens.push((SyntaxKind::BEGIN_BLOCK_COMMENT_NAME, text));
@@ -504,7 +521,7 @@ fn tokenize<'a>(input: &'a str, tokens: &mut Vec<(SyntaxKind, &'a str)>) {
     }
 }
 
-fn tokenize_command_name(text: &str) -> SyntaxKind {
+fn tokenize_command_name(text: &str, options: &ExperimentalOptions) -> SyntaxKind {
     let mut lexer = CommandNameToken::lexer(text);
     match lexer.next().unwrap() {
         CommandNameToken::BeginEnvironment => SyntaxKind::BEGIN_ENVIRONMENT_NAME,
@@ -550,7 +567,29 @@ fn tokenize_command_name(text: &str) -> SyntaxKind {
         CommandNameToken::EnvironmentDefinition => SyntaxKind::ENVIRONMENT_DEFINITION_NAME,
         CommandNameToken::EndBlockComment => SyntaxKind::END_BLOCK_COMMENT_NAME,
         CommandNameToken::GraphicsPath => SyntaxKind::GRAPHICS_PATH_NAME,
-        CommandNameToken::Generic => SyntaxKind::GENERIC_COMMAND_NAME,
+        CommandNameToken::Generic => tokenize_custom_command_name(text, options),
+    }
+}
+
+fn tokenize_custom_command_name(text: &str, options: &ExperimentalOptions) -> SyntaxKind {
+    let name = &text[1..];
+    let contains = |names: &FxHashSet<String>| {
+        names.contains(name)
+            || name
+                .strip_suffix('*')
+                .map_or(false, |name| names.contains(name))
+    };
+
+    if contains(&options.citation_commands) {
+        SyntaxKind::CITATION_NAME
+    } else if contains(&options.label_definition_commands) {
+        SyntaxKind::LABEL_DEFINITION_NAME
+    } else if contains(&options.label_reference_commands) {
+        SyntaxKind::LABEL_REFERENCE_NAME
+    } else if contains(&options.include_commands) {
+        SyntaxKind::LATEX_INCLUDE_NAME
+    } else {
+        SyntaxKind::GENERIC_COMMAND_NAME
     }
 }
 
@@ -576,6 +615,33 @@ fn tokenize_block_comment<'a>(input: &'a str, tokens: &mut Vec<(SyntaxKind, &'a
     &input[end..]
 }
 
+fn tokenize_custom_verbatim_environment<'a>(
+    input: &'a str,
+    environments: &FxHashSet<String>,
+    tokens: &mut Vec<(SyntaxKind, &'a str)>,
+) -> Option<&'a str> {
+    let rest = input.strip_prefix('{')?;
+    let name = &rest[..rest.find('}')?];
+    if !environments.contains(name) {
+        return None;
+    }
+
+    tokens.push((SyntaxKind::L_CURLY, "{"));
+    tokens.push((SyntaxKind::WORD, name));
+    tokens.push((SyntaxKind::R_CURLY, "}"));
+
+    let body = &rest[name.len() + 1..];
+    let end = body
+        .find(&format!("\\end{{{}}}", name))
+        .unwrap_or(body.len());
+
+    if end > 0 {
+        tokens.push((SyntaxKind::VERBATIM, &body[..end]));
+    }
+
+    Some(&body[end..])
+}
+
 fn tokenize_verbatim_environment<'a>(
     input: &'a str,
     tokens: &mut Vec<(SyntaxKind, &'a str)>,
@@ -608,7 +674,14 @@ mod tests {
     use super::*;
 
     fn verify(text: &str) -> Vec<(SyntaxKind, &str)> {
-        let mut tokens = Lexer::new(text).tokens;
+        verify_with_options(text, &ExperimentalOptions::default())
+    }
+
+    fn verify_with_options<'a>(
+        text: &'a str,
+        options: &ExperimentalOptions,
+    ) -> Vec<(SyntaxKind, &'a str)> {
+        let mut tokens = Lexer::new(text, options).tokens;
         tokens.reverse();
         tokens
     }
@@ -671,4 +744,44 @@ mod tests {
 \end{asy}"#
         ));
     }
+
+    #[test]
+    fn test_custom_commands() {
+        let mut options = ExperimentalOptions::default();
+        options.citation_commands.insert("mycite".into());
+        options.label_reference_commands.insert("figref".into());
+
+        assert_eq!(
+            verify_with_options(r#"\mycite*{foo}\figref\bar"#, &options),
+            vec![
+                (SyntaxKind::CITATION_NAME, "\\mycite*"),
+                (SyntaxKind::L_CURLY, "{"),
+                (SyntaxKind::WORD, "foo"),
+                (SyntaxKind::R_CURLY, "}"),
+                (SyntaxKind::LABEL_REFERENCE_NAME, "\\figref"),
+                (SyntaxKind::GENERIC_COMMAND_NAME, "\\bar"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_verbatim_environment() {
+        let mut options = ExperimentalOptions::default();
+        options.verbatim_environments.insert("code".into());
+
+        assert_eq!(
+            verify_with_options(r#"\begin{code}\foo{\end{code}"#, &options),
+            vec![
+                (SyntaxKind::BEGIN_ENVIRONMENT_NAME, "\\begin"),
+                (SyntaxKind::L_CURLY, "{"),
+                (SyntaxKind::WORD, "code"),
+                (SyntaxKind::R_CURLY, "}"),
+                (SyntaxKind::VERBATIM, "\\foo{"),
+                (SyntaxKind::END_ENVIRONMENT_NAME, "\\end"),
+                (SyntaxKind::L_CURLY, "{"),
+                (SyntaxKind::WORD, "code"),
+                (SyntaxKind::R_CURLY, "}"),
+            ]
+        );
+    }
 }
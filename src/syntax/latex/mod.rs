@@ -3,12 +3,19 @@ mod cst;
 mod kind;
 mod lexer;
 mod parser;
+mod reparser;
+
+use std::sync::Arc;
+
+use cstree::interning::Resolver;
+use lasso::{Spur, ThreadedRodeo};
 
 pub use self::{
     analysis::*,
     cst::*,
     kind::SyntaxKind::{self, *},
     parser::{parse, Parse},
+    reparser::{reparse, Reparse, TextChange},
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -34,3 +41,50 @@ pub type SyntaxToken = cstree::ResolvedToken<Language>;
 pub type SyntaxElement = cstree::ResolvedElement<Language>;
 
 pub type SyntaxElementRef<'a> = cstree::ResolvedElementRef<'a, Language>;
+
+/// The number of strings that reparsing may add to an interner
+/// on top of twice the strings of the last full parse.
+const MAX_INTERNER_GROWTH: usize = 4096;
+
+/// Deduplicates the token text of a syntax tree.
+/// The interner is shared between the versions of a document
+/// so that reparsed trees can reuse the green nodes of the previous tree.
+#[derive(Debug, Clone, Default)]
+pub struct SharedInterner {
+    rodeo: Arc<ThreadedRodeo>,
+    base_len: usize,
+}
+
+impl SharedInterner {
+    fn rodeo(&self) -> &ThreadedRodeo {
+        &self.rodeo
+    }
+
+    /// Returns `true` if the interner keeps so many strings of previous versions
+    /// that the document should be parsed again with a fresh interner.
+    fn is_exhausted(&self) -> bool {
+        self.rodeo.len() > 2 * self.base_len + MAX_INTERNER_GROWTH
+    }
+}
+
+impl Resolver for SharedInterner {
+    fn resolve<'a>(&'a self, key: &Spur) -> &'a str {
+        self.rodeo.resolve(key)
+    }
+
+    fn try_resolve<'a>(&'a self, key: &Spur) -> Option<&'a str> {
+        self.rodeo.try_resolve(key)
+    }
+
+    unsafe fn resolve_unchecked<'a>(&'a self, key: &Spur) -> &'a str {
+        self.rodeo.resolve_unchecked(key)
+    }
+
+    fn contains_key(&self, key: &Spur) -> bool {
+        self.rodeo.contains_key(key)
+    }
+
+    fn len(&self) -> usize {
+        self.rodeo.len()
+    }
+}
@@ -1,7 +1,11 @@
-use cstree::GreenNodeBuilder;
+use cstree::{GreenNode, GreenNodeBuilder, NodeCache};
+use lasso::ThreadedRodeo;
+
+use crate::ExperimentalOptions;
 
 use super::{
     lexer::Lexer,
+    SharedInterner,
     SyntaxKind::{self, *},
     SyntaxNode,
 };
@@ -9,6 +13,7 @@ use super::{
 #[derive(Clone)]
 pub struct Parse {
     pub root: SyntaxNode,
+    pub interner: SharedInterner,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -27,16 +32,20 @@ impl Default for ParserContext {
 }
 
 #[derive(Debug)]
-struct Parser<'a> {
+pub(super) struct Parser<'a> {
     lexer: Lexer<'a>,
-    builder: GreenNodeBuilder<'static, 'static>,
+    builder: GreenNodeBuilder<'a, 'a, &'a ThreadedRodeo>,
 }
 
 impl<'a> Parser<'a> {
-    pub fn new(text: &'a str) -> Self {
+    pub fn new(
+        text: &'a str,
+        options: &ExperimentalOptions,
+        cache: &'a mut NodeCache<'a, &'a ThreadedRodeo>,
+    ) -> Self {
         Self {
-            lexer: Lexer::new(text),
-            builder: GreenNodeBuilder::new(),
+            lexer: Lexer::new(text, options),
+            builder: GreenNodeBuilder::with_cache(cache),
         }
     }
 
@@ -81,17 +90,25 @@ impl<'a> Parser<'a> {
         }
     }
 
-    pub fn parse(mut self) -> Parse {
+    pub fn parse(mut self) -> GreenNode {
         self.builder.start_node(ROOT.into());
         self.preamble();
         while self.peek().is_some() {
             self.content(ParserContext::default());
         }
         self.builder.finish_node();
-        let (green_node, interner) = self.builder.finish();
-        Parse {
-            root: SyntaxNode::new_root_with_resolver(green_node, interner.unwrap()),
+        self.builder.finish().0
+    }
+
+    /// Parses the text as a sequence of content items without a preamble.
+    /// The items are wrapped into a `ROOT` node.
+    pub fn parse_content(mut self) -> GreenNode {
+        self.builder.start_node(ROOT.into());
+        while self.peek().is_some() {
+            self.content(ParserContext::default());
         }
+        self.builder.finish_node();
+        self.builder.finish().0
     }
 
     fn content(&mut self, context: ParserContext) {
@@ -1323,8 +1340,19 @@ impl<'a> Parser<'a> {
     }
 }
 
-pub fn parse(text: &str) -> Parse {
-    Parser::new(text).parse()
+pub fn parse(text: &str, options: &ExperimentalOptions) -> Parse {
+    let mut interner = SharedInterner::default();
+    let green_node = {
+        let mut rodeo = interner.rodeo();
+        let mut cache = NodeCache::with_interner(&mut rodeo);
+        Parser::new(text, options, &mut cache).parse()
+    };
+    interner.base_len = interner.rodeo.len();
+
+    Parse {
+        root: SyntaxNode::new_root_with_resolver(green_node, interner.clone()),
+        interner,
+    }
 }
 
 #[cfg(test)]
@@ -1334,7 +1362,11 @@ mod tests {
     use super::*;
 
     fn setup(text: &str) -> SyntaxNode {
-        parse(&text.trim().replace("\r", "")).root
+        parse(
+            &text.trim().replace("\r", ""),
+            &ExperimentalOptions::default(),
+        )
+        .root
     }
 
     #[test]
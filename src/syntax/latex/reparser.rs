@@ -0,0 +1,451 @@
+use std::ops::Range;
+
+use cstree::{GreenNode, GreenToken, NodeCache, NodeOrToken, TextRange, TextSize};
+
+use crate::ExperimentalOptions;
+
+use super::{
+    parser::Parser,
+    SharedInterner,
+    SyntaxKind::{self, *},
+    SyntaxNode,
+};
+
+type GreenElementRef<'a> = NodeOrToken<&'a GreenNode, &'a GreenToken>;
+
+/// A single contiguous change between two versions of a text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct TextChange {
+    /// The range of the old text that has been replaced.
+    pub range: TextRange,
+    /// The length of the inserted text.
+    pub new_len: TextSize,
+}
+
+impl TextChange {
+    /// Computes the smallest change that turns `old_text` into `new_text`.
+    /// Returns `None` if both texts are equal.
+    pub fn between(old_text: &str, new_text: &str) -> Option<Self> {
+        if old_text == new_text {
+            return None;
+        }
+
+        let mut prefix = old_text
+            .bytes()
+            .zip(new_text.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        while !old_text.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+
+        let mut suffix = old_text
+            .bytes()
+            .rev()
+            .zip(new_text.bytes().rev())
+            .take(old_text.len().min(new_text.len()) - prefix)
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        while !old_text.is_char_boundary(old_text.len() - suffix) {
+            suffix -= 1;
+        }
+
+        Some(Self {
+            range: TextRange::new(
+                TextSize::from(prefix as u32),
+                TextSize::from((old_text.len() - suffix) as u32),
+            ),
+            new_len: TextSize::from((new_text.len() - suffix - prefix) as u32),
+        })
+    }
+}
+
+/// The result of reparsing a document incrementally.
+#[derive(Clone)]
+pub struct Reparse {
+    pub root: SyntaxNode,
+
+    /// The range of the old tree that has been reparsed.
+    pub old_range: TextRange,
+
+    /// The range of the new tree that has been reparsed.
+    pub new_range: TextRange,
+}
+
+/// Reparses only the content items around the change and reuses the green nodes of the old tree
+/// for everything else. The new tree is resolved with the interner of the old tree.
+/// Returns `None` if the result could differ from parsing the new text from scratch
+/// or if the interner should be replaced by a fresh one.
+pub fn reparse(
+    root: &SyntaxNode,
+    interner: &SharedInterner,
+    change: TextChange,
+    new_text: &str,
+    options: &ExperimentalOptions,
+) -> Option<Reparse> {
+    if interner.is_exhausted() {
+        return None;
+    }
+
+    let mut containers = Vec::new();
+    let mut node = root;
+    loop {
+        if stop_kinds(node.kind()).is_some() {
+            containers.push(node);
+        }
+
+        match node
+            .children()
+            .find(|child| child.text_range().contains_range(change.range))
+        {
+            Some(child) => node = child,
+            None => break,
+        }
+    }
+
+    containers
+        .into_iter()
+        .rev()
+        .find_map(|container| reparse_container(container, interner, change, new_text, options))
+}
+
+fn reparse_container(
+    container: &SyntaxNode,
+    interner: &SharedInterner,
+    change: TextChange,
+    new_text: &str,
+    options: &ExperimentalOptions,
+) -> Option<Reparse> {
+    let stop_kinds = stop_kinds(container.kind())?;
+    let children: Vec<_> = container.green().children().collect();
+
+    let mut ranges = Vec::with_capacity(children.len());
+    let mut offset = container.text_range().start();
+    for child in &children {
+        let range = TextRange::at(offset, child.text_len());
+        ranges.push(range);
+        offset = range.end();
+    }
+
+    // The reparsed items are surrounded by an unchanged item on both sides.
+    // If these are reproduced exactly, the parser is back in sync with the old tree.
+    let items = item_range(container.kind(), &children);
+    let first = items
+        .clone()
+        .find(|&i| ranges[i].end() >= change.range.start())?;
+    let last = items
+        .clone()
+        .rev()
+        .find(|&i| ranges[i].start() <= change.range.end())?;
+    let start = first.checked_sub(1).filter(|i| items.contains(i))?;
+    let end = last + 1;
+    if !items.contains(&end)
+        || children[start..=end]
+            .iter()
+            .any(|&child| kind(child) == VERBATIM)
+    {
+        return None;
+    }
+
+    let old_range = TextRange::new(ranges[start].start(), ranges[end].end());
+    let new_range = TextRange::new(
+        old_range.start(),
+        old_range.end() - change.range.end() + change.range.start() + change.new_len,
+    );
+
+    let fragment = new_text.get(Range::<usize>::from(new_range))?;
+    let green_node = {
+        let mut rodeo = interner.rodeo();
+        let mut cache = NodeCache::with_interner(&mut rodeo);
+        Parser::new(fragment, options, &mut cache).parse_content()
+    };
+
+    let new_items: Vec<_> = green_node.children().collect();
+    if new_items.len() < 2
+        || new_items[0] != children[start]
+        || new_items[new_items.len() - 1] != children[end]
+        || new_items
+            .iter()
+            .filter_map(|&item| first_token_kind(item))
+            .any(|kind| stop_kinds.contains(&kind))
+    {
+        return None;
+    }
+
+    let new_children: Vec<_> = children[..=start]
+        .iter()
+        .chain(&new_items[1..new_items.len() - 1])
+        .chain(&children[end..])
+        .map(|&child| to_owned(child))
+        .collect();
+
+    let new_container = GreenNode::new(container.green().kind(), new_children);
+    let new_root = container.replace_with(new_container);
+    Some(Reparse {
+        root: SyntaxNode::new_root_with_resolver(new_root, interner.clone()),
+        old_range,
+        new_range,
+    })
+}
+
+/// Returns the tokens that end the content of a node
+/// or `None` if the node does not consist of content items.
+fn stop_kinds(kind: SyntaxKind) -> Option<&'static [SyntaxKind]> {
+    match kind {
+        ROOT => Some(&[]),
+        PREAMBLE | ENVIRONMENT => Some(&[END_ENVIRONMENT_NAME]),
+        PART => Some(&[END_ENVIRONMENT_NAME, R_CURLY, PART_NAME]),
+        CHAPTER => Some(&[END_ENVIRONMENT_NAME, R_CURLY, PART_NAME, CHAPTER_NAME]),
+        SECTION => Some(&[
+            END_ENVIRONMENT_NAME,
+            R_CURLY,
+            PART_NAME,
+            CHAPTER_NAME,
+            SECTION_NAME,
+        ]),
+        SUBSECTION => Some(&[
+            END_ENVIRONMENT_NAME,
+            R_CURLY,
+            PART_NAME,
+            CHAPTER_NAME,
+            SECTION_NAME,
+            SUBSECTION_NAME,
+        ]),
+        SUBSUBSECTION => Some(&[
+            END_ENVIRONMENT_NAME,
+            R_CURLY,
+            PART_NAME,
+            CHAPTER_NAME,
+            SECTION_NAME,
+            SUBSECTION_NAME,
+            SUBSUBSECTION_NAME,
+        ]),
+        PARAGRAPH => Some(&[
+            END_ENVIRONMENT_NAME,
+            R_CURLY,
+            PART_NAME,
+            CHAPTER_NAME,
+            SECTION_NAME,
+            SUBSECTION_NAME,
+            SUBSUBSECTION_NAME,
+            PARAGRAPH_NAME,
+        ]),
+        SUBPARAGRAPH => Some(&[
+            END_ENVIRONMENT_NAME,
+            R_CURLY,
+            PART_NAME,
+            CHAPTER_NAME,
+            SECTION_NAME,
+            SUBSECTION_NAME,
+            SUBSUBSECTION_NAME,
+            PARAGRAPH_NAME,
+            SUBPARAGRAPH_NAME,
+        ]),
+        ENUM_ITEM => Some(&[
+            END_ENVIRONMENT_NAME,
+            R_CURLY,
+            PART_NAME,
+            CHAPTER_NAME,
+            SECTION_NAME,
+            SUBSECTION_NAME,
+            SUBSUBSECTION_NAME,
+            PARAGRAPH_NAME,
+            SUBPARAGRAPH_NAME,
+            ENUM_ITEM_NAME,
+        ]),
+        _ => None,
+    }
+}
+
+/// Returns the indices of the children that have been parsed as content items.
+/// The header of sections and enumeration items is approximated conservatively.
+fn item_range(container_kind: SyntaxKind, children: &[GreenElementRef]) -> Range<usize> {
+    match container_kind {
+        ROOT => 1..children.len(),
+        PREAMBLE => 0..children.len(),
+        ENVIRONMENT => match children.last().map(|&child| kind(child)) {
+            Some(END) | Some(MISSING) => 1..children.len() - 1,
+            _ => 1..children.len(),
+        },
+        _ => {
+            let group_kind = if container_kind == ENUM_ITEM {
+                BRACK_GROUP
+            } else {
+                CURLY_GROUP
+            };
+
+            let mut start = children
+                .iter()
+                .take_while(|child| child.as_token().is_some())
+                .count();
+
+            if children.get(start).map(|&child| kind(child)) == Some(group_kind) {
+                start += 1;
+            }
+
+            start..children.len()
+        }
+    }
+}
+
+fn first_token_kind(element: GreenElementRef) -> Option<SyntaxKind> {
+    match element {
+        NodeOrToken::Node(node) => node.children().next().and_then(first_token_kind),
+        NodeOrToken::Token(_) => Some(kind(element)),
+    }
+}
+
+fn kind(element: GreenElementRef) -> SyntaxKind {
+    <super::Language as cstree::Language>::kind_from_raw(element.kind())
+}
+
+fn to_owned(element: GreenElementRef) -> NodeOrToken<GreenNode, GreenToken> {
+    match element {
+        NodeOrToken::Node(node) => NodeOrToken::Node(node.clone()),
+        NodeOrToken::Token(token) => NodeOrToken::Token(token.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::syntax::latex;
+
+    use super::*;
+
+    fn edit(old_text: &str, old: &str, new: &str) -> (String, Option<Reparse>) {
+        let options = ExperimentalOptions::default();
+        let old_parse = latex::parse(old_text, &options);
+        let new_text = old_text.replacen(old, new, 1);
+        let change = TextChange::between(old_text, &new_text).unwrap();
+        let reparse = reparse(
+            &old_parse.root,
+            &old_parse.interner,
+            change,
+            &new_text,
+            &options,
+        );
+        (new_text, reparse)
+    }
+
+    fn verify(old_text: &str, old: &str, new: &str) -> TextRange {
+        let (new_text, reparse) = edit(old_text, old, new);
+        let reparse = reparse.unwrap();
+        let expected_root = latex::parse(&new_text, &ExperimentalOptions::default()).root;
+        assert_eq!(
+            format!("{:#?}", reparse.root),
+            format!("{:#?}", expected_root)
+        );
+        reparse.new_range
+    }
+
+    #[test]
+    fn test_text_change() {
+        assert_eq!(
+            TextChange::between("foo bar baz", "foo qux baz"),
+            Some(TextChange {
+                range: TextRange::new(4.into(), 7.into()),
+                new_len: 3.into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_text_change_multi_byte() {
+        assert_eq!(
+            TextChange::between("aäb", "aöb"),
+            Some(TextChange {
+                range: TextRange::new(1.into(), 3.into()),
+                new_len: 2.into(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_text_change_equal() {
+        assert_eq!(TextChange::between("foo", "foo"), None);
+    }
+
+    #[test]
+    fn test_word() {
+        let text = "\\section{Foo}\n\\foo{bar}\nHello World\n\\baz\n";
+        let new_range = verify(text, "World", "there");
+        assert_eq!(new_range, TextRange::new(14.into(), 41.into()));
+    }
+
+    #[test]
+    fn test_command() {
+        let text = "\\begin{document}\n\\label{foo} \\ref{foo} \\cite{bar}\nfoo\n\\end{document}";
+        verify(text, "\\ref{foo}", "\\ref{foo} \\ref{baz}");
+    }
+
+    #[test]
+    fn test_new_environment() {
+        let text = "\\begin{document}\nfoo\n\\begin{itemize}\n\\item bar\n\\end{itemize}\nbaz\n\\end{document}";
+        verify(
+            text,
+            "\\item bar",
+            "\\item bar \\begin{center} qux \\end{center}",
+        );
+    }
+
+    #[test]
+    fn test_unbalanced_group() {
+        let (_, reparse) = edit(
+            "\\begin{document}\nfoo \\bar{baz} qux\n\\end{document}",
+            "\\bar{baz}",
+            "\\bar{baz",
+        );
+        assert!(reparse.is_none());
+    }
+
+    #[test]
+    fn test_new_section() {
+        let (_, reparse) = edit(
+            "\\section{Foo}\nfoo \\bar{baz} qux\n",
+            "\\bar{baz}",
+            "\\section{Bar}",
+        );
+        assert!(reparse.is_none());
+    }
+
+    #[test]
+    fn test_end_environment() {
+        let (_, reparse) = edit(
+            "\\begin{document}\nfoo \\bar{baz} qux\n\\end{document}",
+            "\\bar{baz}",
+            "\\end{document}",
+        );
+        assert!(reparse.is_none());
+    }
+
+    #[test]
+    fn test_command_argument() {
+        let text = "\\begin{document}\nfoo \\foo bar \\baz\n\\end{document}";
+        verify(text, " bar", "{bar}");
+    }
+
+    #[test]
+    fn test_exhausted_interner() {
+        let options = ExperimentalOptions::default();
+        let old_text = "\\begin{document}\nfoo bar baz\n\\end{document}";
+        let old_parse = latex::parse(old_text, &options);
+        let rodeo = old_parse.interner.rodeo();
+        for i in 0..=2 * rodeo.len() + latex::MAX_INTERNER_GROWTH {
+            rodeo.get_or_intern(format!("word{}", i));
+        }
+
+        let new_text = old_text.replacen("bar", "qux", 1);
+        let change = TextChange::between(old_text, &new_text).unwrap();
+        let reparse = reparse(
+            &old_parse.root,
+            &old_parse.interner,
+            change,
+            &new_text,
+            &options,
+        );
+        assert!(reparse.is_none());
+        assert!(!latex::parse(&new_text, &options).interner.is_exhausted());
+    }
+}
@@ -41,6 +41,10 @@ pub trait Workspace: Send + Sync {
         if let Some(language) = DocumentLanguage::by_path(&path) {
             let data = fs::read(&path)?;
             let text = String::from_utf8_lossy(&data).into_owned();
+            if let Some(document) = self.get(&uri).filter(|document| document.text == text) {
+                return Ok(Some(document));
+            }
+
             Ok(Some(self.open(
                 uri,
                 text,
@@ -81,6 +85,9 @@ pub trait Workspace: Send + Sync {
 
     fn close(&self, uri: &Uri);
 
+    /// Drops a document that has been deleted from the disk unless the client still has it open.
+    fn remove(&self, uri: &Uri);
+
     fn is_open(&self, uri: &Uri) -> bool;
 
     fn subset(&self, uri: Arc<Uri>) -> Option<WorkspaceSubset>;
@@ -84,6 +84,10 @@ impl<W: Workspace> Workspace for ChildrenExpander<W> {
         self.workspace.close(uri)
     }
 
+    fn remove(&self, uri: &Uri) {
+        self.workspace.remove(uri)
+    }
+
     fn is_open(&self, uri: &Uri) -> bool {
         self.workspace.is_open(uri)
     }
@@ -14,6 +14,7 @@ use crate::{
 #[derive(Debug, Clone)]
 pub struct LatexDocumentData {
     pub root: latex::SyntaxNode,
+    pub interner: latex::SharedInterner,
     pub extras: latex::Extras,
 }
 
@@ -87,18 +88,9 @@ impl Document {
         let line_index = LineIndex::new(&text);
         let data = match language {
             DocumentLanguage::Latex => {
-                let root = latex::parse(&text).root;
-
-                let base_uri = match &context.options.read().unwrap().root_directory {
-                    Some(root_dir) => {
-                        let root_dir = context.current_directory.join(&root_dir);
-                        Uri::from_directory_path(root_dir)
-                            .map(Arc::new)
-                            .unwrap_or_else(|()| Arc::clone(&uri))
-                    }
-                    None => Arc::clone(&uri),
-                };
-
+                let options = context.options_for(&uri);
+                let latex::Parse { root, interner } = latex::parse(&text, &options.experimental);
+                let base_uri = base_uri(&context, &uri);
                 let mut context = LatexAnalyzerContext {
                     inner: context,
                     extras: latex::Extras::default(),
@@ -108,7 +100,12 @@ impl Document {
                 latex::analyze(&mut context, &root);
                 let extras = context.extras;
 
-                LatexDocumentData { root, extras }.into()
+                LatexDocumentData {
+                    root,
+                    interner,
+                    extras,
+                }
+                .into()
             }
             DocumentLanguage::Bibtex => {
                 let root = bibtex::parse(&text).root;
@@ -125,7 +122,172 @@ impl Document {
         }
     }
 
+    /// Creates the next version of a document by reparsing only the parts of the syntax tree
+    /// that are affected by the edit. Falls back to a full parse if that is not possible.
+    pub fn reparse(context: Arc<ServerContext>, old_document: &Self, text: String) -> Self {
+        let uri = Arc::clone(&old_document.uri);
+        let old_data = match &old_document.data {
+            DocumentData::Latex(data) => data,
+            DocumentData::Bibtex(_) | DocumentData::BuildLog(_) => {
+                return Self::parse(context, uri, text, old_document.language());
+            }
+        };
+
+        let options = context.options_for(&uri);
+        let change = latex::TextChange::between(&old_document.text, &text);
+        let reparse = match change.and_then(|change| {
+            latex::reparse(
+                &old_data.root,
+                &old_data.interner,
+                change,
+                &text,
+                &options.experimental,
+            )
+        }) {
+            Some(reparse) => reparse,
+            None => return Self::parse(context, uri, text, DocumentLanguage::Latex),
+        };
+
+        let base_uri = base_uri(&context, &uri);
+        let mut analyzer_context = LatexAnalyzerContext {
+            inner: Arc::clone(&context),
+            extras: old_data.extras.clone(),
+            document_uri: Arc::clone(&uri),
+            base_uri: Arc::clone(&base_uri),
+        };
+        let extras = if latex::analyze_incrementally(
+            &mut analyzer_context,
+            &old_data.root,
+            reparse.old_range,
+            &reparse.root,
+            reparse.new_range,
+        )
+        .is_some()
+        {
+            analyzer_context.extras
+        } else {
+            let mut analyzer_context = LatexAnalyzerContext {
+                inner: context,
+                extras: latex::Extras::default(),
+                document_uri: Arc::clone(&uri),
+                base_uri,
+            };
+            latex::analyze(&mut analyzer_context, &reparse.root);
+            analyzer_context.extras
+        };
+
+        let line_index = LineIndex::new(&text);
+        Self {
+            uri,
+            text,
+            line_index,
+            data: LatexDocumentData {
+                root: reparse.root,
+                interner: old_data.interner.clone(),
+                extras,
+            }
+            .into(),
+        }
+    }
+
     pub fn language(&self) -> DocumentLanguage {
         self.data.language()
     }
 }
+
+fn base_uri(context: &ServerContext, uri: &Arc<Uri>) -> Arc<Uri> {
+    let options = context.options_for(uri);
+    match &options.root_directory {
+        Some(root_dir) => {
+            let root_dir = context.directory_for(uri).join(&root_dir);
+            Uri::from_directory_path(root_dir)
+                .map(Arc::new)
+                .unwrap_or_else(|()| Arc::clone(uri))
+        }
+        None => Arc::clone(uri),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    struct Replay {
+        context: Arc<ServerContext>,
+        document: Document,
+    }
+
+    impl Replay {
+        fn new(text: &str) -> Self {
+            let context = Arc::new(ServerContext::new(env::temp_dir()));
+            let uri = Arc::new(Uri::from_file_path(env::temp_dir().join("main.tex")).unwrap());
+            let document = Document::parse(
+                Arc::clone(&context),
+                uri,
+                text.to_string(),
+                DocumentLanguage::Latex,
+            );
+            Self { context, document }
+        }
+
+        fn edit(&mut self, new_text: String) {
+            let actual = Document::reparse(Arc::clone(&self.context), &self.document, new_text);
+            let expected = Document::parse(
+                Arc::clone(&self.context),
+                Arc::clone(&self.document.uri),
+                actual.text.clone(),
+                DocumentLanguage::Latex,
+            );
+
+            let actual_data = actual.data.as_latex().unwrap();
+            let expected_data = expected.data.as_latex().unwrap();
+            assert_eq!(
+                format!("{:#?}", actual_data.root),
+                format!("{:#?}", expected_data.root),
+                "{}",
+                actual.text
+            );
+            assert_eq!(actual_data.extras, expected_data.extras, "{}", actual.text);
+            self.document = actual;
+        }
+
+        fn insert(&mut self, anchor: &str, text: &str) {
+            let offset = self.document.text.find(anchor).unwrap() + anchor.len();
+            for (i, c) in text.char_indices() {
+                let mut new_text = self.document.text.clone();
+                new_text.insert(offset + i, c);
+                self.edit(new_text);
+            }
+        }
+
+        fn erase(&mut self, text: &str) {
+            let offset = self.document.text.find(text).unwrap();
+            for (i, _) in text.char_indices().rev() {
+                let mut new_text = self.document.text.clone();
+                new_text.remove(offset + i);
+                self.edit(new_text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reparse_edit_sequence() {
+        let mut replay = Replay::new(
+            "\\documentclass{article}\n\\newcommand{\\foo}{bar}\n\\begin{document}\n\\section{Introduction}\nHello \\foo.\n\\end{document}\n",
+        );
+        replay.insert("\\section{Introduction}\n", "\\label{sec:intro} ");
+        replay.insert("Hello \\foo.", " See \\ref{sec:intro} and \\cite{knuth}.");
+        replay.insert("\\cite{knuth}.\n", "\\input{chapter}\n");
+        replay.insert(
+            "\\input{chapter}\n",
+            "\\begin{itemize}\n\\item one \\label{item}\n\\end{itemize}\n",
+        );
+        replay.insert("\\item one", " $x^2 + \\frac{1}{2}$ { nested }");
+        replay.erase("\\label{sec:intro} ");
+        replay.erase("\\input{chapter}\n");
+        replay.erase("\\newcommand{\\foo}{bar}\n");
+        replay.insert("\\end{itemize}\n", "\\subsection{Bar}\nqux\n");
+    }
+}
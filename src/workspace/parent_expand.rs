@@ -91,6 +91,10 @@ where
         self.workspace.close(uri)
     }
 
+    fn remove(&self, uri: &Uri) {
+        self.workspace.remove(uri)
+    }
+
     fn is_open(&self, uri: &Uri) -> bool {
         self.workspace.is_open(uri)
     }
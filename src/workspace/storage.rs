@@ -41,12 +41,17 @@ impl Workspace for Storage {
         source: WorkspaceSource,
     ) -> Arc<Document> {
         log::debug!("(Re)Loading document: {}", uri);
-        let document = Arc::new(Document::parse(
-            Arc::clone(&self.context),
-            Arc::clone(&uri),
-            text,
-            language,
-        ));
+        let old_document = self.get(&uri);
+        let document = Arc::new(match old_document {
+            // Reopening a document with the same text is used to apply configuration changes,
+            // which requires a full parse.
+            Some(old_document)
+                if old_document.language() == language && old_document.text != text =>
+            {
+                Document::reparse(Arc::clone(&self.context), &old_document, text)
+            }
+            _ => Document::parse(Arc::clone(&self.context), Arc::clone(&uri), text, language),
+        });
         {
             self.documents_by_uri
                 .lock()
@@ -91,6 +96,30 @@ impl Workspace for Storage {
         self.opened_documents.lock().unwrap().remove(uri);
     }
 
+    fn remove(&self, uri: &Uri) {
+        if self.is_open(uri) {
+            return;
+        }
+
+        log::debug!("Removing document: {}", uri);
+        if self.documents_by_uri.lock().unwrap().remove(uri).is_none() {
+            return;
+        }
+
+        // The documents that link to the removed one need to be analyzed again.
+        let handlers = { self.open_handlers.lock().unwrap().clone() };
+        for document in self.documents().into_iter().filter(|document| {
+            all_link_targets(document)
+                .into_iter()
+                .flatten()
+                .any(|target| target.as_ref() == uri)
+        }) {
+            for handler in &handlers {
+                handler(Arc::new(self.clone()), Arc::clone(&document));
+            }
+        }
+    }
+
     fn is_open(&self, uri: &Uri) -> bool {
         self.opened_documents.lock().unwrap().contains(uri)
     }
@@ -106,18 +135,8 @@ impl Workspace for Storage {
 
         let mut edges = Vec::new();
         for (i, uri) in all_current_uris.iter().enumerate() {
-            let document = self.get(uri);
-            if let Some(data) = document
-                .as_ref()
-                .and_then(|document| document.data.as_latex())
-            {
-                let extras = &data.extras;
-                let mut all_targets = vec![&extras.implicit_links.aux, &extras.implicit_links.log];
-                for link in &extras.explicit_links {
-                    all_targets.push(&link.targets);
-                }
-
-                for targets in all_targets {
+            if let Some(document) = self.get(uri) {
+                for targets in all_link_targets(&document) {
                     for target in targets {
                         if let Some(j) = all_current_uris.iter().position(|uri| uri == target) {
                             edges.push((i, j, ()));
@@ -143,3 +162,106 @@ impl Workspace for Storage {
         Ok(())
     }
 }
+
+fn all_link_targets(document: &Document) -> Vec<&Vec<Arc<Uri>>> {
+    let mut all_targets = Vec::new();
+    if let Some(data) = document.data.as_latex() {
+        let extras = &data.extras;
+        all_targets.push(&extras.implicit_links.aux);
+        all_targets.push(&extras.implicit_links.log);
+        for link in &extras.explicit_links {
+            all_targets.push(&link.targets);
+        }
+    }
+    all_targets
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+
+    #[test]
+    fn test_open_existing_document() {
+        let context = Arc::new(ServerContext::new(env::temp_dir()));
+        let storage = Storage::new(Arc::clone(&context));
+        let uri = Arc::new(Uri::from_file_path(env::temp_dir().join("main.tex")).unwrap());
+        storage.open(
+            Arc::clone(&uri),
+            "\\begin{document}\nfoo \\label{foo}\nbar\n\\end{document}".into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Client,
+        );
+
+        let new_text = "\\begin{document}\nfoo \\label{foo} \\ref{foo}\nbar\n\\end{document}";
+        let actual = storage.open(
+            Arc::clone(&uri),
+            new_text.into(),
+            DocumentLanguage::Latex,
+            WorkspaceSource::Client,
+        );
+        let expected = Document::parse(context, uri, new_text.into(), DocumentLanguage::Latex);
+
+        assert_eq!(actual.text, expected.text);
+        let actual = actual.data.as_latex().unwrap();
+        let expected = expected.data.as_latex().unwrap();
+        assert_eq!(
+            format!("{:#?}", actual.root),
+            format!("{:#?}", expected.root)
+        );
+        assert_eq!(actual.extras, expected.extras);
+    }
+
+    fn open(storage: &Storage, name: &str, text: &str, source: WorkspaceSource) -> Arc<Uri> {
+        let uri = Arc::new(Uri::from_file_path(env::temp_dir().join(name)).unwrap());
+        storage.open(
+            Arc::clone(&uri),
+            text.into(),
+            DocumentLanguage::Latex,
+            source,
+        );
+        uri
+    }
+
+    #[test]
+    fn test_remove_document() {
+        let storage = Storage::new(Arc::new(ServerContext::new(env::temp_dir())));
+        let uri = open(&storage, "main.tex", "foo", WorkspaceSource::Server);
+
+        storage.remove(&uri);
+        assert!(!storage.has(&uri));
+    }
+
+    #[test]
+    fn test_remove_open_document() {
+        let storage = Storage::new(Arc::new(ServerContext::new(env::temp_dir())));
+        let uri = open(&storage, "main.tex", "foo", WorkspaceSource::Client);
+
+        storage.remove(&uri);
+        assert!(storage.has(&uri));
+    }
+
+    #[test]
+    fn test_remove_reanalyzes_parents() {
+        let storage = Storage::new(Arc::new(ServerContext::new(env::temp_dir())));
+        let main_uri = open(
+            &storage,
+            "main.tex",
+            "\\input{chapter}",
+            WorkspaceSource::Client,
+        );
+        let chapter_uri = open(&storage, "chapter.tex", "foo", WorkspaceSource::Server);
+        open(&storage, "other.tex", "bar", WorkspaceSource::Client);
+
+        let reopened_uris = Arc::new(Mutex::new(Vec::new()));
+        let handler_uris = Arc::clone(&reopened_uris);
+        storage.register_open_handler(Arc::new(move |_, document| {
+            handler_uris.lock().unwrap().push(Arc::clone(&document.uri));
+        }));
+
+        storage.remove(&chapter_uri);
+        assert!(!storage.has(&chapter_uri));
+        assert_eq!(*reopened_uris.lock().unwrap(), vec![main_uri]);
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
     path::PathBuf,
     sync::{Arc, Mutex},
+    thread,
 };
 
 use anyhow::Result;
@@ -14,8 +15,8 @@ use crate::{
 
 pub struct DocumentWatcher<W> {
     workspace: Arc<W>,
-    watcher: Mutex<RecommendedWatcher>,
-    watched_paths: Mutex<FxHashSet<PathBuf>>,
+    watcher: Arc<Mutex<RecommendedWatcher>>,
+    watched_paths: Arc<Mutex<FxHashSet<PathBuf>>>,
 }
 
 impl<W> DocumentWatcher<W>
@@ -23,20 +24,58 @@ where
     W: Workspace + Send + Sync + 'static,
 {
     pub fn new(workspace: Arc<W>) -> Result<Self> {
-        let watcher = Self::create_watcher(Arc::clone(&workspace))?;
+        let watcher = Arc::new(Mutex::new(Self::create_watcher(Arc::clone(&workspace))?));
+        let watched_paths: Arc<Mutex<FxHashSet<PathBuf>>> = Arc::default();
+
+        // Documents loaded by the server do not pass through this layer,
+        // so the folders are watched whenever the underlying workspace opens a document.
+        {
+            let watcher = Arc::clone(&watcher);
+            let watched_paths = Arc::clone(&watched_paths);
+            workspace.register_open_handler(Arc::new(move |_, document| {
+                if document.uri.scheme() == "file" {
+                    if let Ok(mut path) = document.uri.to_file_path() {
+                        path.pop();
+                        if let Err(why) =
+                            watch(&watcher, &watched_paths, path, RecursiveMode::NonRecursive)
+                        {
+                            warn!(
+                                "Failed to watch folder of document \"{}\": {}",
+                                document.uri, why
+                            );
+                        }
+                    }
+                }
+            }));
+        }
+
         Ok(Self {
             workspace,
-            watcher: Mutex::new(watcher),
-            watched_paths: Mutex::default(),
+            watcher,
+            watched_paths,
         })
     }
 
     fn create_watcher(workspace: Arc<W>) -> Result<RecommendedWatcher> {
+        // The callback runs on the event loop of the watcher, which has to be free
+        // when the reloaded documents cause new folders to be watched.
+        let (sender, receiver) = crossbeam_channel::unbounded::<PathBuf>();
+        thread::spawn(move || {
+            for path in receiver {
+                // Renaming a file emits an event for the old path as well.
+                if path.exists() {
+                    let _ = workspace.reload(path);
+                } else if let Ok(uri) = Uri::from_file_path(path) {
+                    workspace.remove(&uri);
+                }
+            }
+        });
+
         let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
             if let Ok(event) = event {
-                if event.kind.is_modify() {
+                if event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove() {
                     for path in event.paths {
-                        let _ = workspace.reload(path);
+                        let _ = sender.send(path);
                     }
                 }
             }
@@ -53,19 +92,7 @@ impl<W: Workspace> Workspace for DocumentWatcher<W> {
         language: DocumentLanguage,
         source: WorkspaceSource,
     ) -> Arc<Document> {
-        let document = self.workspace.open(uri, text, language, source);
-        if document.uri.scheme() == "file" {
-            if let Ok(mut path) = document.uri.to_file_path() {
-                path.pop();
-                if let Err(why) = self.watch(path, RecursiveMode::NonRecursive) {
-                    warn!(
-                        "Failed to watch folder of document \"{}\": {}",
-                        document.uri, why
-                    );
-                }
-            }
-        }
-        document
+        self.workspace.open(uri, text, language, source)
     }
 
     fn register_open_handler(&self, handler: OpenHandler) {
@@ -88,6 +115,10 @@ impl<W: Workspace> Workspace for DocumentWatcher<W> {
         self.workspace.close(uri)
     }
 
+    fn remove(&self, uri: &Uri) {
+        self.workspace.remove(uri)
+    }
+
     fn is_open(&self, uri: &Uri) -> bool {
         self.workspace.is_open(uri)
     }
@@ -97,12 +128,21 @@ impl<W: Workspace> Workspace for DocumentWatcher<W> {
     }
 
     fn watch(&self, path: PathBuf, mode: RecursiveMode) -> Result<()> {
-        let mut watched_paths = self.watched_paths.lock().unwrap();
-        if !watched_paths.contains(&path) {
-            self.watcher.lock().unwrap().watch(&path, mode)?;
-            watched_paths.insert(path);
-        }
+        watch(&self.watcher, &self.watched_paths, path, mode)
+    }
+}
 
-        Ok(())
+fn watch(
+    watcher: &Mutex<RecommendedWatcher>,
+    watched_paths: &Mutex<FxHashSet<PathBuf>>,
+    path: PathBuf,
+    mode: RecursiveMode,
+) -> Result<()> {
+    let mut watched_paths = watched_paths.lock().unwrap();
+    if !watched_paths.contains(&path) {
+        watcher.lock().unwrap().watch(&path, mode)?;
+        watched_paths.insert(path);
     }
+
+    Ok(())
 }